@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use todos::matcher::Matcher;
+use todos::scan::{scan_string, scan_string_with};
+
+/// A large source file: mostly ordinary code, with a TODO every few lines and
+/// plenty of lines mentioning "todo" without being entries
+fn corpus() -> String {
+    let lines = [
+        "fn process(items: &[Item]) -> Result<Vec<Output>, Error> {",
+        "    // TODO: handle empty input",
+        "    let mut outputs = Vec::with_capacity(items.len());",
+        "    for item in items.iter().filter(|item| item.enabled) {",
+        "        // todo0 validate the item before processing it",
+        "        outputs.push(transform(item)?);",
+        "    }",
+        "    let todos = outputs.iter().filter(|o| o.is_todo()).count(); // not an entry",
+        "    // todo@perf avoid the second pass",
+        "    /* Ünïcödé comment ñ with TODO(alice): translate */",
+        "    Ok(outputs)",
+        "}",
+    ];
+
+    lines.iter().cycle().take(120_000).map(|line| format!("{}\n", line)).collect()
+}
+
+fn scan(c: &mut Criterion) {
+    let corpus = corpus();
+
+    let mut group = c.benchmark_group("scan_string");
+    group.throughput(Throughput::Bytes(corpus.len() as u64));
+    group.bench_function("120k lines", |b| b.iter(|| {
+        let mut entries = vec![];
+        scan_string(black_box(corpus.clone()), PathBuf::from("src/lib.rs"), &mut entries);
+        entries
+    }));
+    // Extra keywords are matched in the same pass, so they shouldn't cost much
+    let matcher = Matcher::new(&["fixme", "hack", "xxx", "bug"]).unwrap();
+    group.bench_function("120k lines, 5 keywords", |b| b.iter(|| {
+        let mut entries = vec![];
+        scan_string_with(black_box(corpus.clone()), PathBuf::from("src/lib.rs"), &matcher, &mut entries);
+        entries
+    }));
+
+    group.finish();
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
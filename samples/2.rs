@@ -1,7 +1,9 @@
 fn foo() {
-    // Rust TODOs can only be generic
+    // Macros are their own kind, never categories or priorities
     todo!("generic");
     todo!();
     todo!("@foo not category");
     todo!("00 not priority");
+    unimplemented!("@foo");
+    UNIMPLEMENTED!(not a macro);
 }
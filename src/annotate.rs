@@ -0,0 +1,137 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+use unicode_width::UnicodeWidthStr;
+
+use crate::config::Config;
+use crate::entries::{Entry, EntryData};
+use crate::paths::resolve;
+use crate::render::{priority_notation, write_ansi};
+use crate::scan::read_text;
+use crate::scanners::Scanners;
+
+/// A line of an annotated file. `entry` is set for lines with a TODO.
+pub struct AnnotatedLine<'a> {
+    pub number: usize,
+    pub text: &'a str,
+    pub entry: Option<&'a Entry>,
+}
+
+impl Entry {
+    /// A short label for the entry's kind, e.g. `todo0` or `@testing`
+    pub fn badge(&self) -> String {
+        let badge = match &self.data {
+            EntryData::Priority(priority) => priority_notation(*priority),
+            EntryData::Category(category) => format!("@{}", category),
+            EntryData::Generic => "todo".to_string(),
+            EntryData::Someday => "todo?".to_string(),
+            EntryData::Unimplemented => "todo!".to_string(),
+            EntryData::Marker => "marker".to_string(),
+        };
+
+        // e.g. `FIXME0` or `FIXME@ui`
+        match &self.keyword {
+            Some(keyword) if matches!(self.data, EntryData::Category(_)) => format!("{}{}", keyword.to_uppercase(), badge),
+            Some(keyword) => badge.replacen("todo", &keyword.to_uppercase(), 1),
+            None => badge,
+        }
+    }
+}
+
+/// Pair each line of `content` with the entry found on it, if any
+pub fn annotate<'a>(content: &'a str, entries: &'a [Entry]) -> Vec<AnnotatedLine<'a>> {
+    content.lines().enumerate().map(|(index, text)| AnnotatedLine {
+        number: index + 1,
+        text,
+        entry: entries.iter().find(|entry| entry.location.line == index + 1),
+    }).collect()
+}
+
+/// Read `file` (relative to `root`) and scan it with the scanner a full scan would use. The
+/// path is resolved first, so todo.md and the readme are recognized as `./todo.md` too.
+pub fn scan_annotated(root: &Path, file: &Path, scanners: &Scanners) -> io::Result<(String, Vec<Entry>)> {
+    let path = resolve(root, file);
+    let (content, _) = read_text(&path)?;
+    let mut entries: Vec<Entry> = vec![];
+
+    scanners.scan(content.clone(), &path, &mut entries);
+
+    Ok((content, entries))
+}
+
+/// Print a file with its TODO lines highlighted and their badges in the gutter, scanned by the
+/// same `scanners` as in a full scan
+pub fn render_annotated(root: &Path, file: &Path, scanners: &Scanners, config: &Config) -> io::Result<()> {
+    let (content, entries) = scan_annotated(root, file, scanners)?;
+
+    let lines = annotate(&content, &entries);
+    let number_width = lines.len().to_string().len();
+    let badge_width = entries.iter().map(|entry| entry.badge().width()).max().unwrap_or(0);
+
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    for line in lines {
+        write_ansi(&mut stdout, Color::Ansi256(243), &format!("{:>width$} │ ", line.number, width = number_width), false);
+
+        match line.entry {
+            Some(entry) => {
+                let badge = entry.badge();
+
+                write_ansi(&mut stdout, entry.color(config), &badge, true);
+                write!(stdout, "{} ", " ".repeat(badge_width - badge.width()))?;
+                write_ansi(&mut stdout, Color::Yellow, line.text, true);
+            },
+            None => write!(stdout, "{} {}", " ".repeat(badge_width), line.text)?,
+        }
+
+        writeln!(stdout)?;
+    }
+
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+    use std::path::PathBuf;
+    use std::fs;
+    use crate::entries::SourceKind;
+    use crate::matcher::Matcher;
+    use crate::scan::scan_string;
+    use crate::scanners::TodoFile;
+
+    #[test]
+    fn annotate_test() {
+        let content = "fn main() {\n    // todo0 foo\n    // todo@bar baz\n}\n";
+        let mut entries: Vec<Entry> = vec![];
+        scan_string(content.to_string(), PathBuf::from("main.rs"), &mut entries);
+
+        let lines = annotate(content, &entries);
+
+        assert_eq!(4, lines.len());
+        assert!(lines[0].entry.is_none());
+        assert_eq!("todo0", lines[1].entry.unwrap().badge());
+        assert_eq!("@bar", lines[2].entry.unwrap().badge());
+        assert_eq!("    // todo@bar baz", lines[2].text);
+        assert!(lines[3].entry.is_none());
+    }
+
+    #[test]
+    fn scan_annotated_test() {
+        let dir = TempDir::new("annotate");
+        fs::write(dir.join("todo.md"), "- foo\n").unwrap();
+
+        let mut scanners = Scanners::new(Matcher::todo().clone());
+        scanners.register(TodoFile { path: dir.join("todo.md") });
+
+        for file in ["todo.md", "./todo.md", "src/../todo.md"] {
+            let (_, entries) = scan_annotated(&dir, Path::new(file), &scanners).unwrap();
+
+            assert_eq!(1, entries.len());
+            assert_eq!(SourceKind::TodoFile, entries[0].source);
+            assert_eq!(dir.join("todo.md").as_path(), &*entries[0].location.file);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+use tracing::debug;
+
+use crate::entries::Entry;
+use crate::git::{BlameLine, blame};
+use crate::render::write_ansi;
+use crate::schema::print_json;
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// The entries an author introduced, according to `git blame`
+#[derive(Serialize, JsonSchema, Debug, PartialEq)]
+pub struct AuthorStats {
+    pub author: String,
+    pub entries: usize,
+    pub average_age_days: u64,
+}
+
+/// Blame the files containing entries. Files git can't blame (e.g. untracked ones) are skipped.
+pub fn blame_files(dir: &Path, entries: &[Entry]) -> HashMap<Arc<Path>, Vec<BlameLine>> {
+    let mut blames: HashMap<Arc<Path>, Vec<BlameLine>> = HashMap::new();
+
+    for entry in entries {
+        if blames.contains_key(&entry.location.file) {
+            continue;
+        }
+
+        match blame(dir, &entry.location.file) {
+            Ok(lines) => blames.insert(entry.location.file.clone(), lines),
+            Err(err) => {
+                debug!(file = %entry.location.file.to_string_lossy(), error = %err, "Failed to blame");
+                blames.insert(entry.location.file.clone(), vec![])
+            },
+        };
+    }
+
+    blames
+}
+
+/// Entries introduced per author with their average age at `now` (a Unix timestamp), most
+/// entries first. Entries on uncommitted lines aren't attributed to anyone.
+pub fn author_stats(entries: &[Entry], blames: &HashMap<Arc<Path>, Vec<BlameLine>>, now: i64) -> Vec<AuthorStats> {
+    let mut ages: HashMap<&str, Vec<i64>> = HashMap::new();
+
+    for entry in entries {
+        let blame = blames.get(&entry.location.file)
+            .and_then(|lines| lines.iter().find(|line| line.line == entry.location.line));
+
+        if let Some(blame) = blame {
+            ages.entry(&blame.author).or_default().push((now - blame.time).max(0));
+        }
+    }
+
+    let mut stats: Vec<AuthorStats> = ages.into_iter().map(|(author, ages)| AuthorStats {
+        author: author.to_string(),
+        entries: ages.len(),
+        average_age_days: (ages.iter().sum::<i64>() / ages.len() as i64 / DAY) as u64,
+    }).collect();
+
+    stats.sort_by(|a, b| b.entries.cmp(&a.entries).then_with(|| a.author.cmp(&b.author)));
+
+    stats
+}
+
+/// Mark the entries last changed more than `max_age_days` before `now` (a Unix timestamp)
+/// as stale. Entries on uncommitted lines are never stale.
+pub fn mark_stale(entries: &mut [Entry], blames: &HashMap<Arc<Path>, Vec<BlameLine>>, now: i64, max_age_days: u64) {
+    for entry in entries {
+        let blame = blames.get(&entry.location.file)
+            .and_then(|lines| lines.iter().find(|line| line.line == entry.location.line));
+
+        entry.stale = blame.is_some_and(|blame| (now - blame.time) / DAY > max_age_days as i64);
+    }
+}
+
+pub fn render_authors(stats: &[AuthorStats]) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, "# Authors", true);
+    write!(stdout, "\n\n").unwrap();
+
+    for author in stats {
+        write_ansi(&mut stdout, Color::Ansi256(243), "- ", false);
+        write_ansi(&mut stdout, Color::Green, &author.author, true);
+        writeln!(stdout, " ({} TODOs, {} days old on average)", author.entries, author.average_age_days).unwrap();
+    }
+
+    stdout.flush().unwrap();
+}
+
+/// The shape of `authors --format json` output
+#[derive(Serialize, JsonSchema)]
+pub struct AuthorsReport {
+    pub authors: Vec<AuthorStats>,
+}
+
+pub fn render_authors_json(authors: Vec<AuthorStats>) {
+    print_json(AuthorsReport { authors });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::entry;
+    use crate::entries::EntryData;
+
+    #[test]
+    fn mark_stale_test() {
+        let blames = HashMap::from([
+            (Path::new("a.rs").into(), vec![
+                BlameLine { line: 1, author: "Alice".to_string(), time: 0 },
+                BlameLine { line: 2, author: "Bob".to_string(), time: 90 * DAY },
+            ]),
+        ]);
+
+        let mut entries = vec![entry("a.rs", 1, EntryData::Generic, "foo"), entry("a.rs", 2, EntryData::Generic, "foo"), entry("a.rs", 3, EntryData::Generic, "foo"), entry("todo.md", 1, EntryData::Generic, "foo")];
+        mark_stale(&mut entries, &blames, 200 * DAY, 180);
+
+        assert_eq!(vec![true, false, false, false], entries.iter().map(|entry| entry.stale).collect::<Vec<bool>>());
+    }
+
+    #[test]
+    fn author_stats_test() {
+        let blame = |line: usize, author: &str, days_ago: i64| BlameLine { line, author: author.to_string(), time: 100 * DAY - days_ago * DAY };
+
+        let blames = HashMap::from([
+            (Path::new("a.rs").into(), vec![blame(1, "Alice", 10), blame(2, "Bob", 3), blame(5, "Alice", 20)]),
+            (Path::new("b.rs").into(), vec![blame(1, "Bob", 1)]),
+            (Path::new("todo.md").into(), vec![]),
+        ]);
+
+        let entries = vec![entry("a.rs", 1, EntryData::Generic, "foo"), entry("a.rs", 2, EntryData::Generic, "foo"), entry("a.rs", 5, EntryData::Generic, "foo"), entry("b.rs", 1, EntryData::Generic, "foo"), entry("todo.md", 3, EntryData::Generic, "foo")];
+
+        assert_eq!(vec![
+            AuthorStats { author: "Alice".to_string(), entries: 2, average_age_days: 15 },
+            AuthorStats { author: "Bob".to_string(), entries: 2, average_age_days: 2 },
+        ], author_stats(&entries, &blames, 100 * DAY));
+    }
+}
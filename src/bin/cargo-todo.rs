@@ -0,0 +1,29 @@
+//! Entrypoint for `cargo todo`. Cargo runs this as `cargo-todo todo [args]`,
+//! which gets forwarded to `todos --cargo [args]`.
+
+use std::process::{exit, Command};
+
+fn main() {
+    let mut args = std::env::args_os().skip(1).peekable();
+
+    if args.peek().is_some_and(|arg| arg == "todo") {
+        args.next();
+    }
+
+    // Prefer the `todos` binary installed alongside this one
+    let mut todos = std::env::current_exe().unwrap();
+    todos.set_file_name(format!("todos{}", std::env::consts::EXE_SUFFIX));
+
+    let program = if todos.exists() { todos.into_os_string() } else { "todos".into() };
+
+    let status = Command::new(program)
+        .arg("--cargo")
+        .args(args)
+        .status()
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to run todos: {}", err);
+            exit(1);
+        });
+
+    exit(status.code().unwrap_or(1));
+}
@@ -0,0 +1,284 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tracing::{error, info, warn};
+
+use crate::diff::{Diff, PR_COMMENT_MARKER, diff, pr_comment};
+use crate::entries::EntryData;
+use crate::git;
+use crate::template::Template;
+
+/// How the bot talks to GitHub, and where it keeps its clones
+pub struct BotConfig {
+    /// A token that can read the repositories, and write comments and commit statuses
+    pub token: String,
+    /// The webhook secret, for verifying the payloads' `X-Hub-Signature-256`
+    pub secret: String,
+    /// Where the repositories are fetched, one directory per repository
+    pub work_dir: PathBuf,
+    /// e.g. `https://api.github.com`, or `https://github.example.com/api/v3` for GitHub Enterprise
+    pub api_url: String,
+}
+
+/// A pull request to review, from a `pull_request` webhook payload
+#[derive(Debug, PartialEq)]
+pub struct PullRequest {
+    /// e.g. `org/repo`
+    pub repository: String,
+    pub clone_url: String,
+    /// e.g. `https://github.com/org/repo`
+    pub html_url: String,
+    pub number: u64,
+    pub head_sha: String,
+    /// The branch the pull request targets
+    pub base_ref: String,
+}
+
+/// The outcome of reviewing a pull request
+pub struct Review {
+    pub diff: Diff,
+    /// The markdown comment, see [`pr_comment`]
+    pub comment: String,
+    /// Priority TODOs, `todo!()` calls, and code markers added by the pull request. Like in
+    /// `todos hook check`, they fail the commit status.
+    pub blocking: usize,
+}
+
+/// HMAC-SHA256 of `message`, see RFC 2104
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut key = match key.len() > BLOCK_SIZE {
+        true => Sha256::digest(key).to_vec(),
+        false => key.to_vec(),
+    };
+    key.resize(BLOCK_SIZE, 0);
+
+    let pad = |byte: u8| key.iter().map(|key| key ^ byte).collect::<Vec<u8>>();
+    let inner = Sha256::new().chain_update(pad(0x36)).chain_update(message).finalize();
+
+    Sha256::new().chain_update(pad(0x5c)).chain_update(inner).finalize().to_vec()
+}
+
+/// Whether `signature` (the `X-Hub-Signature-256` header, `sha256=<hex>`) signs `payload` with `secret`
+pub fn verify_signature(secret: &str, payload: &[u8], signature: &str) -> bool {
+    let Some(signature) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+
+    let expected: String = hmac_sha256(secret.as_bytes(), payload).iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    // Compared in constant time, so that the signature can't be guessed byte by byte
+    expected.len() == signature.len() && expected.bytes().zip(signature.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// The pull request a webhook is about, or `None` for events that don't change its code
+pub fn parse_event(event: &str, payload: &[u8]) -> Result<Option<PullRequest>, Box<dyn Error>> {
+    if event != "pull_request" {
+        return Ok(None);
+    }
+
+    let payload: Value = serde_json::from_slice(payload)?;
+
+    if ! matches!(payload["action"].as_str(), Some("opened" | "reopened" | "synchronize")) {
+        return Ok(None);
+    }
+
+    let field = |path: &[&str]| -> Result<String, String> {
+        path.iter().fold(&payload, |value, key| &value[key])
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("missing {} in the payload", path.join(".")))
+    };
+
+    Ok(Some(PullRequest {
+        repository: field(&["repository", "full_name"])?,
+        clone_url: field(&["repository", "clone_url"])?,
+        html_url: field(&["repository", "html_url"])?,
+        number: payload["number"].as_u64().ok_or("missing number in the payload")?,
+        head_sha: field(&["pull_request", "head", "sha"])?,
+        base_ref: field(&["pull_request", "base", "ref"])?,
+    }))
+}
+
+/// Fetch the pull request and compare its TODOs with the ones at the point where it branched
+/// off, like `todos pr-comment`. Nothing is checked out, the files are read from git.
+pub fn review(pr: &PullRequest, config: &BotConfig) -> Result<Review, Box<dyn Error>> {
+    let dir = config.work_dir.join(pr.repository.replace('/', "__"));
+
+    if ! dir.join(".git").exists() {
+        fs::create_dir_all(&dir)?;
+        git::git(&dir, &["init", "--quiet"])?;
+    }
+
+    // The token is only used for the fetch, so it isn't stored in the repository's config
+    let url = pr.clone_url.replacen("https://", &format!("https://x-access-token:{}@", config.token), 1);
+    let head = format!("+refs/pull/{}/head:refs/todos/pull/{}", pr.number, pr.number);
+    let base = format!("+refs/heads/{}:refs/todos/base/{}", pr.base_ref, pr.base_ref);
+
+    git::git(&dir, &["fetch", "--quiet", "--no-tags", &url, &head, &base])
+        .map_err(|err| err.to_string().replace(&config.token, "***"))?;
+
+    let root = git::toplevel(&dir)?;
+    let merge_base = git::merge_base(&root, &pr.head_sha, &format!("refs/todos/base/{}", pr.base_ref))?;
+
+    let repo_config = git::ref_config(&root, &pr.head_sha)?;
+    let scan = |git_ref: &str| git::scan_repository(&root, git_ref, &repo_config);
+
+    let diff = diff(scan(&merge_base)?, scan(&pr.head_sha)?, repo_config.diff.similarity);
+
+    let mut comment_config = repo_config;
+    let url = format!("{}/blob/{}/{{file}}#L{{line}}", pr.html_url.replace('{', "%7B").replace('}', "%7D"), pr.head_sha);
+    comment_config.locations.url = Some(Template::parse(&url).unwrap());
+
+    let blocking = diff.added.iter()
+        .filter(|entry| matches!(entry.data, EntryData::Priority(_) | EntryData::Unimplemented | EntryData::Marker))
+        .count();
+
+    Ok(Review { comment: pr_comment(&diff, &comment_config), blocking, diff })
+}
+
+fn api(config: &BotConfig, method: &str, path: &str) -> ureq::Request {
+    ureq::request(method, &format!("{}{}", config.api_url.trim_end_matches('/'), path))
+        .set("User-Agent", "todos")
+        .set("Accept", "application/vnd.github+json")
+        .set("Authorization", &format!("Bearer {}", config.token))
+}
+
+/// Post the review as the pull request's TODO comment, editing the previous one if there is
+/// one, and set the `todos` commit status on its head
+pub fn publish(pr: &PullRequest, review: &Review, config: &BotConfig) -> Result<(), Box<dyn Error>> {
+    let comments: Vec<Value> = api(config, "GET", &format!("/repos/{}/issues/{}/comments?per_page=100", pr.repository, pr.number))
+        .call()?
+        .into_json()?;
+
+    let previous = comments.iter()
+        .find(|comment| comment["body"].as_str().is_some_and(|body| body.starts_with(PR_COMMENT_MARKER)))
+        .and_then(|comment| comment["id"].as_u64());
+
+    let body = json!({ "body": review.comment });
+
+    match previous {
+        Some(id) => api(config, "PATCH", &format!("/repos/{}/issues/comments/{}", pr.repository, id)).send_json(body)?,
+        None => api(config, "POST", &format!("/repos/{}/issues/{}/comments", pr.repository, pr.number)).send_json(body)?,
+    };
+
+    let (state, description) = match review.blocking {
+        0 => ("success", review.diff.summary()),
+        count => ("failure", format!("Adds {} priority TODOs or code markers", count)),
+    };
+
+    api(config, "POST", &format!("/repos/{}/statuses/{}", pr.repository, pr.head_sha))
+        .send_json(json!({ "state": state, "description": description, "context": "todos" }))?;
+
+    Ok(())
+}
+
+/// Handle a webhook delivery: verify it, then review the pull request it's about and publish
+/// the review. Returns the pull request, or `None` if the event was ignored. For running the
+/// bot inside another server; [`serve`] runs a standalone one.
+pub fn handle(event: &str, signature: &str, payload: &[u8], config: &BotConfig) -> Result<Option<PullRequest>, Box<dyn Error>> {
+    if ! verify_signature(&config.secret, payload, signature) {
+        return Err("invalid signature".into());
+    }
+
+    let Some(pr) = parse_event(event, payload)? else {
+        return Ok(None);
+    };
+
+    publish(&pr, &review(&pr, config)?, config)?;
+
+    Ok(Some(pr))
+}
+
+/// Listen for webhooks on `address` (e.g. `0.0.0.0:3000`), handling one delivery at a time.
+/// Deliveries are acknowledged before they're handled, since GitHub only waits 10 seconds.
+pub fn serve(address: &str, config: &BotConfig) -> Result<(), Box<dyn Error>> {
+    let server = tiny_http::Server::http(address).map_err(|err| err.to_string())?;
+
+    info!(address, "Listening for webhooks");
+
+    for mut request in server.incoming_requests() {
+        let header = |name: &'static str| request.headers().iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.to_string())
+            .unwrap_or_default();
+
+        let event = header("X-GitHub-Event");
+        let signature = header("X-Hub-Signature-256");
+
+        let mut payload: Vec<u8> = vec![];
+        let read = request.as_reader().read_to_end(&mut payload);
+
+        let (status, pr) = if *request.method() != tiny_http::Method::Post || read.is_err() {
+            (400, None)
+        } else if ! verify_signature(&config.secret, &payload, &signature) {
+            warn!(event, "Rejected a webhook with an invalid signature");
+            (401, None)
+        } else {
+            match parse_event(&event, &payload) {
+                Ok(pr) => (if pr.is_some() { 202 } else { 204 }, pr),
+                Err(err) => {
+                    warn!(event, error = %err, "Failed to parse a webhook");
+                    (400, None)
+                },
+            }
+        };
+
+        let _ = request.respond(tiny_http::Response::empty(status));
+
+        let Some(pr) = pr else {
+            continue;
+        };
+
+        info!(repository = pr.repository, number = pr.number, "Reviewing pull request");
+
+        if let Err(err) = review(&pr, config).and_then(|review| publish(&pr, &review, config)) {
+            error!(repository = pr.repository, number = pr.number, error = %err, "Failed to review pull request");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_test() {
+        // RFC 4231, test case 2
+        let hex: String = hmac_sha256(b"Jefe", b"what do ya want for nothing?").iter().map(|byte| format!("{:02x}", byte)).collect();
+        assert_eq!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843", hex);
+
+        assert!(verify_signature("Jefe", b"what do ya want for nothing?", "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"));
+        assert!(! verify_signature("Jefe", b"what do ya want for nothing!", "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"));
+        assert!(! verify_signature("Jefe", b"what do ya want for nothing?", "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"));
+    }
+
+    #[test]
+    fn parse_event_test() {
+        let payload = |action: &str| format!(r#"{{
+            "action": "{}",
+            "number": 42,
+            "pull_request": {{ "head": {{ "sha": "abc" }}, "base": {{ "ref": "main" }} }},
+            "repository": {{ "full_name": "org/repo", "clone_url": "https://github.com/org/repo.git", "html_url": "https://github.com/org/repo" }}
+        }}"#, action);
+
+        assert_eq!(Some(PullRequest {
+            repository: "org/repo".to_string(),
+            clone_url: "https://github.com/org/repo.git".to_string(),
+            html_url: "https://github.com/org/repo".to_string(),
+            number: 42,
+            head_sha: "abc".to_string(),
+            base_ref: "main".to_string(),
+        }), parse_event("pull_request", payload("synchronize").as_bytes()).unwrap());
+
+        assert_eq!(None, parse_event("pull_request", payload("closed").as_bytes()).unwrap());
+        assert_eq!(None, parse_event("push", payload("opened").as_bytes()).unwrap());
+        assert!(parse_event("pull_request", br#"{"action": "opened"}"#).is_err());
+    }
+}
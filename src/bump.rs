@@ -0,0 +1,126 @@
+use std::path::{Path, PathBuf};
+
+use crate::entries::{Entry, EntryData, stable_ids};
+use crate::matcher::Matcher;
+use crate::render::priority_notation;
+use crate::rename::RenamedLine;
+use crate::scan::read_text;
+
+/// The least urgent priority that can be written, todo9
+const LOWEST_PRIORITY: isize = 9;
+
+/// The entry `target` refers to: `file:line` (with `file` relative to `current_dir`), or its
+/// stable ID, as recorded by `--db`. IDs can be shortened, as long as they're unique.
+pub fn find<'a>(entries: &'a [Entry], target: &str, root: &Path, current_dir: &Path) -> Result<&'a Entry, String> {
+    if let Some((file, line)) = target.rsplit_once(':').and_then(|(file, line)| Some((file, line.parse::<usize>().ok()?))) {
+        let file = crate::paths::normalize(current_dir.join(file));
+
+        return entries.iter()
+            .find(|entry| std::iter::once(&entry.location).chain(&entry.also).any(|location| *location.file == *file && location.line == line))
+            .ok_or_else(|| format!("no TODO at {}", target));
+    }
+
+    let ids = stable_ids(entries, root);
+    let mut matches = entries.iter().zip(&ids).filter(|(_, id)| id.starts_with(&target.to_lowercase()));
+
+    match (matches.next(), matches.next()) {
+        (Some((entry, _)), None) => Ok(entry),
+        (Some(_), Some(_)) => Err(format!("more than one TODO has an ID starting with {}", target)),
+        (None, _) => Err(format!("no TODO with the ID {}", target)),
+    }
+}
+
+/// The lines changing the priority of `entry` by `by` levels, e.g. from todo1 to todo0 for 1
+/// and to todo2 for -1, at all of its locations
+pub fn reprioritize(entry: &Entry, by: isize, matcher: &Matcher, todo_files: &[PathBuf]) -> Result<Vec<RenamedLine>, String> {
+    let EntryData::Priority(priority) = entry.data else {
+        return Err(format!("{}:{} isn't a priority TODO", entry.location.file.to_string_lossy(), entry.location.line));
+    };
+
+    let new_priority = priority - by;
+
+    if new_priority > LOWEST_PRIORITY {
+        return Err(format!("{} is already the lowest priority", priority_notation(priority)));
+    }
+
+    std::iter::once(&entry.location).chain(&entry.also).map(|location| {
+        let (content, _) = read_text(&location.file).map_err(|err| err.to_string())?;
+        let before = location.line.checked_sub(1).and_then(|index| content.lines().nth(index)).unwrap_or_default();
+
+        // todo.md is always read with the default keywords
+        let matcher = match todo_files.iter().any(|file| *file == *location.file) {
+            true => Matcher::todo(),
+            false => matcher,
+        };
+
+        let after = rewrite(before, matcher, priority, new_priority)
+            .ok_or_else(|| format!("no {} at {}:{}", priority_notation(priority), location.file.to_string_lossy(), location.line))?;
+
+        Ok(RenamedLine { location: location.clone(), before: before.to_string(), after })
+    }).collect()
+}
+
+/// `line` with the priority of its `todo{from}` keyword changed, e.g. `// TODO1 foo` becomes
+/// `// TODO0 foo`. Only the digits are replaced, so the keyword keeps its case.
+fn rewrite(line: &str, matcher: &Matcher, from: isize, to: isize) -> Option<String> {
+    let (from, to) = (&priority_notation(from)[4..], &priority_notation(to)[4..]);
+
+    matcher.words(line).find_map(|(word, keyword_len)| {
+        let suffix = &word[keyword_len..];
+        let digits = suffix.len() - suffix.trim_start_matches(|char: char| char.is_ascii_digit()).len();
+
+        if &suffix[..digits] != from {
+            return None;
+        }
+
+        // `word` is a slice of `line`
+        let start = word.as_ptr() as usize - line.as_ptr() as usize + keyword_len;
+
+        Some(format!("{}{}{}", &line[..start], to, &line[start + digits..]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+    use std::fs;
+
+    #[test]
+    fn rewrite_test() {
+        let matcher = Matcher::todo();
+
+        assert_eq!(Some("// TODO0 foo".to_string()), rewrite("// TODO1 foo", matcher, 1, 0));
+        assert_eq!(Some("// todo00 foo".to_string()), rewrite("// todo0 foo", matcher, 0, -1));
+        assert_eq!(Some("- todo0 foo".to_string()), rewrite("- todo00 foo", matcher, -1, 0));
+        assert_eq!(Some("# FIXME2 foo todo1".to_string()), rewrite("# FIXME1 foo todo1", matcher, 1, 2));
+        assert_eq!(None, rewrite("// todo2 foo", matcher, 1, 0));
+        assert_eq!(None, rewrite("// todo10 foo", matcher, 1, 0));
+    }
+
+    #[test]
+    fn bump_test() {
+        let dir = TempDir::new("bump");
+
+        let file = dir.join("main.rs");
+        fs::write(&file, "// todo1 foo\n// todo9 bar\n// todo baz\n").unwrap();
+
+        let mut entries: Vec<Entry> = vec![];
+        crate::scan::scan_string(fs::read_to_string(&file).unwrap(), file.clone(), &mut entries);
+
+        let foo = find(&entries, "main.rs:1", &dir, &dir).unwrap();
+        let id = foo.stable_id(&dir, 0);
+        assert_eq!(foo, find(&entries, &id[..6], &dir, &dir).unwrap());
+        assert!(find(&entries, "main.rs:4", &dir, &dir).is_err());
+        assert!(find(&entries, "ffffffffffffffff", &dir, &dir).is_err());
+
+        let bumped = reprioritize(foo, 1, Matcher::todo(), &[]).unwrap();
+        assert_eq!(vec!["// todo0 foo"], bumped.iter().map(|line| line.after.as_str()).collect::<Vec<_>>());
+
+        let demoted = reprioritize(foo, -1, Matcher::todo(), &[]).unwrap();
+        assert_eq!(vec!["// todo2 foo"], demoted.iter().map(|line| line.after.as_str()).collect::<Vec<_>>());
+
+        assert!(reprioritize(find(&entries, "main.rs:2", &dir, &dir).unwrap(), -1, Matcher::todo(), &[]).is_err());
+        assert!(reprioritize(find(&entries, "main.rs:3", &dir, &dir).unwrap(), 1, Matcher::todo(), &[]).is_err());
+    }
+}
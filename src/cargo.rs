@@ -0,0 +1,68 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::Value;
+
+use crate::entries::Entry;
+
+pub struct Workspace {
+    pub root: PathBuf,
+    pub target_dir: PathBuf,
+    /// Names and directories of the workspace's crates
+    pub members: Vec<(String, PathBuf)>,
+}
+
+/// Read the Cargo workspace containing `dir` using `cargo metadata`
+pub fn workspace(dir: &Path) -> io::Result<Workspace> {
+    let output = Command::new(std::env::var("CARGO").unwrap_or("cargo".to_string()))
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .current_dir(dir)
+        .output()?;
+
+    if ! output.status.success() {
+        return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+
+    let metadata: Value = serde_json::from_slice(&output.stdout)?;
+    let mut members: Vec<(String, PathBuf)> = vec![];
+
+    for package in metadata["packages"].as_array().unwrap_or(&vec![]) {
+        if let (Some(name), Some(manifest)) = (package["name"].as_str(), package["manifest_path"].as_str()) {
+            members.push((name.to_string(), Path::new(manifest).parent().unwrap().to_path_buf()));
+        }
+    }
+
+    Ok(Workspace {
+        root: PathBuf::from(metadata["workspace_root"].as_str().unwrap_or_default()),
+        target_dir: PathBuf::from(metadata["target_directory"].as_str().unwrap_or_default()),
+        members,
+    })
+}
+
+/// Group entries by the crate they belong to. Entries outside of any crate (e.g. in
+/// the todo.md of a virtual workspace) are grouped under the workspace itself.
+pub fn group_by_member(entries: Vec<Entry>, workspace: &Workspace) -> Vec<(String, Vec<Entry>)> {
+    let mut groups: Vec<(String, Vec<Entry>)> = workspace.members.iter()
+        .map(|(name, _)| (name.clone(), vec![]))
+        .collect();
+
+    groups.push(("workspace".to_string(), vec![]));
+
+    for entry in entries {
+        // Nested crates take precedence over the crates containing them
+        let member = workspace.members.iter()
+            .enumerate()
+            .filter(|(_, (_, dir))| entry.location.file.starts_with(dir))
+            .max_by_key(|(_, (_, dir))| dir.components().count())
+            .map(|(index, _)| index)
+            .unwrap_or(workspace.members.len());
+
+        groups[member].1.push(entry);
+    }
+
+    groups.retain(|(_, entries)| ! entries.is_empty());
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+
+    groups
+}
@@ -0,0 +1,133 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+
+use crate::entries::{Entry, EntryData, Location};
+use crate::git::git;
+use crate::render::write_ansi;
+use crate::schema::print_json;
+use crate::scan::scan_string;
+
+/// A commit from `git log`
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    pub message: String,
+}
+
+/// A TODO made in a commit message
+#[derive(Serialize, JsonSchema, Debug, PartialEq)]
+pub struct CommitEntry {
+    pub hash: String,
+    pub author: String,
+    pub text: String,
+    /// Same as `EntryData::key()`, e.g. `priority:0` or `generic`
+    pub kind: String,
+}
+
+/// Commits reachable from HEAD, newest first. With `since`, only the commits after that ref.
+pub fn commits(dir: &Path, since: Option<&str>) -> io::Result<Vec<Commit>> {
+    let range = since.map(|since| format!("{}..HEAD", since));
+    let mut args = vec!["log", "--format=%H%x1f%an%x1f%B%x1e"];
+    args.extend(range.as_deref());
+
+    Ok(parse_log(&git(dir, &args)?))
+}
+
+/// Parse `git log` output using the format above: fields separated by 0x1F, commits by 0x1E
+fn parse_log(log: &str) -> Vec<Commit> {
+    log.split('\x1e')
+        .filter_map(|commit| {
+            let mut fields = commit.trim_start().splitn(3, '\x1f');
+
+            Some(Commit {
+                hash: fields.next().filter(|hash| ! hash.is_empty())?.to_string(),
+                author: fields.next()?.to_string(),
+                message: fields.next()?.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The TODOs in a commit message. Besides the usual markers, `FIXME` lines are included as generic entries.
+pub fn scan_message(message: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = vec![];
+    scan_string(message.to_string(), PathBuf::new(), &mut entries);
+
+    for (index, line) in message.lines().enumerate() {
+        if entries.iter().any(|entry| entry.location.line == index + 1) {
+            continue;
+        }
+
+        let marker = line.split_whitespace().find(|word| word.to_lowercase().starts_with("fixme"));
+
+        if let Some(marker) = marker {
+            entries.push(Entry::new(
+                line.split_once(marker).unwrap().1.trim_start_matches(':').trim().to_string(),
+                Location { file: PathBuf::new().into(), line: index + 1 },
+                EntryData::Generic,
+            ));
+        }
+    }
+
+    entries.sort_by_key(|entry| entry.location.line);
+
+    entries
+}
+
+/// The TODOs in the messages of `commits`, in the same order
+pub fn scan_commits(commits: &[Commit]) -> Vec<CommitEntry> {
+    commits.iter()
+        .flat_map(|commit| scan_message(&commit.message).into_iter().map(|entry| CommitEntry {
+            hash: commit.hash.clone(),
+            author: commit.author.clone(),
+            text: entry.text,
+            kind: entry.data.key(),
+        }))
+        .collect()
+}
+
+pub fn render_commit_entries(entries: &[CommitEntry]) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, "# TODOs in commit messages", true);
+    write!(stdout, "\n\n").unwrap();
+
+    for entry in entries {
+        write_ansi(&mut stdout, Color::Ansi256(243), "- [ ] ", false);
+        write_ansi(&mut stdout, Color::Blue, &entry.text, true);
+        write_ansi(&mut stdout, Color::Ansi256(243), &format!(" ({}, {})", &entry.hash[..entry.hash.len().min(7)], entry.author), false);
+        writeln!(stdout).unwrap();
+    }
+
+    stdout.flush().unwrap();
+}
+
+/// The shape of `scan-log --format json` output
+#[derive(Serialize, JsonSchema)]
+pub struct CommitsReport {
+    pub entries: Vec<CommitEntry>,
+}
+
+pub fn render_commit_entries_json(entries: Vec<CommitEntry>) {
+    print_json(CommitsReport { entries });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_commits_test() {
+        let log = "1111111\x1fAlice\x1fAdd login\n\nTODO: rate limiting\ntodo0 validate emails\n\x1e\n2222222\x1fBob\x1fFix typo\n\x1e\n3333333\x1fCarol\x1fQuick fix\n\nFIXME: this is a hack\n\x1e\n";
+
+        assert_eq!(vec![
+            CommitEntry { hash: "1111111".to_string(), author: "Alice".to_string(), text: "rate limiting".to_string(), kind: "generic".to_string() },
+            CommitEntry { hash: "1111111".to_string(), author: "Alice".to_string(), text: "validate emails".to_string(), kind: "priority:0".to_string() },
+            CommitEntry { hash: "3333333".to_string(), author: "Carol".to_string(), text: "this is a hack".to_string(), kind: "generic".to_string() },
+        ], scan_commits(&parse_log(log)));
+    }
+}
@@ -0,0 +1,488 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+use termcolor::Color;
+
+use crate::matcher::{Matcher, Syntax};
+use crate::scanners::Plugin;
+use crate::scan::SKIPPED_FILES;
+use crate::template::Template;
+
+/// The config files looked for in the project root, in order. Both are TOML.
+pub const CONFIG_FILES: [&str; 2] = ["todo.toml", ".todorc"];
+
+/// Settings read from `todo.toml` (or `.todorc`). Everything is optional.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub sections: Sections,
+    /// Heading colors by section, e.g. `todo00 = "red"`. Colors are names (`red`, `yellow`, ...),
+    /// ANSI 256 color numbers (`208`), or RGB triples (`255,128,0`).
+    pub colors: HashMap<String, String>,
+    pub locations: Locations,
+    /// Labels added to section headings, e.g. `todo0 = "Now"` renders `## todo0: Now`
+    pub labels: HashMap<String, String>,
+    /// Text rendered under category headings, e.g. `Backend = "Server-side work"`. The paragraphs
+    /// under the headings of todo.md are used for the categories not listed here.
+    pub descriptions: BTreeMap<String, String>,
+    pub categories: Categories,
+    pub sources: Sources,
+    pub scan: Scan,
+    /// Print a warning (without failing) when a section has more entries than this, e.g.
+    /// `todo0 = 3`. Sections are named like in `[sections]`, and `total` counts all entries.
+    pub thresholds: HashMap<String, usize>,
+    pub stale: Stale,
+    pub severity: Severity,
+    pub diff: Diff,
+    /// External commands that scan other file types, see [`Plugin`]
+    pub plugins: Vec<Plugin>,
+}
+
+/// The categories teams agreed on. Entries using any others are reported by `todos lint`.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Categories {
+    /// The valid categories, e.g. `["auth", "ui"]`. Any category is valid when this is empty.
+    pub allowed: Vec<String>,
+    /// List the entries with unknown categories in an `Unknown categories` section, instead of
+    /// in sections of their own
+    pub unknown_section: bool,
+    /// Who handles each category's TODOs (a person, team, or chat handle), e.g.
+    /// `auth = "@backend-team"`. Shown next to the category's heading and included in exports.
+    pub owners: BTreeMap<String, String>,
+    /// Categories of the generic entries in some paths, e.g. `"src/api/**" = "backend"`. Paths are
+    /// patterns relative to the project root, and the longest one matching the file (or one of
+    /// its directories) is used.
+    pub paths: BTreeMap<String, String>,
+}
+
+impl Categories {
+    pub fn is_allowed(&self, category: &str) -> bool {
+        self.allowed.is_empty() || self.allowed.iter().any(|allowed| allowed == category)
+    }
+
+    /// The category of generic entries in `file`, relative to the project root, per `paths`
+    pub fn for_path(&self, file: &Path) -> Option<&str> {
+        self.path_rule(file).map(|(_, category)| category)
+    }
+
+    /// The pattern in `paths` used for `file`, and its category
+    pub fn path_rule(&self, file: &Path) -> Option<(&str, &str)> {
+        self.paths.iter()
+            .filter(|(pattern, _)| matches_path(pattern, file))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(pattern, category)| (pattern.as_str(), category.as_str()))
+    }
+}
+
+/// Whether `pattern` (relative to the project root) matches `file` or one of its directories
+fn matches_path(pattern: &str, file: &Path) -> bool {
+    let options = MatchOptions { require_literal_separator: true, ..MatchOptions::new() };
+
+    Pattern::new(pattern.trim_end_matches('/')).is_ok_and(|pattern| {
+        file.ancestors().any(|path| pattern.matches_path_with(path, options))
+    })
+}
+
+/// Paths whose entries matter less than the rest, e.g. examples
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Severity {
+    /// Patterns relative to the project root, like in `[categories] paths`, e.g. `["examples/**"]`.
+    /// Their entries are listed in an `Informational` section, and don't count toward
+    /// `--fail-on` and the thresholds.
+    pub informational: Vec<String>,
+}
+
+impl Severity {
+    /// Whether the entries in `file`, relative to the project root, are informational
+    pub fn is_informational(&self, file: &Path) -> bool {
+        self.informational.iter().any(|pattern| matches_path(pattern, file))
+    }
+}
+
+/// Whether todo.md and the readme are scanned, along with the code
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sources {
+    pub todo_file: bool,
+    pub readme: bool,
+}
+
+/// Entries older than `max_age_days` (per `git blame`) are marked with a warning and listed
+/// in a `Stale` section
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Stale {
+    pub max_age_days: Option<u64>,
+}
+
+/// How `diff`, `resolved`, and pull request comments match the entries of two scans
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Diff {
+    /// How similar (0 to 1, by the words they share) the text of an added TODO has to be to a
+    /// resolved one of the same kind in the same file for it to count as reworded instead
+    pub similarity: f64,
+}
+
+/// How entries are read from the code
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scan {
+    /// Tokens removed from the end of entry text, in addition to the built-in comment closers
+    /// (`*/`, `-->`, `?>`, ...), e.g. `["end", "]]"]`
+    pub closers: Vec<String>,
+    /// Markers scanned for in addition to `todo`, with the same syntax, e.g. `["fixme", "hack"]`,
+    /// or tables with options, e.g. `{ name = "review", color = "cyan", priorities = false }`
+    pub keywords: Vec<Keyword>,
+    /// Regexes matched in addition to the keywords, with the named capture groups `text`,
+    /// `category`, and `priority`, e.g. `['NOCOMMIT:\s*(?P<text>.*)']`
+    pub patterns: Vec<String>,
+    /// Files that aren't read, as names or patterns matched at any depth (like `--exclude`),
+    /// e.g. `["*.png", "*.lock"]`. Replaces the default list of lockfiles, images, fonts, etc.
+    pub skip: Option<Vec<String>>,
+}
+
+/// A configured keyword: its name, or a table with its options
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "KeywordSpec")]
+pub struct Keyword {
+    pub name: String,
+    /// The color of the keyword in front of its entries, like in `[colors]`
+    pub color: Option<String>,
+    pub syntax: Syntax,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeywordSpec {
+    Name(String),
+    Options(KeywordOptions),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeywordOptions {
+    name: String,
+    color: Option<String>,
+    #[serde(default = "enabled")]
+    categories: bool,
+    #[serde(default = "enabled")]
+    priorities: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl From<KeywordSpec> for Keyword {
+    fn from(spec: KeywordSpec) -> Keyword {
+        match spec {
+            KeywordSpec::Name(name) => Keyword { name, color: None, syntax: Syntax::default() },
+            KeywordSpec::Options(options) => Keyword {
+                name: options.name,
+                color: options.color,
+                syntax: Syntax { categories: options.categories, priorities: options.priorities },
+            },
+        }
+    }
+}
+
+impl Scan {
+    pub fn matcher(&self) -> Result<Matcher, String> {
+        let names: Vec<&str> = self.keywords.iter().map(|keyword| keyword.name.as_str()).collect();
+        let mut matcher = Matcher::new(&names)?.with_patterns(&self.patterns)?;
+
+        for keyword in &self.keywords {
+            matcher = matcher.with_syntax(&keyword.name, keyword.syntax);
+        }
+
+        Ok(matcher)
+    }
+
+    /// The configured `skip` list, or the default one
+    pub fn skipped_files(&self) -> Vec<&str> {
+        match &self.skip {
+            Some(skip) => skip.iter().map(String::as_str).collect(),
+            None => SKIPPED_FILES.to_vec(),
+        }
+    }
+}
+
+/// Order and visibility of the sections in the markdown output.
+///
+/// Sections are referred to by the priority notation (`todo0`), the category name, or one of the
+/// groups: `priorities`, `unimplemented`, `markers`, `categories`, `unknown`, `other`, `stale`,
+/// `someday`, and `informational`. Sections listed individually in `order` are rendered in that
+/// position rather than with the rest of their group.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sections {
+    pub order: Vec<String>,
+    pub hidden: Vec<String>,
+    /// Render the title and the `Other` section even when they're empty, for stable diffs
+    pub always_show: bool,
+    pub sort: SortOrder,
+    pub bullets: BulletStyle,
+}
+
+/// The order of the entries within a section
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// By file, then line
+    #[default]
+    Location,
+    Text,
+}
+
+/// How entries are listed in the markdown output
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BulletStyle {
+    /// `- [ ] foo`
+    #[default]
+    Checkbox,
+    /// `- foo`
+    Dash,
+    /// `1. foo`, numbered within each section
+    Numbered,
+}
+
+impl BulletStyle {
+    /// The bullet of the entry at `index` (zero-based) in its list, including the trailing space
+    pub fn bullet(self, index: usize) -> String {
+        match self {
+            BulletStyle::Checkbox => "- [ ] ".to_string(),
+            BulletStyle::Dash => "- ".to_string(),
+            BulletStyle::Numbered => format!("{}. ", index + 1),
+        }
+    }
+}
+
+/// How the locations of entries are rendered
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Locations {
+    /// Prefixes replaced in the rendered paths, which are relative to the project root unless
+    /// `--absolute` is used, e.g. `"packages/app/" = ""`. The longest matching prefix is used.
+    pub prefixes: HashMap<String, String>,
+    /// Link the locations using this template (see `--template`), e.g.
+    /// `https://github.com/org/repo/blob/main/{file}#L{line}`
+    #[serde(deserialize_with = "deserialize_url")]
+    pub url: Option<Template>,
+}
+
+/// Parses `locations.url` once, when the config is loaded
+fn deserialize_url<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Template>, D::Error> {
+    let url = String::deserialize(deserializer)?;
+
+    Template::parse(&url).map(Some).map_err(|err| serde::de::Error::custom(format!("invalid location url: {}", err)))
+}
+
+/// Defaults declared in a YAML front-matter block at the start of todo.md, so that the
+/// project's conventions travel with the file. Settings in todo.toml take precedence.
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrontMatter {
+    /// The category of the items above the first heading
+    pub category: Option<String>,
+    /// Labels of the priority sections, e.g. `todo0: Now`
+    pub priorities: HashMap<String, String>,
+    /// Same as `sections.order` in todo.toml
+    pub order: Option<Vec<String>>,
+}
+
+impl FrontMatter {
+    /// The YAML between the `---` lines at the start of `content`, and the number of lines
+    /// the block spans including the delimiters
+    pub fn split(content: &str) -> Option<(String, usize)> {
+        let mut lines = content.lines();
+
+        if lines.next()?.trim_end() != "---" {
+            return None;
+        }
+
+        let mut yaml: Vec<&str> = vec![];
+
+        for line in lines {
+            if line.trim_end() == "---" {
+                return Some((yaml.join("\n"), yaml.len() + 2));
+            }
+
+            yaml.push(line);
+        }
+
+        None
+    }
+
+    /// The front matter of `content`, or the defaults if it doesn't have one
+    pub fn parse(content: &str) -> Result<FrontMatter, String> {
+        match FrontMatter::split(content) {
+            Some((yaml, _)) if ! yaml.trim().is_empty() => serde_yaml_ng::from_str(&yaml).map_err(|err| err.to_string()),
+            _ => Ok(FrontMatter::default()),
+        }
+    }
+}
+
+impl Default for Sections {
+    fn default() -> Sections {
+        Sections {
+            order: vec!["priorities".to_string(), "unimplemented".to_string(), "markers".to_string(), "categories".to_string(), "unknown".to_string(), "other".to_string(), "stale".to_string(), "someday".to_string(), "informational".to_string()],
+            hidden: vec![],
+            always_show: false,
+            sort: SortOrder::default(),
+            bullets: BulletStyle::default(),
+        }
+    }
+}
+
+impl Default for Diff {
+    fn default() -> Diff {
+        Diff {
+            similarity: 0.6,
+        }
+    }
+}
+
+impl Default for Sources {
+    fn default() -> Sources {
+        Sources {
+            todo_file: true,
+            readme: true,
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config, String> {
+        Config::parse(&fs::read_to_string(path).map_err(|err| err.to_string())?)
+    }
+
+    /// Parse and validate the content of a todo.toml, e.g. one read from git
+    pub fn parse(content: &str) -> Result<Config, String> {
+        let config: Config = toml::from_str(content).map_err(|err| err.to_string())?;
+
+        for (section, color) in &config.colors {
+            color.parse::<Color>().map_err(|err| format!("invalid color for {}: {}", section, err))?;
+        }
+
+        for pattern in config.categories.paths.keys() {
+            Pattern::new(pattern).map_err(|err| format!("invalid category path {}: {}", pattern, err))?;
+        }
+
+        for pattern in &config.severity.informational {
+            Pattern::new(pattern).map_err(|err| format!("invalid informational path {}: {}", pattern, err))?;
+        }
+
+        if ! (0.0..=1.0).contains(&config.diff.similarity) {
+            return Err(format!("invalid diff similarity {}, expected a number from 0 to 1", config.diff.similarity));
+        }
+
+        for keyword in &config.scan.keywords {
+            if let Some(color) = &keyword.color {
+                color.parse::<Color>().map_err(|err| format!("invalid color for keyword {}: {}", keyword.name, err))?;
+            }
+        }
+
+        config.scan.matcher().map_err(|err| format!("invalid scan keywords or patterns: {}", err))?;
+
+        for plugin in &config.plugins {
+            plugin.validate().map_err(|err| format!("invalid plugin {}: {}", plugin.command.join(" "), err))?;
+        }
+
+        Ok(config)
+    }
+
+    /// Use the section order and labels from todo.md's front matter, unless todo.toml sets them
+    pub fn apply_front_matter(&mut self, front_matter: FrontMatter) {
+        if let Some(order) = front_matter.order {
+            if self.sections.order == Sections::default().order {
+                self.sections.order = order;
+            }
+        }
+
+        for (section, label) in front_matter.priorities {
+            self.labels.entry(section).or_insert(label);
+        }
+    }
+
+    /// Use the category descriptions from todo.md, unless todo.toml sets them
+    pub fn apply_descriptions(&mut self, descriptions: BTreeMap<String, String>) {
+        for (category, description) in descriptions {
+            self.descriptions.entry(category).or_insert(description);
+        }
+    }
+
+    /// The configured heading color of a section, if any
+    pub fn color(&self, section: &str) -> Option<Color> {
+        self.colors.get(section).and_then(|color| color.parse().ok())
+    }
+
+    /// The configured color of a keyword (e.g. `fixme`), if any
+    pub fn keyword_color(&self, keyword: &str) -> Option<Color> {
+        self.scan.keywords.iter()
+            .find(|configured| configured.name.eq_ignore_ascii_case(keyword))
+            .and_then(|configured| configured.color.as_ref()?.parse().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn front_matter_test() {
+        let content = "---\ncategory: backlog\npriorities:\n  todo0: Now\norder: [todo0, backlog, other]\n---\n\n- foo\n";
+
+        assert_eq!(Some(("category: backlog\npriorities:\n  todo0: Now\norder: [todo0, backlog, other]".to_string(), 6)), FrontMatter::split(content));
+        assert_eq!(FrontMatter {
+            category: Some("backlog".to_string()),
+            priorities: HashMap::from([("todo0".to_string(), "Now".to_string())]),
+            order: Some(vec!["todo0".to_string(), "backlog".to_string(), "other".to_string()]),
+        }, FrontMatter::parse(content).unwrap());
+
+        assert_eq!(None, FrontMatter::split("- foo\n---\n"));
+        assert_eq!(None, FrontMatter::split("---\nunclosed: true\n"));
+        assert_eq!(FrontMatter::default(), FrontMatter::parse("# Heading\n").unwrap());
+        assert!(FrontMatter::parse("---\nunknown: true\n---\n").is_err());
+
+        let mut config = Config::default();
+        config.labels.insert("todo0".to_string(), "Today".to_string());
+        config.apply_front_matter(FrontMatter::parse(content).unwrap());
+
+        assert_eq!(vec!["todo0", "backlog", "other"], config.sections.order);
+        assert_eq!("Today", config.labels["todo0"]);
+    }
+
+    #[test]
+    fn keywords_test() {
+        let config = Config::parse("[scan]\nkeywords = [\"optimize\", { name = \"review\", color = \"cyan\", priorities = false }, { name = \"@todo\" }]\n").unwrap();
+
+        assert_eq!(vec![
+            Keyword { name: "optimize".to_string(), color: None, syntax: Syntax::default() },
+            Keyword { name: "review".to_string(), color: Some("cyan".to_string()), syntax: Syntax { categories: true, priorities: false } },
+            Keyword { name: "@todo".to_string(), color: None, syntax: Syntax::default() },
+        ], config.scan.keywords);
+
+        assert_eq!(Some(Color::Cyan), config.keyword_color("REVIEW"));
+        assert_eq!(None, config.keyword_color("optimize"));
+
+        assert!(Config::parse("[scan]\nkeywords = [{ name = \"review\", color = \"nope\" }]\n").is_err());
+        assert!(Config::parse("[scan]\nkeywords = [{ name = \"review\", unknown = true }]\n").is_err());
+        assert!(Config::parse("[scan]\nkeywords = [\"re@view\"]\n").is_err());
+    }
+
+    #[test]
+    fn locations_url_test() {
+        let config = Config::parse("[locations]\nurl = \"https://example.com/{file}#L{line}\"\n").unwrap();
+        assert_eq!(Some(Template::parse("https://example.com/{file}#L{line}").unwrap()), config.locations.url);
+
+        let err = Config::parse("[locations]\nurl = \"https://example.com/{nope}\"\n").unwrap_err();
+        assert!(err.contains("invalid location url"), "{}", err);
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+
+use crate::entries::{Entry, EntryData, stable_ids};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS scans (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        scanned_at INTEGER NOT NULL,
+        entry_count INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS entries (
+        id TEXT PRIMARY KEY,
+        file TEXT NOT NULL,
+        line INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        value TEXT,
+        text TEXT NOT NULL,
+        first_seen INTEGER NOT NULL,
+        last_seen INTEGER NOT NULL,
+        last_scan INTEGER NOT NULL REFERENCES scans (id),
+        resolved_at INTEGER
+    );
+";
+
+/// Upsert the entries of a scan into a SQLite database, keyed by their stable IDs.
+///
+/// Entries seen for the first time get a `first_seen` timestamp, all entries get their
+/// `last_seen` timestamp updated, and entries from previous scans that weren't found
+/// anymore get a `resolved_at` timestamp. Timestamps are unix seconds. File paths are
+/// stored relative to `root`.
+pub fn record_scan(path: &Path, root: &Path, entries: &[Entry]) -> rusqlite::Result<()> {
+    let mut conn = Connection::open(path)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    conn.execute_batch(SCHEMA)?;
+
+    let tx = conn.transaction()?;
+
+    tx.execute("INSERT INTO scans (scanned_at, entry_count) VALUES (?1, ?2)", params![now, entries.len() as i64])?;
+    let scan = tx.last_insert_rowid();
+
+    {
+        let mut upsert = tx.prepare("
+            INSERT INTO entries (id, file, line, kind, value, text, first_seen, last_seen, last_scan, resolved_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7, ?8, NULL)
+            ON CONFLICT (id) DO UPDATE SET
+                file = excluded.file,
+                line = excluded.line,
+                last_seen = excluded.last_seen,
+                last_scan = excluded.last_scan,
+                resolved_at = NULL
+        ")?;
+
+        for (entry, id) in entries.iter().zip(stable_ids(entries, root)) {
+            let file = entry.location.file.strip_prefix(root).unwrap_or(&entry.location.file);
+            let (kind, value) = match &entry.data {
+                EntryData::Priority(priority) => ("priority", Some(priority.to_string())),
+                EntryData::Category(category) => ("category", Some(category.clone())),
+                EntryData::Generic => ("generic", None),
+                EntryData::Someday => ("someday", None),
+                EntryData::Unimplemented => ("unimplemented", None),
+                EntryData::Marker => ("marker", None),
+            };
+
+            upsert.execute(params![id, file.to_string_lossy(), entry.location.line as i64, kind, value, entry.text, now, scan])?;
+        }
+    }
+
+    tx.execute("UPDATE entries SET resolved_at = ?1 WHERE resolved_at IS NULL AND last_scan < ?2", params![now, scan])?;
+
+    tx.commit()
+}
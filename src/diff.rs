@@ -0,0 +1,365 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+
+use crate::config::Config;
+use crate::entries::{Entry, EntryData, normalize_text};
+use crate::render::{JsonReport, write_ansi};
+use crate::schema::{SCHEMA_VERSION, Versioned, print_json};
+use crate::suggest::similarity;
+
+/// An entry that's present in both scans, but in a different file or with reworded text
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MovedEntry {
+    pub from: Entry,
+    pub to: Entry,
+}
+
+/// Changes between two scans
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Diff {
+    pub added: Vec<Entry>,
+    pub removed: Vec<Entry>,
+    pub moved: Vec<MovedEntry>,
+    /// Entries whose text was edited, matched by `[diff] similarity`
+    #[serde(default)]
+    pub reworded: Vec<MovedEntry>,
+}
+
+/// The shape of `resolved --format json` output
+#[derive(Serialize, JsonSchema)]
+pub struct ResolvedReport {
+    pub resolved: Vec<Entry>,
+}
+
+/// Read the entries from a file created using `--format json`
+pub fn read_entries(path: &Path) -> io::Result<Vec<Entry>> {
+    let report: Versioned<JsonReport> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+    if report.schema_version > SCHEMA_VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported schema version {}, expected {} or older", report.schema_version, SCHEMA_VERSION)));
+    }
+
+    Ok(report.data.entries)
+}
+
+/// Remove the first entry matching `key` from the map of unmatched entries
+fn take<K: std::hash::Hash + Eq>(unmatched: &mut HashMap<K, Vec<Entry>>, key: &K) -> Option<Entry> {
+    let entries = unmatched.get_mut(key)?;
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    Some(entries.remove(0))
+}
+
+/// The words of an entry's normalized text
+fn words(entry: &Entry) -> BTreeSet<String> {
+    normalize_text(&entry.text).split(' ').filter(|word| ! word.is_empty()).map(String::from).collect()
+}
+
+/// Compare two scans. Entries are matched by their file, kind, and normalized text, so entries
+/// that only changed lines (or whitespace, case, and punctuation) aren't reported. Unmatched
+/// entries with the same kind and text in different files are reported as moved, and the ones
+/// of the same kind in the same file whose words are at least `min_similarity` similar (0 to 1)
+/// as reworded.
+pub fn diff(old: Vec<Entry>, new: Vec<Entry>, min_similarity: f64) -> Diff {
+    let mut unmatched_old: HashMap<(Arc<Path>, EntryData, String), Vec<Entry>> = HashMap::new();
+
+    for entry in old {
+        unmatched_old.entry((entry.location.file.clone(), entry.data.clone(), normalize_text(&entry.text))).or_default().push(entry);
+    }
+
+    let mut unmatched_new: Vec<Entry> = vec![];
+
+    for entry in new {
+        let key = (entry.location.file.clone(), entry.data.clone(), normalize_text(&entry.text));
+
+        if take(&mut unmatched_old, &key).is_none() {
+            unmatched_new.push(entry);
+        }
+    }
+
+    let mut removed_by_content: HashMap<(EntryData, String), Vec<Entry>> = HashMap::new();
+
+    for entry in unmatched_old.into_values().flatten() {
+        removed_by_content.entry((entry.data.clone(), normalize_text(&entry.text))).or_default().push(entry);
+    }
+
+    let mut added: Vec<Entry> = vec![];
+    let mut moved: Vec<MovedEntry> = vec![];
+
+    for entry in unmatched_new {
+        match take(&mut removed_by_content, &(entry.data.clone(), normalize_text(&entry.text))) {
+            Some(from) => moved.push(MovedEntry { from, to: entry }),
+            None => added.push(entry),
+        }
+    }
+
+    let by_location = |a: &Entry, b: &Entry| (&a.location.file, a.location.line).cmp(&(&b.location.file, b.location.line));
+
+    let mut removed: Vec<Entry> = removed_by_content.into_values().flatten().collect();
+    removed.sort_by(by_location);
+
+    let mut reworded: Vec<MovedEntry> = vec![];
+    let mut unmatched_added: Vec<Entry> = vec![];
+
+    // Each added entry is matched with the most similar resolved one
+    for entry in added {
+        let text = words(&entry);
+        let best = removed.iter().enumerate()
+            .filter(|(_, from)| from.location.file == entry.location.file && from.data == entry.data)
+            .map(|(index, from)| (index, similarity(&text, &words(from))))
+            .filter(|(_, similarity)| *similarity > 0.0 && *similarity >= min_similarity)
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(&a.0)));
+
+        match best {
+            Some((index, _)) => reworded.push(MovedEntry { from: removed.remove(index), to: entry }),
+            None => unmatched_added.push(entry),
+        }
+    }
+
+    let mut added = unmatched_added;
+    added.sort_by(by_location);
+    moved.sort_by(|a, b| by_location(&a.to, &b.to));
+    reworded.sort_by(|a, b| by_location(&a.to, &b.to));
+
+    Diff { added, removed, moved, reworded }
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty() && self.reworded.is_empty()
+    }
+
+    /// One-line summary, e.g. "Adds 3 TODOs and resolves 1"
+    pub fn summary(&self) -> String {
+        let plural = |count: usize| if count == 1 { "TODO" } else { "TODOs" };
+        let mut summary = format!("Adds {} {} and resolves {}", self.added.len(), plural(self.added.len()), self.removed.len());
+
+        if ! self.moved.is_empty() {
+            summary.push_str(&format!(", {} moved", self.moved.len()));
+        }
+
+        if ! self.reworded.is_empty() {
+            summary.push_str(&format!(", {} reworded", self.reworded.len()));
+        }
+
+        summary
+    }
+}
+
+pub fn render_diff(diff: &Diff, config: &Config) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, "# TODO changes", true);
+    write!(stdout, "\n\n{}\n", diff.summary()).unwrap();
+
+    for (heading, color, entries) in [("Added", Color::Red, &diff.added), ("Resolved", Color::Green, &diff.removed)] {
+        if entries.is_empty() {
+            continue;
+        }
+
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, color, format!("## {} ({})", heading, entries.len()).as_str(), true);
+        writeln!(stdout).unwrap();
+
+        for (index, entry) in entries.iter().enumerate() {
+            entry.render(&mut stdout, config, true, index);
+        }
+    }
+
+    if ! diff.moved.is_empty() {
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, Color::Cyan, format!("## Moved ({})", diff.moved.len()).as_str(), true);
+        writeln!(stdout).unwrap();
+
+        for (index, moved) in diff.moved.iter().enumerate() {
+            moved.to.render(&mut stdout, config, true, index);
+            write_ansi(&mut stdout, Color::Ansi256(243), format!("  (from {})", moved.from.rendered_location(&config.locations)).as_str(), false);
+            writeln!(stdout).unwrap();
+        }
+    }
+
+    if ! diff.reworded.is_empty() {
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, Color::Cyan, format!("## Reworded ({})", diff.reworded.len()).as_str(), true);
+        writeln!(stdout).unwrap();
+
+        for (index, reworded) in diff.reworded.iter().enumerate() {
+            reworded.to.render(&mut stdout, config, true, index);
+            write_ansi(&mut stdout, Color::Ansi256(243), format!("  (was: {})", reworded.from.text).as_str(), false);
+            writeln!(stdout).unwrap();
+        }
+    }
+
+    stdout.flush().unwrap();
+}
+
+/// Render the entries that were resolved as a plain list, suitable for release notes
+pub fn render_resolved(diff: &Diff, config: &Config) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, "# Resolved TODOs", true);
+    writeln!(stdout, "\n").unwrap();
+
+    for entry in &diff.removed {
+        let location = entry.rendered_location(&config.locations);
+
+        write_ansi(&mut stdout, Color::Ansi256(243), "- ", false);
+
+        if entry.text.is_empty() {
+            write_ansi(&mut stdout, Color::Cyan, location.as_str(), true);
+        } else {
+            write_ansi(&mut stdout, Color::Green, entry.text.as_str(), true);
+            write_ansi(&mut stdout, Color::Ansi256(243), format!(" ({})", location).as_str(), false);
+        }
+
+        writeln!(stdout).unwrap();
+    }
+
+    stdout.flush().unwrap();
+}
+
+/// Hidden in PR comments, so that CI can find its previous comment and update it
+pub const PR_COMMENT_MARKER: &str = "<!-- todos:pr-comment -->";
+
+/// A markdown comment for a pull request: the summary, followed by the added, resolved, moved,
+/// and reworded TODOs in collapsible sections. Only the added ones are expanded.
+pub fn pr_comment(diff: &Diff, config: &Config) -> String {
+    let mut comment = format!("{}\n### TODO changes\n\n", PR_COMMENT_MARKER);
+
+    if diff.is_empty() {
+        comment.push_str("No TODOs added or resolved.\n");
+
+        return comment;
+    }
+
+    comment.push_str(&format!("{}\n", diff.summary()));
+
+    let item = |entry: &Entry, location: String| match entry.text.is_empty() {
+        true => format!("- `{}` {}\n", entry.badge(), location),
+        false => format!("- `{}` {} ({})\n", entry.badge(), entry.text, location),
+    };
+
+    let moved: Vec<String> = diff.moved.iter()
+        .map(|moved| item(&moved.to, format!("{}, from {}", moved.to.rendered_locations(&config.locations), moved.from.rendered_location(&config.locations))))
+        .collect();
+
+    let reworded: Vec<String> = diff.reworded.iter()
+        .map(|reworded| item(&reworded.to, format!("{}, was: {}", reworded.to.rendered_locations(&config.locations), reworded.from.text)))
+        .collect();
+
+    let sections = [
+        ("Added", true, diff.added.iter().map(|entry| item(entry, entry.rendered_locations(&config.locations))).collect()),
+        ("Resolved", false, diff.removed.iter().map(|entry| item(entry, entry.rendered_locations(&config.locations))).collect()),
+        ("Moved", false, moved),
+        ("Reworded", false, reworded),
+    ];
+
+    for (heading, open, items) in sections {
+        if items.is_empty() {
+            continue;
+        }
+
+        let open = if open { " open" } else { "" };
+        comment.push_str(&format!("\n<details{}>\n<summary>{} ({})</summary>\n\n{}\n</details>\n", open, heading, items.len(), items.concat()));
+    }
+
+    comment
+}
+
+pub fn render_resolved_json(diff: &Diff) {
+    print_json(ResolvedReport { resolved: diff.removed.clone() });
+}
+
+pub fn render_diff_json(diff: &Diff) {
+    print_json(diff);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::entry;
+
+    #[test]
+    fn diff_test() {
+        let old = vec![
+            entry("a.rs", 1, EntryData::Generic, "unchanged"),
+            entry("a.rs", 2, EntryData::Generic, "shifted"),
+            entry("a.rs", 3, EntryData::Generic, "removed"),
+            entry("a.rs", 4, EntryData::Generic, "moved"),
+            entry("c.rs", 1, EntryData::Generic, "reworded"),
+            entry("c.rs", 2, EntryData::Generic, "fix the login form on mobile"),
+            entry("c.rs", 3, EntryData::Generic, "cache results"),
+        ];
+
+        let new = vec![
+            entry("a.rs", 1, EntryData::Generic, "unchanged"),
+            entry("a.rs", 10, EntryData::Generic, "shifted"),
+            entry("b.rs", 1, EntryData::Generic, "moved"),
+            entry("b.rs", 2, EntryData::Generic, "added"),
+            entry("c.rs", 1, EntryData::Generic, "Reworded."),
+            entry("c.rs", 12, EntryData::Generic, "fix the login form on tablets"),
+            entry("c.rs", 13, EntryData::Generic, "retry requests"),
+        ];
+
+        // Without the similarity matching, rewording is resolving and adding a TODO
+        assert_eq!(3, diff(old.clone(), new.clone(), 1.0).added.len());
+
+        let diff = diff(old, new, 0.6);
+
+        assert_eq!(vec![entry("b.rs", 2, EntryData::Generic, "added"), entry("c.rs", 13, EntryData::Generic, "retry requests")], diff.added);
+        assert_eq!(vec![entry("a.rs", 3, EntryData::Generic, "removed"), entry("c.rs", 3, EntryData::Generic, "cache results")], diff.removed);
+        assert_eq!(vec![MovedEntry {
+            from: entry("a.rs", 4, EntryData::Generic, "moved"),
+            to: entry("b.rs", 1, EntryData::Generic, "moved"),
+        }], diff.moved);
+        assert_eq!(vec![MovedEntry {
+            from: entry("c.rs", 2, EntryData::Generic, "fix the login form on mobile"),
+            to: entry("c.rs", 12, EntryData::Generic, "fix the login form on tablets"),
+        }], diff.reworded);
+
+        assert_eq!("Adds 2 TODOs and resolves 2, 1 moved, 1 reworded", diff.summary());
+    }
+
+    #[test]
+    fn pr_comment_test() {
+        let changes = diff(vec![entry("a.rs", 3, EntryData::Generic, "removed"), entry("a.rs", 4, EntryData::Generic, "moved")], vec![entry("b.rs", 1, EntryData::Generic, "moved"), entry("b.rs", 2, EntryData::Generic, "added")], 0.6);
+
+        assert_eq!(format!("{}
+### TODO changes
+
+Adds 1 TODO and resolves 1, 1 moved
+
+<details open>
+<summary>Added (1)</summary>
+
+- `todo` added (b.rs:2)
+
+</details>
+
+<details>
+<summary>Resolved (1)</summary>
+
+- `todo` removed (a.rs:3)
+
+</details>
+
+<details>
+<summary>Moved (1)</summary>
+
+- `todo` moved (b.rs:1, from a.rs:4)
+
+</details>
+", PR_COMMENT_MARKER), pr_comment(&changes, &Config::default()));
+
+        assert_eq!(format!("{}\n### TODO changes\n\nNo TODOs added or resolved.\n", PR_COMMENT_MARKER), pr_comment(&diff(vec![], vec![], 0.6), &Config::default()));
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+
+use crate::config::Config;
+use crate::diff::diff;
+use crate::entries::Entry;
+use crate::git::BlameLine;
+use crate::render::write_ansi;
+
+/// How many of the oldest outstanding entries are listed
+const OLDEST_COUNT: usize = 5;
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// A summary of the changes to the TODOs over a period, e.g. for a weekly team update
+pub struct Digest {
+    /// e.g. `7d`
+    pub period: String,
+    pub added: Vec<Entry>,
+    pub resolved: Vec<Entry>,
+    /// The oldest outstanding entries, with their age in days
+    pub oldest: Vec<(Entry, i64)>,
+    pub count_before: usize,
+    pub count_after: usize,
+}
+
+/// Compare the entries at the start of the period with the current ones. Ages are taken
+/// from `blames` as of `now` (a Unix timestamp), and reworded entries are matched by `similarity`.
+pub fn digest(period: &str, before: Vec<Entry>, after: Vec<Entry>, blames: &HashMap<Arc<Path>, Vec<BlameLine>>, now: i64, similarity: f64) -> Digest {
+    let count_before = before.len();
+    let count_after = after.len();
+
+    let mut oldest: Vec<(Entry, i64)> = after.iter()
+        .filter_map(|entry| {
+            let blame = blames.get(&entry.location.file)?.iter().find(|line| line.line == entry.location.line)?;
+
+            Some((entry.clone(), (now - blame.time).max(0) / DAY))
+        })
+        .collect();
+
+    oldest.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| (&a.0.location.file, a.0.location.line).cmp(&(&b.0.location.file, b.0.location.line))));
+    oldest.truncate(OLDEST_COUNT);
+
+    // Moved and reworded entries are neither new nor resolved
+    let diff = diff(before, after, similarity);
+
+    Digest {
+        period: period.to_string(),
+        added: diff.added,
+        resolved: diff.removed,
+        oldest,
+        count_before,
+        count_after,
+    }
+}
+
+impl Digest {
+    /// e.g. `42 → 45 TODOs (+3)`
+    pub fn trend(&self) -> String {
+        let change = self.count_after as i64 - self.count_before as i64;
+
+        format!("{} → {} TODOs ({:+})", self.count_before, self.count_after, change)
+    }
+}
+
+pub fn render_digest(digest: &Digest, config: &Config) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, &format!("# TODO digest (last {})", digest.period), true);
+    write!(stdout, "\n\n{}\n", digest.trend()).unwrap();
+
+    let sections = [
+        ("New", Color::Red, &digest.added),
+        ("Resolved", Color::Green, &digest.resolved),
+    ];
+
+    for (heading, color, entries) in sections {
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, color, &format!("## {} ({})", heading, entries.len()), true);
+        writeln!(stdout).unwrap();
+
+        for (index, entry) in entries.iter().enumerate() {
+            entry.render(&mut stdout, config, true, index);
+        }
+    }
+
+    if ! digest.oldest.is_empty() {
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, Color::Cyan, "## Oldest outstanding", true);
+        writeln!(stdout).unwrap();
+
+        for (index, (entry, days)) in digest.oldest.iter().enumerate() {
+            entry.render(&mut stdout, config, true, index);
+            write_ansi(&mut stdout, Color::Ansi256(243), &format!("  ({} days old)", days), false);
+            writeln!(stdout).unwrap();
+        }
+    }
+
+    stdout.flush().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::scan_string;
+    use std::path::PathBuf;
+
+    #[test]
+    fn digest_test() {
+        let mut before: Vec<Entry> = vec![];
+        scan_string("// todo0 a\n// todo b\n".to_string(), PathBuf::from("foo.rs"), &mut before);
+
+        let mut after: Vec<Entry> = vec![];
+        scan_string("// todo0 a\n// todo@x c\n// todo d\n".to_string(), PathBuf::from("foo.rs"), &mut after);
+
+        let blames = HashMap::from([(Path::new("foo.rs").into(), vec![
+            BlameLine { line: 1, author: "Alice".to_string(), time: 0 },
+            BlameLine { line: 2, author: "Bob".to_string(), time: 9 * DAY },
+        ])]);
+
+        let digest = digest("7d", before, after, &blames, 10 * DAY, 0.6);
+
+        assert_eq!(vec!["c", "d"], digest.added.iter().map(|entry| entry.text.as_str()).collect::<Vec<&str>>());
+        assert_eq!(vec!["b"], digest.resolved.iter().map(|entry| entry.text.as_str()).collect::<Vec<&str>>());
+        assert_eq!(vec![("a", 10), ("c", 1)], digest.oldest.iter().map(|(entry, days)| (entry.text.as_str(), *days)).collect::<Vec<(&str, i64)>>());
+        assert_eq!("2 → 3 TODOs (+1)", digest.trend());
+    }
+}
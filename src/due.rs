@@ -0,0 +1,202 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::Write;
+
+use serde::Serialize;
+use schemars::JsonSchema;
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+
+use crate::config::Config;
+use crate::entries::Entry;
+use crate::render::write_ansi;
+use crate::schema::print_json;
+
+/// A calendar date, stored as days since 1970-01-01
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Date(pub i64);
+
+impl Date {
+    /// Parse a `YYYY-MM-DD` date
+    pub fn parse(date: &str) -> Option<Date> {
+        let mut parts = date.splitn(3, '-');
+        let year: i64 = parts.next()?.parse().ok()?;
+        let month: i64 = parts.next().filter(|month| month.len() == 2)?.parse().ok()?;
+        let day: i64 = parts.next().filter(|day| day.len() == 2)?.parse().ok()?;
+
+        if ! (1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        // Days from civil, see http://howardhinnant.github.io/date_algorithms.html
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        Some(Date(era * 146097 + day_of_era - 719468))
+    }
+
+    /// The date of a Unix timestamp, in UTC
+    pub fn from_timestamp(timestamp: i64) -> Date {
+        Date(timestamp.div_euclid(24 * 60 * 60))
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Civil from days, the inverse of the above
+        let days = self.0 + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Entry {
+    /// The due date from a `due:YYYY-MM-DD` token in the text, e.g. `todo0 release notes due:2026-03-01`
+    pub fn due(&self) -> Option<Date> {
+        self.text.split_whitespace()
+            .find_map(|word| word.strip_prefix("due:"))
+            .and_then(|date| Date::parse(date.trim_end_matches([',', '.', ';', ')'])))
+    }
+}
+
+/// Parse a window like `7d` or `2w` into days
+pub fn parse_window(window: &str) -> Result<i64, String> {
+    let (number, days) = match window.char_indices().last() {
+        Some((index, 'd')) => (&window[..index], 1),
+        Some((index, 'w')) => (&window[..index], 7),
+        _ => (window, 1),
+    };
+
+    number.parse::<i64>()
+        .map(|number| number * days)
+        .map_err(|_| format!("invalid window {}, expected e.g. 7d or 2w", window))
+}
+
+/// Entries due within `days` of `today`, including overdue ones, sorted by the due date
+pub fn due_within(entries: Vec<Entry>, today: Date, days: i64) -> Vec<(Date, Entry)> {
+    let mut due: Vec<(Date, Entry)> = entries.into_iter()
+        .filter_map(|entry| Some((entry.due()?, entry)))
+        .filter(|(date, _)| date.0 <= today.0 + days)
+        .collect();
+
+    due.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| (&a.1.location.file, a.1.location.line).cmp(&(&b.1.location.file, b.1.location.line))));
+
+    due
+}
+
+pub fn render_due(due: &[(Date, Entry)], today: Date, config: &Config) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, "# Due soon", true);
+    write!(stdout, "\n\n").unwrap();
+
+    let mut previous: Option<Date> = None;
+    let mut index = 0;
+
+    for (date, entry) in due {
+        if previous != Some(*date) {
+            index = 0;
+
+            if previous.is_some() {
+                writeln!(stdout).unwrap();
+            }
+
+            let (color, heading) = match date.cmp(&today) {
+                Ordering::Less => (Color::Red, format!("## {} (overdue)", date)),
+                Ordering::Equal => (Color::Red, format!("## {} (today)", date)),
+                Ordering::Greater => (Color::Yellow, format!("## {}", date)),
+            };
+
+            write_ansi(&mut stdout, color, &heading, true);
+            writeln!(stdout).unwrap();
+
+            previous = Some(*date);
+        }
+
+        entry.render(&mut stdout, config, true, index);
+        index += 1;
+    }
+
+    stdout.flush().unwrap();
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DueEntry {
+    /// `YYYY-MM-DD`
+    pub due: String,
+    pub overdue: bool,
+    pub entry: Entry,
+}
+
+/// The shape of `due --format json` output
+#[derive(Serialize, JsonSchema)]
+pub struct DueReport {
+    pub entries: Vec<DueEntry>,
+}
+
+pub fn render_due_json(due: Vec<(Date, Entry)>, today: Date) {
+    let entries = due.into_iter()
+        .map(|(date, entry)| DueEntry { due: date.to_string(), overdue: date < today, entry })
+        .collect();
+
+    print_json(DueReport { entries });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::scan::scan_string;
+
+    #[test]
+    fn date_test() {
+        assert_eq!(Some(Date(0)), Date::parse("1970-01-01"));
+        assert_eq!(Some(Date(20454)), Date::parse("2026-01-01"));
+        assert_eq!("2024-02-29", Date::parse("2024-02-29").unwrap().to_string());
+        assert_eq!("1969-12-31", Date(-1).to_string());
+        assert_eq!(None, Date::parse("2025-02-29"));
+        assert_eq!(None, Date::parse("2025-13-01"));
+        assert_eq!(None, Date::parse("2025-1-01"));
+        assert_eq!(Date(20454), Date::from_timestamp(20454 * 86400 + 3600));
+
+        assert_eq!(Ok(7), parse_window("7d"));
+        assert_eq!(Ok(14), parse_window("2w"));
+        assert_eq!(Ok(3), parse_window("3"));
+        assert!(parse_window("soon").is_err());
+    }
+
+    #[test]
+    fn due_within_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo0 a due:2026-01-20\n// todo b due:2026-01-05.\n// todo c due:2026-03-01\n// todo d\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        let today = Date::parse("2026-01-10").unwrap();
+        let due: Vec<(String, String)> = due_within(entries, today, 14).into_iter()
+            .map(|(date, entry)| (date.to_string(), entry.text))
+            .collect();
+
+        assert_eq!(vec![
+            ("2026-01-05".to_string(), "b due:2026-01-05.".to_string()),
+            ("2026-01-20".to_string(), "a due:2026-01-20".to_string()),
+        ], due);
+    }
+}
@@ -1,21 +1,493 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-#[derive(Debug, PartialEq, Clone)]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Categories, Config, Severity};
+use crate::paths::{normalize, relative};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Location {
-    pub file: PathBuf,
+    /// Shared by all entries found in the same file
+    pub file: Arc<Path>,
     pub line: usize,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl Location {
+    /// The file relative to `root`, always using `/` as the separator
+    pub fn relative_path(&self, root: &Path) -> String {
+        let file = self.file.strip_prefix(root).unwrap_or(&self.file);
+
+        file.to_string_lossy().replace('\\', "/")
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Entry {
     pub text: String,
     pub location: Location,
     pub data: EntryData,
+    /// The person responsible, from `todo(alice)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    /// The unmodified line, only kept with `--raw`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    /// Older than `stale.max_age_days`, according to `git blame`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+    /// In one of the `[severity] informational` paths, so it's listed separately and doesn't
+    /// count toward `--fail-on` and the thresholds
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub informational: bool,
+    /// Whether the entry is a comment in code or a curated list item. Missing in scans saved
+    /// before it was added, which are read as `code`.
+    #[serde(default)]
+    pub source: SourceKind,
+    /// More places the same task applies to, from merged duplicates (e.g. the readme when it's
+    /// copied from todo.md) and `(also at file:line)` references
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also: Vec<Location>,
+    /// The permalink of the location, only set in JSON output when `locations.url` is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The marker, e.g. `fixme` for `FIXME: foo`, when it isn't `todo`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// Where an entry was written
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SourceKind {
+    /// A comment in code, or any other file scanned like code (including org-mode files,
+    /// notebooks, and plugins)
+    #[default]
+    Code,
+    /// A list item in a todo.md file
+    TodoFile,
+    /// A list item under the TODO heading of the readme
+    Readme,
+}
+
+impl SourceKind {
+    /// The name used in JSON, e.g. `todo-file`
+    pub fn name(self) -> &'static str {
+        match self {
+            SourceKind::Code => "code",
+            SourceKind::TodoFile => "todo-file",
+            SourceKind::Readme => "readme",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
 pub enum EntryData {
     Priority(isize),
     Category(String),
     Generic,
+    /// `todo?`: an explicitly deprioritized idea
+    Someday,
+    /// `todo!()` or `unimplemented!()`, which panic when they're reached
+    Unimplemented,
+    /// `dbg!()`, or `unreachable!()`/`panic!()` with a TODO message
+    Marker,
+}
+
+impl EntryData {
+    /// e.g. `priority:-1`, `category:foo`, or `generic`
+    pub fn key(&self) -> String {
+        match self {
+            EntryData::Priority(priority) => format!("priority:{}", priority),
+            EntryData::Category(category) => format!("category:{}", category),
+            EntryData::Generic => "generic".to_string(),
+            EntryData::Someday => "someday".to_string(),
+            EntryData::Unimplemented => "unimplemented".to_string(),
+            EntryData::Marker => "marker".to_string(),
+        }
+    }
+
+    /// Whether this is `category` or one of its subcategories, e.g. `backend/auth` for `backend`
+    pub fn is_in_category(&self, category: &str) -> bool {
+        matches!(self, EntryData::Category(own) if own.strip_prefix(category).is_some_and(|rest| rest.is_empty() || rest.starts_with('/')))
+    }
+}
+
+impl Entry {
+    /// An entry found in code, without an assignee, keyword, or any of the later added details
+    pub fn new(text: String, location: Location, data: EntryData) -> Entry {
+        Entry {
+            text,
+            location,
+            data,
+            assignee: None,
+            raw: None,
+            stale: false,
+            informational: false,
+            source: SourceKind::Code,
+            also: vec![],
+            url: None,
+            keyword: None,
+        }
+    }
+
+    /// The entry's file relative to `root`, always using `/` as the separator
+    pub fn relative_path(&self, root: &Path) -> String {
+        self.location.relative_path(root)
+    }
+
+    /// An identifier that stays the same across scans as long as the entry's file,
+    /// kind, and normalized text don't change. Line numbers aren't part of it, so entries
+    /// keep their IDs when code around them moves. `occurrence` tells apart identical
+    /// entries within the same file.
+    pub fn stable_id(&self, root: &Path, occurrence: usize) -> String {
+        let key = format!("{}\0{}\0{}\0{}", self.relative_path(root), self.data.key(), normalize_text(&self.text), occurrence);
+
+        // FNV-1a, since std's hashers aren't guaranteed to be stable across releases
+        let mut hash: u64 = 0xcbf29ce484222325;
+
+        for byte in key.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+
+        format!("{:016x}", hash)
+    }
+}
+
+/// The text used to tell whether two entries are the same task, by merging duplicates, diffs,
+/// and stable IDs: lowercase, with punctuation (including comment markers like `//` and `*/`)
+/// removed and whitespace collapsed. `// Fix the  API, later.` becomes `fix the api later`.
+pub fn normalize_text(text: &str) -> String {
+    text.to_lowercase()
+        .split(|char: char| ! char.is_alphanumeric())
+        .filter(|word| ! word.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Merge the entries in `files` (e.g. todo.md and the readme) that are listed in more than one
+/// of them, with the same kind and normalized text. The one from the earliest of `files` is kept,
+/// regardless of the order the files were scanned in, with the locations of the others in `also`.
+/// Repeated entries within a single file aren't merged.
+pub fn merge_duplicates(entries: &mut Vec<Entry>, files: &[PathBuf]) {
+    let rank = |file: &Path| files.iter().position(|source| **source == *file);
+    let mut first: HashMap<(EntryData, String), usize> = HashMap::new();
+    let mut merged: Vec<Entry> = Vec::with_capacity(entries.len());
+
+    for entry in entries.drain(..) {
+        if entry.text.is_empty() || ! files.iter().any(|file| **file == *entry.location.file) {
+            merged.push(entry);
+            continue;
+        }
+
+        let key = (entry.data.clone(), normalize_text(&entry.text));
+
+        match first.get(&key).map(|index| &mut merged[*index]) {
+            Some(kept) if kept.location.file != entry.location.file && ! kept.also.iter().any(|location| location.file == entry.location.file) => {
+                let mut entry = entry;
+
+                if rank(&entry.location.file) < rank(&kept.location.file) {
+                    std::mem::swap(kept, &mut entry);
+                }
+
+                kept.also.push(entry.location);
+                kept.also.extend(entry.also);
+            },
+            Some(_) => merged.push(entry),
+            None => {
+                first.insert(key, merged.len());
+                merged.push(entry);
+            },
+        }
+    }
+
+    *entries = merged;
+}
+
+/// Categorize the generic entries by their location, per `[categories] paths`. The files are
+/// relative to `root`.
+pub fn categorize_by_path(entries: &mut [Entry], categories: &Categories, root: &Path) {
+    if categories.paths.is_empty() {
+        return;
+    }
+
+    for entry in entries {
+        if entry.data != EntryData::Generic {
+            continue;
+        }
+
+        if let Some(category) = categories.for_path(&relative(&entry.location.file, root)) {
+            entry.data = EntryData::Category(category.to_string());
+        }
+    }
+}
+
+/// Mark the entries in the `[severity] informational` paths. The files are relative to `root`.
+pub fn mark_informational(entries: &mut [Entry], severity: &Severity, root: &Path) {
+    if severity.informational.is_empty() {
+        return;
+    }
+
+    for entry in entries {
+        entry.informational = severity.is_informational(&relative(&entry.location.file, root));
+    }
+}
+
+/// Move `(also at file:line, ...)` references at the end of the entries' text to their other
+/// locations, for tasks that need changes in several places. The files are relative to `root`.
+pub fn add_references(entries: &mut [Entry], root: &Path) {
+    for entry in entries {
+        let Some((text, references)) = split_references(&entry.text) else {
+            continue;
+        };
+
+        entry.also.extend(references.into_iter().map(|(file, line)| Location {
+            file: normalize(root.join(file)).into(),
+            line,
+        }));
+        entry.text = text.to_string();
+    }
+}
+
+/// Everything done with the scanned entries before they're filtered and rendered: merging the
+/// duplicates in `sources` (todo.md and the readme), removing `[scan] closers`, and applying
+/// references, `[categories] paths`, and `[severity]`. The files are relative to `root`.
+pub fn postprocess(entries: &mut Vec<Entry>, config: &Config, root: &Path, sources: &[PathBuf]) {
+    merge_duplicates(entries, sources);
+
+    // The built-in closers are removed by the scanner
+    if ! config.scan.closers.is_empty() {
+        for entry in entries.iter_mut() {
+            entry.text = crate::scan::strip_closers(&entry.text, &config.scan.closers).to_string();
+        }
+    }
+
+    add_references(entries, root);
+    categorize_by_path(entries, &config.categories, root);
+    mark_informational(entries, &config.severity, root);
+}
+
+/// Make the paths of the entries, including their other locations, relative to `base`
+pub fn make_relative(entries: &mut [Entry], base: &Path) {
+    // Entries from the same file keep sharing their path
+    let mut relatives: HashMap<Arc<Path>, Arc<Path>> = HashMap::new();
+
+    for entry in entries {
+        for location in std::iter::once(&mut entry.location).chain(&mut entry.also) {
+            location.file = relatives.entry(location.file.clone())
+                .or_insert_with_key(|file| relative(file, base).into())
+                .clone();
+        }
+    }
+}
+
+/// The text before the `(also at ...)` references, and the references
+fn split_references(text: &str) -> Option<(&str, Vec<(&str, usize)>)> {
+    let (text, references) = text.strip_suffix(')')?.rsplit_once("(also at ")?;
+
+    let references = references.split(',')
+        .map(|reference| {
+            let (file, line) = reference.trim().rsplit_once(':')?;
+
+            Some((file, line.parse().ok()?))
+        })
+        .collect::<Option<Vec<(&str, usize)>>>()?;
+
+    Some((text.trim_end(), references))
+}
+
+/// Stable IDs for all entries, in the same order
+pub fn stable_ids(entries: &[Entry], root: &Path) -> Vec<String> {
+    let mut occurrences: HashMap<(&Arc<Path>, &EntryData, String), usize> = HashMap::new();
+
+    entries.iter().map(|entry| {
+        let occurrence = occurrences.entry((&entry.location.file, &entry.data, normalize_text(&entry.text))).or_default();
+        *occurrence += 1;
+
+        entry.stable_id(root, *occurrence - 1)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::entry;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn stable_id_test() {
+        let root = Path::new("/project");
+
+        // Moving an entry to another line keeps its ID
+        assert_eq!(
+            entry("/project/a.rs", 1, EntryData::Generic, "abc").stable_id(root, 0),
+            entry("/project/a.rs", 20, EntryData::Generic, "abc").stable_id(root, 0),
+        );
+
+        // The root isn't part of the ID
+        assert_eq!(
+            entry("/project/a.rs", 1, EntryData::Generic, "abc").stable_id(root, 0),
+            entry("/elsewhere/a.rs", 1, EntryData::Generic, "abc").stable_id(Path::new("/elsewhere"), 0),
+        );
+
+        assert_ne!(
+            entry("/project/a.rs", 1, EntryData::Generic, "abc").stable_id(root, 0),
+            entry("/project/a.rs", 1, EntryData::Generic, "abcd").stable_id(root, 0),
+        );
+
+        // Neither are whitespace, case, and punctuation
+        assert_eq!(
+            entry("/project/a.rs", 1, EntryData::Generic, "fix the API.").stable_id(root, 0),
+            entry("/project/a.rs", 1, EntryData::Generic, "Fix  the api").stable_id(root, 0),
+        );
+
+        // Identical entries in the same file get distinct IDs
+        let ids = stable_ids(&[entry("/project/a.rs", 1, EntryData::Generic, "abc"), entry("/project/a.rs", 2, EntryData::Generic, "abc")], root);
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn source_kind_test() {
+        let json = r#"{ "text": "foo", "location": { "file": "todo.md", "line": 1 }, "data": { "kind": "generic" } }"#;
+        assert_eq!(SourceKind::Code, serde_json::from_str::<Entry>(json).unwrap().source);
+
+        assert_eq!("\"todo-file\"", serde_json::to_string(&SourceKind::TodoFile).unwrap());
+        assert_eq!("todo-file", SourceKind::TodoFile.name());
+    }
+
+    #[test]
+    fn is_in_category_test() {
+        assert!(EntryData::Category("api".to_string()).is_in_category("api"));
+        assert!(EntryData::Category("api/v2".to_string()).is_in_category("api"));
+        assert!(! EntryData::Category("apis".to_string()).is_in_category("api"));
+        assert!(! EntryData::Category("api".to_string()).is_in_category("api/v2"));
+        assert!(! EntryData::Generic.is_in_category("api"));
+    }
+
+    #[test]
+    fn normalize_text_test() {
+        assert_eq!("fix the api later", normalize_text("// Fix the  API, later."));
+        assert_eq!("handle utf 8 bom", normalize_text("Handle UTF-8 BOM */"));
+        assert_eq!("přidat překlady", normalize_text("Přidat\tpřeklady!"));
+        assert_eq!("", normalize_text(" -- "));
+    }
+
+    #[test]
+    fn postprocess_test() {
+        let config = Config::parse("[scan]\nclosers = [\"-->\"]\n\n[categories.paths]\n\"src/api\" = \"backend\"\n\n[severity]\ninformational = [\"examples\"]\n").unwrap();
+        let sources = vec![PathBuf::from("/project/todo.md"), PathBuf::from("/project/README.md")];
+
+        let generic = |file: &str, text: &str| entry(file, 1, EntryData::Generic, text);
+        let mut entries = vec![
+            generic("/project/todo.md", "Add tests"),
+            generic("/project/README.md", "add tests"),
+            generic("/project/src/api/a.rs", "foo -->"),
+            generic("/project/examples/b.rs", "bar (also at src/api/a.rs:3)"),
+        ];
+
+        postprocess(&mut entries, &config, Path::new("/project"), &sources);
+
+        assert_eq!(vec!["Add tests", "foo", "bar"], entries.iter().map(|entry| entry.text.as_str()).collect::<Vec<_>>());
+        assert_eq!(PathBuf::from("/project/README.md"), entries[0].also[0].file.to_path_buf());
+        assert_eq!(EntryData::Category("backend".to_string()), entries[1].data);
+        assert!(entries[2].informational);
+        assert_eq!(vec![Location { file: PathBuf::from("/project/src/api/a.rs").into(), line: 3 }], entries[2].also);
+
+        make_relative(&mut entries, Path::new("/project"));
+        assert_eq!(PathBuf::from("src/api/a.rs"), entries[2].also[0].file.to_path_buf());
+    }
+
+    #[test]
+    fn categorize_by_path_test() {
+        let categories = Categories {
+            paths: BTreeMap::from([
+                ("src".to_string(), "code".to_string()),
+                ("src/api/**".to_string(), "backend".to_string()),
+                ("src/*.tsx".to_string(), "frontend".to_string()),
+            ]),
+            ..Categories::default()
+        };
+
+        let generic = |file: &str| entry(file, 1, EntryData::Generic, "foo");
+        let mut entries = vec![
+            generic("/project/src/api/v1/users.rs"),
+            generic("/project/src/App.tsx"),
+            generic("/project/src/lib/App.tsx"),
+            generic("/project/docs/index.md"),
+            entry("/project/src/api/a.rs", 1, EntryData::Category("foo".to_string()), "foo"),
+        ];
+
+        categorize_by_path(&mut entries, &categories, Path::new("/project"));
+
+        assert_eq!(vec![
+            EntryData::Category("backend".to_string()),
+            EntryData::Category("frontend".to_string()),
+            EntryData::Category("code".to_string()),
+            EntryData::Generic,
+            // Entries with a category keep it
+            EntryData::Category("foo".to_string()),
+        ], entries.into_iter().map(|entry| entry.data).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn add_references_test() {
+        let mut entries = vec![
+            entry("/project/src/a.rs", 1, EntryData::Generic, "rename the field (also at src/b.rs:4, docs/api.md:10)"),
+            entry("/project/src/a.rs", 2, EntryData::Generic, "handle errors (see the docs)"),
+            entry("/project/src/a.rs", 3, EntryData::Generic, "foo (also at b.rs)"),
+        ];
+
+        add_references(&mut entries, Path::new("/project"));
+
+        assert_eq!("rename the field", entries[0].text);
+        assert_eq!(vec![
+            Location { file: PathBuf::from("/project/src/b.rs").into(), line: 4 },
+            Location { file: PathBuf::from("/project/docs/api.md").into(), line: 10 },
+        ], entries[0].also);
+
+        // Not valid references
+        assert_eq!("handle errors (see the docs)", entries[1].text);
+        assert_eq!("foo (also at b.rs)", entries[2].text);
+        assert!(entries[1].also.is_empty() && entries[2].also.is_empty());
+    }
+
+    #[test]
+    fn merge_duplicates_test() {
+        let mut entries = vec![
+            entry("/project/todo.md", 3, EntryData::Generic, "Add  rate limiting."),
+            entry("/project/todo.md", 4, EntryData::Generic, "fix login"),
+            entry("/project/todo.md", 5, EntryData::Generic, "fix login"),
+            entry("/project/README.md", 12, EntryData::Generic, "add rate limiting"),
+            entry("/project/src/a.rs", 1, EntryData::Generic, "add rate limiting"),
+        ];
+
+        merge_duplicates(&mut entries, &[PathBuf::from("/project/todo.md"), PathBuf::from("/project/README.md")]);
+
+        let entries: Vec<(&str, usize, Vec<usize>)> = entries.iter()
+            .map(|entry| (entry.text.as_str(), entry.location.line, entry.also.iter().map(|location| location.line).collect()))
+            .collect();
+
+        // Code comments are separate tasks, even with the same text
+        assert_eq!(vec![
+            ("Add  rate limiting.", 3, vec![12]),
+            ("fix login", 4, vec![]),
+            ("fix login", 5, vec![]),
+            ("add rate limiting", 1, vec![]),
+        ], entries);
+
+        // The todo.md entry is kept even when the readme is scanned first, e.g. when reading from git
+        let mut entries = vec![
+            entry("/project/README.md", 12, EntryData::Generic, "add rate limiting"),
+            entry("/project/todo.md", 3, EntryData::Generic, "Add  rate limiting."),
+        ];
+
+        merge_duplicates(&mut entries, &[PathBuf::from("/project/todo.md"), PathBuf::from("/project/README.md")]);
+
+        assert_eq!(1, entries.len());
+        assert_eq!((3, 12), (entries[0].location.line, entries[0].also[0].line));
+    }
 }
@@ -0,0 +1,80 @@
+use std::path::{Component, Path, PathBuf};
+
+use ignore::Match;
+
+use crate::gitignore::Ignores;
+use crate::paths::canonicalize;
+use crate::scan::{matches_exclude, read_text};
+
+/// Print the chain of decisions `scan_dir` makes on its way to `target`,
+/// similar to `git check-ignore -v`.
+pub fn explain(target: &Path, paths: &[PathBuf], excludes: &[PathBuf]) {
+    println!("{}", target.to_string_lossy());
+
+    let Ok(realpath) = canonicalize(target) else {
+        println!("  Path does not exist");
+        println!("Result: not scanned");
+        return;
+    };
+
+    let root = paths.iter()
+        .filter_map(|path| canonicalize(path).ok())
+        .find(|root| realpath.starts_with(root));
+
+    let Some(root) = root else {
+        println!("  Not inside any of the scanned paths: {:?}", paths);
+        println!("Result: not scanned");
+        return;
+    };
+
+    let components: Vec<Component> = realpath.strip_prefix(&root).unwrap().components().collect();
+    let mut current = root.clone();
+    let mut ignores = Ignores::for_dir(&root);
+
+    println!("  {}: scan root", current.to_string_lossy());
+
+    for component in components {
+        current.push(component);
+
+        if component.as_os_str().to_string_lossy().starts_with('.') {
+            println!("  {}: hidden path", current.to_string_lossy());
+            println!("Result: not scanned");
+            return;
+        }
+
+        if let Some(exclude) = matching_exclude(&current, excludes) {
+            println!("  {}: excluded by exclude {}", current.to_string_lossy(), exclude.to_string_lossy());
+            println!("Result: not scanned");
+            return;
+        }
+
+        match ignores.matched(&current, current.is_dir()) {
+            Match::Ignore(glob) => {
+                println!("  {}: excluded by {}", current.to_string_lossy(), ignores.describe(glob));
+                println!("Result: not scanned");
+                return;
+            },
+            Match::Whitelist(glob) => println!("  {}: visited, re-included by {}", current.to_string_lossy(), ignores.describe(glob)),
+            Match::None => println!("  {}: visited", current.to_string_lossy()),
+        }
+
+        if current.is_dir() {
+            ignores = ignores.with_dir(&current);
+        }
+    }
+
+    if realpath.is_dir() {
+        println!("Result: walked");
+    } else if read_text(&realpath).is_err() {
+        // `scan_file` silently skips files that can't be decoded
+        println!("Result: visited, but skipped (not UTF-8 or UTF-16 with a BOM)");
+    } else {
+        println!("Result: scanned");
+    }
+}
+
+fn matching_exclude<'a>(path: &Path, excludes: &'a [PathBuf]) -> Option<&'a PathBuf> {
+    let realpath = canonicalize(path).ok()?;
+
+    excludes.iter().find(|exclude| **exclude == realpath || matches_exclude(path, exclude))
+}
@@ -0,0 +1,557 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{CONFIG_FILES, Config};
+use crate::entries::{Entry, make_relative, postprocess};
+use crate::gitignore::IgnoreCache;
+use crate::paths::normalize;
+use crate::scan::{Stats, decode_text, is_scannable, resolve_exclude, scan_file};
+use crate::scanners::{Plugin, Readme, Scanners, TodoFile};
+
+/// A line added in a diff, with its path relative to the repository root
+pub struct AddedLine {
+    pub file: PathBuf,
+    pub line: usize,
+    pub text: String,
+}
+
+/// Who last changed a line, from `git blame`
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlameLine {
+    pub line: usize,
+    pub author: String,
+    /// Unix timestamp of the commit's author date
+    pub time: i64,
+}
+
+/// Run git in `dir` and return its stdout
+pub fn git(dir: &Path, args: &[&str]) -> io::Result<String> {
+    Ok(String::from_utf8_lossy(&git_bytes(dir, args)?).to_string())
+}
+
+pub fn git_bytes(dir: &Path, args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(["-c", "core.quotePath=false"])
+        .args(args)
+        .current_dir(dir)
+        .output()?;
+
+    if ! output.status.success() {
+        return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+
+    Ok(output.stdout)
+}
+
+pub fn toplevel(dir: &Path) -> io::Result<PathBuf> {
+    // Git uses forward slashes on Windows too
+    Ok(normalize(PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim())))
+}
+
+/// The URL of the `origin` remote
+pub fn origin_url(dir: &Path) -> io::Result<String> {
+    Ok(git(dir, &["remote", "get-url", "origin"])?.trim().to_string())
+}
+
+/// The full sha of HEAD
+pub fn head_commit(dir: &Path) -> io::Result<String> {
+    Ok(git(dir, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// The web URL that files at `commit` are under on the forge hosting `remote`, e.g.
+/// `https://github.com/org/repo/blob/<sha>/` for `git@github.com:org/repo.git`. GitLab
+/// uses `/-/blob/` instead. Returns `None` for remotes that aren't URLs, like local paths.
+pub fn permalink_base(remote: &str, commit: &str) -> Option<String> {
+    let remote = remote.trim().trim_end_matches('/');
+    let remote = remote.strip_suffix(".git").unwrap_or(remote);
+
+    let (host, path) = if let Some(rest) = remote.strip_prefix("https://").or_else(|| remote.strip_prefix("http://")) {
+        let (host, path) = rest.split_once('/')?;
+
+        // Credentials, e.g. `https://token@github.com/org/repo`
+        (host.rsplit('@').next()?, path)
+    } else if let Some(rest) = remote.strip_prefix("ssh://") {
+        let (host, path) = rest.split_once('/')?;
+
+        // The SSH port isn't the web one
+        (host.rsplit('@').next()?.split(':').next()?, path)
+    } else {
+        // scp-like syntax, e.g. `git@github.com:org/repo`
+        let (host, path) = remote.split_once(':')?;
+
+        if host.contains('/') {
+            return None;
+        }
+
+        (host.rsplit('@').next()?, path.trim_start_matches('/'))
+    };
+
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+
+    let blob = if host.contains("gitlab") { "-/blob" } else { "blob" };
+
+    Some(format!("https://{}/{}/{}/{}/", host, path, blob, commit))
+}
+
+/// The best common ancestor of two commits
+pub fn merge_base(dir: &Path, a: &str, b: &str) -> io::Result<String> {
+    Ok(git(dir, &["merge-base", a, b])?.trim().to_string())
+}
+
+/// The last commit on `git_ref` (e.g. `HEAD`) made before a Unix timestamp, if there is one
+pub fn commit_before(dir: &Path, git_ref: &str, timestamp: i64) -> io::Result<Option<String>> {
+    let commit = git(dir, &["rev-list", "-1", &format!("--before={}", timestamp), git_ref])?;
+
+    Ok(Some(commit.trim().to_string()).filter(|commit| ! commit.is_empty()))
+}
+
+/// Files with staged changes (excluding deletions), relative to the repository root
+pub fn staged_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    Ok(git(dir, &["diff", "--cached", "--name-only", "--diff-filter=ACMR", "-z"])?
+        .split('\0')
+        .filter(|file| ! file.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Files changed in the working tree since the branch diverged from `git_ref` (excluding
+/// deletions), and untracked files that aren't ignored, as absolute paths
+pub fn changed_files(dir: &Path, git_ref: &str) -> io::Result<Vec<PathBuf>> {
+    let root = toplevel(dir)?;
+    let base = merge_base(dir, git_ref, "HEAD")?;
+    let changed = git(dir, &["diff", "--name-only", "--diff-filter=ACMR", "-z", &base])?;
+    let untracked = git(dir, &["ls-files", "--others", "--exclude-standard", "--full-name", "-z"])?;
+
+    Ok(changed.split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|file| ! file.is_empty())
+        .map(|file| normalize(root.join(file)))
+        .collect())
+}
+
+/// Content of a file in the git index. `file` is relative to the repository root.
+pub fn index_content(dir: &Path, file: &Path) -> io::Result<String> {
+    show(dir, &format!(":{}", file.to_string_lossy()))
+}
+
+/// Files in the tree of `git_ref`, relative to the repository root
+pub fn ref_files(dir: &Path, git_ref: &str) -> io::Result<Vec<PathBuf>> {
+    Ok(git(dir, &["ls-tree", "-r", "-z", "--full-tree", "--name-only", git_ref])?
+        .split('\0')
+        .filter(|file| ! file.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Content of a file at `git_ref`. `file` is relative to the repository root.
+pub fn ref_content(dir: &Path, git_ref: &str, file: &Path) -> io::Result<String> {
+    show(dir, &format!("{}:{}", git_ref, file.to_string_lossy()))
+}
+
+fn show(dir: &Path, object: &str) -> io::Result<String> {
+    decode_text(git_bytes(dir, &["show", object])?).map(|(text, _)| text)
+}
+
+/// Scan the staged content of files with staged changes, instead of their content
+/// in the working tree. This includes partially staged files. `sources` (todo.md and the
+/// readme) are scanned even when they're outside of `paths`.
+pub fn scan_staged(dir: &Path, paths: &[PathBuf], excludes: &[PathBuf], sources: &[PathBuf], scanners: &Scanners, entries: &mut Vec<Entry>, stats: &mut Stats) -> io::Result<()> {
+    let root = toplevel(dir)?;
+    let mut ignores = IgnoreCache::default();
+
+    for file in staged_files(dir)? {
+        let path = normalize(root.join(&file));
+
+        if ! sources.contains(&path) && ! is_scannable(&path, paths, excludes, &mut ignores) {
+            continue;
+        }
+
+        stats.add_file(path.to_string_lossy().to_string());
+
+        // Like in `scan_file`, files that can't be decoded are skipped
+        let Ok(content) = index_content(dir, &file) else {
+            continue;
+        };
+
+        scanners.scan(content, &path, entries);
+    }
+
+    Ok(())
+}
+
+/// Scan the `changed` files (see [`changed_files`]) as they are in the working tree.
+/// `sources` are scanned like in [`scan_staged`].
+pub fn scan_changed(changed: &[PathBuf], paths: &[PathBuf], excludes: &[PathBuf], sources: &[PathBuf], scanners: &Scanners, entries: &mut Vec<Entry>, stats: &mut Stats) -> io::Result<()> {
+    let mut ignores = IgnoreCache::default();
+
+    for path in changed {
+        if ! sources.contains(path) && ! is_scannable(path, paths, excludes, &mut ignores) {
+            continue;
+        }
+
+        stats.add_file(path.to_string_lossy().to_string());
+
+        if scan_file(path, scanners, entries)? {
+            stats.add_transcoded_file(path.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Scan the files as they are at `git_ref`, without touching the working tree. Paths
+/// in the entries are the same as they'd be when scanning the working tree. `sources` are
+/// scanned like in [`scan_staged`].
+pub fn scan_ref(dir: &Path, git_ref: &str, paths: &[PathBuf], excludes: &[PathBuf], sources: &[PathBuf], scanners: &Scanners, entries: &mut Vec<Entry>) -> io::Result<()> {
+    let root = toplevel(dir)?;
+    let mut ignores = IgnoreCache::default();
+
+    for file in ref_files(dir, git_ref)? {
+        let path = normalize(root.join(&file));
+
+        if ! sources.contains(&path) && ! is_scannable(&path, paths, excludes, &mut ignores) {
+            continue;
+        }
+
+        let Ok(content) = ref_content(dir, git_ref, &file) else {
+            continue;
+        };
+
+        scanners.scan(content, &path, entries);
+    }
+
+    Ok(())
+}
+
+/// The repository's config (todo.toml or .todorc) at `git_ref`, or the default one
+pub fn ref_config(root: &Path, git_ref: &str) -> Result<Config, String> {
+    let config = CONFIG_FILES.iter()
+        .find_map(|file| Some((file, ref_content(root, git_ref, Path::new(file)).ok()?)));
+
+    match config {
+        Some((file, content)) => Config::parse(&content).map_err(|err| format!("invalid {}: {}", file, err)),
+        None => Ok(Config::default()),
+    }
+}
+
+/// The scanners for the repository at `root` with `config`, along with the markdown sources
+/// (todo.md and the readme) and the excludes, like the CLI's defaults
+fn repository_scanners(root: &Path, config: &Config, plugins: &[Plugin]) -> Result<(Scanners, Vec<PathBuf>, Vec<PathBuf>), String> {
+    let todo_file = root.join("todo.md");
+    let readme = root.join("README.md");
+    let mut sources: Vec<PathBuf> = vec![];
+    let mut scanners = Scanners::new(config.scan.matcher()?);
+
+    for plugin in plugins {
+        scanners.register(plugin.clone());
+    }
+
+    if config.sources.todo_file {
+        scanners.register(TodoFile { path: todo_file.clone() });
+        sources.push(todo_file);
+    }
+
+    if config.sources.readme {
+        scanners.register(Readme { path: readme.clone() });
+        sources.push(readme);
+    }
+
+    let excludes: Vec<PathBuf> = ["node_modules", "vendor"].into_iter()
+        .chain(config.scan.skipped_files())
+        .map(|exclude| resolve_exclude(root, exclude))
+        .collect();
+
+    Ok((scanners, sources, excludes))
+}
+
+/// Scan the whole repository at `root` as it is at `git_ref`, like scanning its working tree
+/// with `config`, with the paths relative to `root`. Plugins aren't run, since the code may
+/// not be trusted (e.g. a pull request).
+pub fn scan_repository(root: &Path, git_ref: &str, config: &Config) -> Result<Vec<Entry>, String> {
+    let (scanners, sources, excludes) = repository_scanners(root, config, &[])?;
+
+    let mut entries: Vec<Entry> = vec![];
+    scan_ref(root, git_ref, &[root.to_path_buf()], &excludes, &sources, &scanners, &mut entries).map_err(|err| err.to_string())?;
+
+    postprocess(&mut entries, config, root, &sources);
+    make_relative(&mut entries, root);
+
+    Ok(entries)
+}
+
+/// Scan the lines added in `git diff <diff_args>` (e.g. `--cached` for the staged changes) in
+/// the repository at `root`, like scanning its working tree with `config`, with the paths
+/// relative to `root`
+pub fn scan_added(root: &Path, diff_args: &[&str], config: &Config) -> Result<Vec<Entry>, String> {
+    let (scanners, sources, excludes) = repository_scanners(root, config, &config.plugins)?;
+
+    let mut entries: Vec<Entry> = vec![];
+    scan_added_lines(root, diff_args, &[root.to_path_buf()], &excludes, &sources, &scanners, &mut entries).map_err(|err| err.to_string())?;
+
+    postprocess(&mut entries, config, root, &sources);
+    make_relative(&mut entries, root);
+
+    Ok(entries)
+}
+
+/// The added lines in `git diff -U0 <diff_args>`
+pub fn added_lines(dir: &Path, diff_args: &[&str]) -> io::Result<Vec<AddedLine>> {
+    let diff = git(dir, &[&["diff", "-U0", "--no-color", "--no-ext-diff"], diff_args].concat())?;
+
+    Ok(parse_added_lines(&diff))
+}
+
+/// Parse the added lines out of a diff with no context lines
+pub fn parse_added_lines(diff: &str) -> Vec<AddedLine> {
+    let mut lines: Vec<AddedLine> = vec![];
+    let mut file: Option<PathBuf> = None;
+    let mut line_num: usize = 0;
+
+    // The lines left in the current hunk, on the old and the new side. Within a hunk, an
+    // added line starting with `++ ` looks like a file header.
+    let mut old_left: usize = 0;
+    let mut new_left: usize = 0;
+
+    for line in diff.lines() {
+        if old_left > 0 || new_left > 0 {
+            match line.chars().next() {
+                Some('+') => {
+                    if let Some(file) = &file {
+                        lines.push(AddedLine {
+                            file: file.clone(),
+                            line: line_num,
+                            text: line[1..].to_string(),
+                        });
+                    }
+
+                    line_num += 1;
+                    new_left = new_left.saturating_sub(1);
+                },
+                Some('-') => old_left = old_left.saturating_sub(1),
+                // `\ No newline at end of file`
+                Some('\\') => {},
+                _ => {
+                    line_num += 1;
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                },
+            }
+
+            continue;
+        }
+
+        if let Some(path) = line.strip_prefix("+++ ") {
+            // Deleted files have `/dev/null` as the new path
+            file = path.strip_prefix("b/").map(PathBuf::from);
+
+            continue;
+        }
+
+        if let Some(hunk) = line.strip_prefix("@@ ") {
+            // @@ -old_start,old_count +new_start,new_count @@, where the count is 1 if omitted
+            let range = |sign: char| hunk.split_whitespace()
+                .find_map(|range| range.strip_prefix(sign))
+                .map(|range| range.split_once(',').unwrap_or((range, "1")))
+                .map(|(start, count)| (start.parse::<usize>().unwrap_or(0), count.parse::<usize>().unwrap_or(0)))
+                .unwrap_or((0, 0));
+
+            (_, old_left) = range('-');
+            (line_num, new_left) = range('+');
+        }
+    }
+
+    lines
+}
+
+/// Blame every committed line of `file`
+pub fn blame(dir: &Path, file: &Path) -> io::Result<Vec<BlameLine>> {
+    Ok(parse_blame(&git(dir, &["blame", "--line-porcelain", "--", &file.to_string_lossy()])?))
+}
+
+/// Parse the output of `git blame --line-porcelain`. Uncommitted lines are skipped.
+pub fn parse_blame(output: &str) -> Vec<BlameLine> {
+    let mut lines: Vec<BlameLine> = vec![];
+    let mut current: Option<BlameLine> = None;
+    let mut committed = false;
+
+    for line in output.lines() {
+        // The content of the line ends each entry
+        if line.starts_with('\t') {
+            if let Some(blame) = current.take().filter(|_| committed) {
+                lines.push(blame);
+            }
+
+            continue;
+        }
+
+        match current.as_mut() {
+            None => {
+                // <sha> <original line> <final line> [<lines in group>]
+                let mut parts = line.split_whitespace();
+                let sha = parts.next().unwrap_or_default();
+                committed = sha.chars().any(|char| char != '0');
+
+                if let Some(line) = parts.nth(1).and_then(|line| line.parse().ok()) {
+                    current = Some(BlameLine { line, author: String::new(), time: 0 });
+                }
+            },
+            Some(blame) => {
+                if let Some(author) = line.strip_prefix("author ") {
+                    blame.author = author.to_string();
+                } else if let Some(time) = line.strip_prefix("author-time ") {
+                    blame.time = time.parse().unwrap_or(0);
+                }
+            },
+        }
+    }
+
+    lines
+}
+
+/// Scan only the lines added in `git diff <diff_args>` to the files that would be scanned,
+/// using absolute paths in the entries. `sources` are scanned like in [`scan_staged`].
+pub fn scan_added_lines(dir: &Path, diff_args: &[&str], paths: &[PathBuf], excludes: &[PathBuf], sources: &[PathBuf], scanners: &Scanners, entries: &mut Vec<Entry>) -> io::Result<()> {
+    let root = toplevel(dir)?;
+    let mut ignores = IgnoreCache::default();
+
+    for added in added_lines(dir, diff_args)? {
+        let file = normalize(root.join(&added.file));
+
+        if ! sources.contains(&file) && ! is_scannable(&file, paths, excludes, &mut ignores) {
+            continue;
+        }
+
+        let mut line_entries: Vec<Entry> = vec![];
+        scanners.scan(added.text, &file, &mut line_entries);
+
+        for mut entry in line_entries {
+            entry.location.line = added.line;
+            entries.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::entries::{EntryData, SourceKind};
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn blame_test() {
+        let output = "1111111111111111111111111111111111111111 1 1 1
+author Alice
+author-mail <alice@example.com>
+author-time 1700000000
+author-tz +0100
+summary Add foo
+filename foo.rs
+\t// todo0 foo
+0000000000000000000000000000000000000000 2 2 1
+author Not Committed Yet
+author-time 1800000000
+filename foo.rs
+\t// todo1 bar
+2222222222222222222222222222222222222222 5 3 1
+author Bob
+author-time 1600000000
+filename foo.rs
+\tbaz();
+";
+
+        assert_eq!(vec![
+            BlameLine { line: 1, author: "Alice".to_string(), time: 1700000000 },
+            BlameLine { line: 3, author: "Bob".to_string(), time: 1600000000 },
+        ], parse_blame(output));
+    }
+
+    #[test]
+    fn permalink_base_test() {
+        let github = Some("https://github.com/org/repo/blob/abc/".to_string());
+
+        assert_eq!(github, permalink_base("git@github.com:org/repo.git", "abc"));
+        assert_eq!(github, permalink_base("https://github.com/org/repo.git\n", "abc"));
+        assert_eq!(github, permalink_base("https://token@github.com/org/repo", "abc"));
+        assert_eq!(github, permalink_base("ssh://git@github.com:22/org/repo.git", "abc"));
+        assert_eq!(Some("https://gitlab.com/group/sub/repo/-/blob/abc/".to_string()), permalink_base("git@gitlab.com:group/sub/repo.git", "abc"));
+        assert_eq!(None, permalink_base("/srv/git/repo.git", "abc"));
+        assert_eq!(None, permalink_base("../repo", "abc"));
+    }
+
+    #[test]
+    fn added_lines_test() {
+        let diff = r#"diff --git a/foo.rs b/foo.rs
+index 1111111..2222222 100644
+--- a/foo.rs
++++ b/foo.rs
+@@ -2,0 +3,2 @@ fn main() {
++    // todo0 foo
++    bar();
+@@ -10 +12 @@ fn baz() {
+-    old();
++    // todo@abc new
+diff --git a/counter.c b/counter.c
+--- a/counter.c
++++ b/counter.c
+@@ -5,0 +6,2 @@ int main() {
++++ i; // todo1 overflow
++    return i;
+diff --git a/deleted.rs b/deleted.rs
+deleted file mode 100644
+--- a/deleted.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-// todo0 deleted
+"#;
+
+        let lines = parse_added_lines(diff);
+
+        assert_eq!(5, lines.len());
+
+        assert_eq!(PathBuf::from("foo.rs"), lines[0].file);
+        assert_eq!(3, lines[0].line);
+        assert_eq!("    // todo0 foo", lines[0].text);
+
+        assert_eq!(4, lines[1].line);
+        assert_eq!("    bar();", lines[1].text);
+
+        assert_eq!(12, lines[2].line);
+        assert_eq!("    // todo@abc new", lines[2].text);
+
+        // `++ i` isn't a file header
+        assert_eq!(PathBuf::from("counter.c"), lines[3].file);
+        assert_eq!(6, lines[3].line);
+        assert_eq!("++ i; // todo1 overflow", lines[3].text);
+        assert_eq!(PathBuf::from("counter.c"), lines[4].file);
+        assert_eq!(7, lines[4].line);
+    }
+
+    #[test]
+    fn scan_added_test() {
+        let dir = TempDir::new("added");
+        git(&dir, &["init", "--quiet"]).unwrap();
+        let root = toplevel(&dir).unwrap();
+
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("generated")).unwrap();
+        fs::write(root.join("src").join("main.rs"), "// REVIEW0 check this\n// todo0 foo\n").unwrap();
+        fs::write(root.join("generated").join("api.rs"), "// todo0 bar\n").unwrap();
+        fs::write(root.join("todo.md"), "- todo0 baz\n").unwrap();
+        git(&root, &["add", "--all"]).unwrap();
+
+        let config = Config::parse("[scan]\nkeywords = [\"review\"]\nskip = [\"generated\"]\n").unwrap();
+        let entries = scan_added(&root, &["--cached"], &config).unwrap();
+
+        assert_eq!(vec![
+            (PathBuf::from("src").join("main.rs"), 1, Some("review"), &EntryData::Priority(0), SourceKind::Code),
+            (PathBuf::from("src").join("main.rs"), 2, None, &EntryData::Priority(0), SourceKind::Code),
+            (PathBuf::from("todo.md"), 1, None, &EntryData::Priority(0), SourceKind::TodoFile),
+        ], entries.iter().map(|entry| (entry.location.file.to_path_buf(), entry.location.line, entry.keyword.as_deref(), &entry.data, entry.source)).collect::<Vec<_>>());
+    }
+}
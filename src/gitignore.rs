@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder, Glob, gitconfig_excludes_path};
+
+/// The gitignore rules that apply to the entries of a directory, following git: the global
+/// excludes file (`core.excludesFile`), `.git/info/exclude`, and the .gitignore files from the
+/// repository root down to the directory. Rules in deeper .gitignore files take precedence.
+#[derive(Clone, Default)]
+pub struct Ignores {
+    /// Lowest precedence first
+    rules: Vec<Arc<Rules>>,
+}
+
+/// The rules of one file
+struct Rules {
+    file: PathBuf,
+    gitignore: Gitignore,
+    /// The line number of each pattern, by [`Glob::original`]
+    lines: HashMap<String, usize>,
+}
+
+impl Ignores {
+    /// The rules for the entries of `dir`. Outside of a git repository, only the .gitignore
+    /// in `dir` itself applies.
+    pub fn for_dir(dir: &Path) -> Ignores {
+        let mut ignores = Ignores::default();
+
+        let Some(repository) = dir.ancestors().find(|ancestor| ancestor.join(".git").exists()) else {
+            return ignores.with_dir(dir);
+        };
+
+        if let Some(global) = gitconfig_excludes_path() {
+            ignores = ignores.with_file(repository, &global);
+        }
+
+        if let Some(git_dir) = git_dir(repository) {
+            ignores = ignores.with_file(repository, &git_dir.join("info").join("exclude"));
+        }
+
+        let mut dirs: Vec<&Path> = dir.ancestors().take_while(|ancestor| ancestor.starts_with(repository)).collect();
+        dirs.reverse();
+
+        for dir in dirs {
+            ignores = ignores.with_dir(dir);
+        }
+
+        ignores
+    }
+
+    /// These rules, along with the .gitignore in `dir` (a subdirectory), for its entries
+    pub fn with_dir(&self, dir: &Path) -> Ignores {
+        self.with_file(dir, &dir.join(".gitignore"))
+    }
+
+    fn with_file(&self, root: &Path, file: &Path) -> Ignores {
+        let mut ignores = self.clone();
+
+        let Ok(content) = fs::read_to_string(file) else {
+            return ignores;
+        };
+
+        let mut builder = GitignoreBuilder::new(root);
+        let mut lines: HashMap<String, usize> = HashMap::new();
+
+        for (index, line) in content.lines().enumerate() {
+            // A byte order mark is ignored, like git does
+            let line = if index == 0 { line.trim_start_matches('\u{feff}') } else { line };
+
+            // Invalid lines are skipped, like git does
+            if builder.add_line(Some(file.to_path_buf()), line).is_ok() {
+                // Trailing whitespace isn't part of the pattern, unless it's escaped. When a
+                // pattern is repeated, the last occurrence is the one that matches.
+                let original = if line.ends_with("\\ ") { line } else { line.trim_end() };
+                lines.insert(original.to_string(), index + 1);
+            }
+        }
+
+        if let Ok(gitignore) = builder.build() {
+            ignores.rules.push(Arc::new(Rules { file: file.to_path_buf(), gitignore, lines }));
+        }
+
+        ignores
+    }
+
+    /// The rule deciding whether `path` (an entry of the directory) is ignored, if any. A
+    /// `Whitelist` match is a `!pattern` re-including it.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Match<&Glob> {
+        self.rules.iter().rev()
+            .map(|rules| rules.gitignore.matched(path, is_dir))
+            .find(|matched| ! matched.is_none())
+            .unwrap_or(Match::None)
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.matched(path, is_dir).is_ignore()
+    }
+
+    /// `file:line:pattern` for the rule `glob` (matched by these rules), like `git check-ignore -v`
+    pub fn describe(&self, glob: &Glob) -> String {
+        let line = self.rules.iter()
+            .find(|rules| glob.from() == Some(rules.file.as_path()))
+            .and_then(|rules| rules.lines.get(glob.original()));
+
+        match (glob.from(), line) {
+            (Some(file), Some(line)) => format!("{}:{}:{}", file.to_string_lossy(), line, glob.original()),
+            _ => glob.original().to_string(),
+        }
+    }
+}
+
+/// The git directory of the repository at `repository`. In worktrees and submodules, `.git`
+/// is a file pointing to it.
+fn git_dir(repository: &Path) -> Option<PathBuf> {
+    let git = repository.join(".git");
+
+    if git.is_dir() {
+        return Some(git);
+    }
+
+    let gitdir = fs::read_to_string(&git).ok()?;
+    let gitdir = gitdir.trim().strip_prefix("gitdir:")?.trim();
+
+    Some(repository.join(gitdir))
+}
+
+/// The rules of each directory, loaded once, for checking files found outside of a walk (e.g.
+/// in the git index). Within a walk, [`Ignores::with_dir`] is used directly.
+#[derive(Default)]
+pub struct IgnoreCache {
+    dirs: HashMap<PathBuf, Ignores>,
+}
+
+impl IgnoreCache {
+    /// The rules for the entries of `dir`, which is the scan root `root` or below it
+    pub fn get(&mut self, root: &Path, dir: &Path) -> Ignores {
+        if let Some(ignores) = self.dirs.get(dir) {
+            return ignores.clone();
+        }
+
+        let ignores = match dir.parent() {
+            Some(parent) if dir != root && dir.starts_with(root) => self.get(root, parent).with_dir(dir),
+            _ => Ignores::for_dir(dir),
+        };
+
+        self.dirs.insert(dir.to_path_buf(), ignores.clone());
+
+        ignores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn ignores_test() {
+        let dir = TempDir::new("gitignore");
+        fs::create_dir_all(dir.join(".git").join("info")).unwrap();
+        fs::create_dir_all(dir.join("src").join("generated")).unwrap();
+
+        fs::write(dir.join(".gitignore"), "*.log\n!keep.log\nbuild/\n/dist\nsrc/**/*.tmp\n").unwrap();
+        fs::write(dir.join("src").join(".gitignore"), "generated/*\n!generated/api.rs\n").unwrap();
+        fs::write(dir.join(".git").join("info").join("exclude"), "local.rs\n").unwrap();
+
+        let root = Ignores::for_dir(&dir);
+        let src = root.with_dir(&dir.join("src"));
+
+        assert!(root.is_ignored(&dir.join("debug.log"), false));
+        assert!(! root.is_ignored(&dir.join("keep.log"), false));
+        assert!(root.is_ignored(&dir.join("local.rs"), false));
+
+        // Trailing slashes only match directories, leading ones only the root
+        assert!(root.is_ignored(&dir.join("build"), true));
+        assert!(! root.is_ignored(&dir.join("build"), false));
+        assert!(root.is_ignored(&dir.join("dist"), true));
+        assert!(! src.is_ignored(&dir.join("src").join("dist"), true));
+
+        assert!(src.is_ignored(&dir.join("src").join("a").join("b.tmp"), false));
+        assert!(src.is_ignored(&dir.join("src").join("generated").join("models.rs"), false));
+        assert!(! src.is_ignored(&dir.join("src").join("generated").join("api.rs"), false));
+
+        // The cache builds the same rules, one directory at a time
+        let mut cache = IgnoreCache::default();
+        assert!(! cache.get(&dir, &dir.join("src")).is_ignored(&dir.join("src").join("generated").join("api.rs"), false));
+        assert!(cache.get(&dir, &dir.join("src")).is_ignored(&dir.join("src").join("generated").join("models.rs"), false));
+    }
+
+    #[test]
+    fn describe_test() {
+        let dir = TempDir::new("describe");
+        fs::write(dir.join(".gitignore"), "*.log\r\n# build output\r\nbuild/  \r\n*.log\r\n").unwrap();
+
+        let ignores = Ignores::for_dir(&dir);
+        let describe = |path: &str, is_dir: bool| match ignores.matched(&dir.join(path), is_dir) {
+            Match::Ignore(glob) | Match::Whitelist(glob) => ignores.describe(glob),
+            Match::None => String::new(),
+        };
+
+        // The last occurrence of a repeated pattern is the one that matches
+        assert_eq!(format!("{}:4:*.log", dir.join(".gitignore").to_string_lossy()), describe("debug.log", false));
+        assert_eq!(format!("{}:3:build/", dir.join(".gitignore").to_string_lossy()), describe("build", true));
+    }
+}
@@ -0,0 +1,91 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use todos::config::{CONFIG_FILES, Config};
+use todos::entries::{EntryData, SourceKind};
+use todos::git;
+use todos::render::{Terminal, print_rendered};
+
+pub enum HookKind {
+    PreCommit,
+    PrePush,
+}
+
+/// Write a git hook running `todos hook check` into the repository containing `dir`
+pub fn install(dir: &Path, kind: HookKind, force: bool) -> Result<PathBuf, Box<dyn Error>> {
+    // Respects core.hooksPath and worktrees
+    let hooks_dir = PathBuf::from(git::git(dir, &["rev-parse", "--git-path", "hooks"])?.trim());
+    let mut hooks_dir = if hooks_dir.is_absolute() { hooks_dir } else { dir.join(hooks_dir) };
+
+    let (name, check_args) = match kind {
+        HookKind::PreCommit => ("pre-commit", ""),
+        HookKind::PrePush => ("pre-push", " --base '@{upstream}'"),
+    };
+
+    fs::create_dir_all(&hooks_dir)?;
+    hooks_dir.push(name);
+    let hook = hooks_dir;
+
+    if hook.exists() && ! force {
+        return Err(format!("{} already exists, use --force to overwrite it", hook.to_string_lossy()).into());
+    }
+
+    let executable = std::env::current_exe()?;
+    let script = format!(
+        "#!/bin/sh\n# Installed by `todos hook install`. Blocks {} that add priority TODOs, todo!() calls, or dbg!() leftovers.\nexec \"{}\" hook check{}\n",
+        match kind { HookKind::PreCommit => "commits", HookKind::PrePush => "pushes" },
+        executable.to_string_lossy(),
+        check_args,
+    );
+
+    fs::write(&hook, script)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(hook)
+}
+
+/// Scan the lines added in the staged changes (or since `base`) with the repository's todo.toml
+/// and return whether they're free of priority TODOs, `todo!()`/`unimplemented!()` calls (which
+/// panic at runtime), and code markers like `dbg!()`. Only code blocks the change, not todo.md
+/// or the readme, and neither do informational entries.
+pub fn check(dir: &Path, base: Option<String>) -> Result<bool, Box<dyn Error>> {
+    let root = git::toplevel(dir)?;
+
+    let config = match CONFIG_FILES.iter().map(|file| root.join(file)).find(|path| path.exists()) {
+        Some(path) => Config::load(&path).map_err(|err| format!("failed to read {}: {}", path.to_string_lossy(), err))?,
+        None => Config::default(),
+    };
+
+    let mut entries = match base {
+        Some(base) => {
+            if git::git(dir, &["rev-parse", "--verify", "--quiet", &base]).is_err() {
+                eprintln!("[todos] Skipping TODO check: {} doesn't exist", base);
+
+                return Ok(true);
+            }
+
+            git::scan_added(&root, &[&format!("{}...HEAD", base)], &config)?
+        },
+        None => git::scan_added(&root, &["--cached"], &config)?,
+    };
+
+    entries.retain(|entry| entry.source == SourceKind::Code && ! entry.informational);
+    entries.retain(|entry| matches!(entry.data, EntryData::Priority(_) | EntryData::Unimplemented | EntryData::Marker));
+
+    if entries.is_empty() {
+        return Ok(true);
+    }
+
+    print_rendered(&Terminal { title: "Priority TODOs and code markers added in this change".to_string() }, entries, &config);
+
+    eprintln!("\n[todos] Resolve the TODOs above first (or bypass the check with --no-verify)");
+
+    Ok(false)
+}
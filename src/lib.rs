@@ -0,0 +1,44 @@
+pub mod scan;
+pub mod scanner;
+pub mod matcher;
+pub mod scanners;
+pub mod render;
+pub mod template;
+#[cfg(feature = "report-template")]
+pub mod report;
+pub mod entries;
+pub mod explain;
+pub mod cargo;
+pub mod config;
+pub mod git;
+pub mod gitignore;
+pub mod paths;
+pub mod diff;
+pub mod digest;
+pub mod due;
+pub mod annotate;
+pub mod authors;
+pub mod commits;
+pub mod owners;
+pub mod schema;
+pub mod thresholds;
+pub mod lint;
+pub mod org;
+pub mod suggest;
+pub mod rename;
+pub mod bump;
+#[cfg(test)]
+mod test_helpers;
+#[cfg(feature = "sqlite")]
+pub mod db;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "bot")]
+pub mod bot;
+
+pub use entries::{Entry, EntryData, Location, SourceKind, normalize_text};
+pub use scanner::{ScanOptions, Scanner};
@@ -0,0 +1,132 @@
+use std::io::Write;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+
+use crate::config::{Categories, Config};
+use crate::entries::{Entry, EntryData};
+use crate::render::write_ansi;
+use crate::schema::print_json;
+
+/// An entry whose category isn't in `[categories] allowed`
+#[derive(Serialize, JsonSchema, Debug, PartialEq)]
+pub struct UnknownCategory {
+    pub category: String,
+    /// The closest allowed category, e.g. `auth` for `authn`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    pub entry: Entry,
+}
+
+/// The entries with categories that aren't allowed, in the same order
+pub fn unknown_categories(entries: &[Entry], config: &Categories) -> Vec<UnknownCategory> {
+    entries.iter()
+        .filter_map(|entry| match &entry.data {
+            EntryData::Category(category) if ! config.is_allowed(category) => Some(UnknownCategory {
+                category: category.clone(),
+                suggestion: suggestion(category, &config.allowed),
+                entry: entry.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The allowed category `category` is most likely a typo or variant of: one it starts with or
+/// that starts with it (`authentication` for `auth`), or else the closest one within two edits
+fn suggestion(category: &str, allowed: &[String]) -> Option<String> {
+    let category = category.to_lowercase();
+
+    let prefixed = allowed.iter()
+        .filter(|allowed| {
+            let allowed = allowed.to_lowercase();
+            category.starts_with(&allowed) || allowed.starts_with(&category)
+        })
+        .min_by_key(|allowed| allowed.len().abs_diff(category.len()));
+
+    prefixed
+        .or_else(|| allowed.iter()
+            .map(|allowed| (allowed, edit_distance(&category, &allowed.to_lowercase())))
+            .filter(|(_, distance)| *distance <= 2)
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(allowed, _)| allowed))
+        .cloned()
+}
+
+/// The Levenshtein distance between `a` and `b`, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b) in b.iter().enumerate() {
+            current.push((previous[j] + usize::from(a != *b)).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+pub fn render_lint(unknown: &[UnknownCategory], config: &Config) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    for unknown in unknown {
+        write_ansi(&mut stdout, Color::Cyan, &unknown.entry.rendered_location(&config.locations), true);
+        write!(stdout, ": unknown category ").unwrap();
+        write_ansi(&mut stdout, Color::Yellow, &unknown.category, true);
+
+        if let Some(suggestion) = &unknown.suggestion {
+            write!(stdout, ", did you mean ").unwrap();
+            write_ansi(&mut stdout, Color::Green, suggestion, true);
+            write!(stdout, "?").unwrap();
+        }
+
+        writeln!(stdout).unwrap();
+    }
+
+    stdout.flush().unwrap();
+}
+
+/// The shape of `lint --format json` output
+#[derive(Serialize, JsonSchema)]
+pub struct LintReport {
+    pub unknown_categories: Vec<UnknownCategory>,
+}
+
+pub fn render_lint_json(unknown: Vec<UnknownCategory>) {
+    print_json(LintReport { unknown_categories: unknown });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::scan::scan_string;
+
+    #[test]
+    fn unknown_categories_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo@auth a\n// todo@authn b\n// todo@authentication c\n// todo@iu d\n// todo@billing e\n// todo0 f\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        let config = Categories { allowed: vec!["auth".to_string(), "ui".to_string()], ..Categories::default() };
+        let unknown = unknown_categories(&entries, &config);
+        let unknown: Vec<(&str, Option<&str>)> = unknown.iter()
+            .map(|unknown| (unknown.entry.text.as_str(), unknown.suggestion.as_deref()))
+            .collect();
+
+        assert_eq!(vec![
+            ("b", Some("auth")),
+            ("c", Some("auth")),
+            ("d", Some("ui")),
+            ("e", None),
+        ], unknown);
+
+        // Any category is allowed without the list
+        assert!(unknown_categories(&entries, &Categories::default()).is_empty());
+    }
+}
@@ -1,52 +1,954 @@
-use std::fs::canonicalize;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use clap::{Parser, ArgAction};
-use crate::entries::Entry;
-use crate::render::render_entries;
-use crate::scan::{Stats, scan_dir, scan_todo_file, scan_readme_file};
+use clap::{Parser, Subcommand, ValueEnum, ArgAction};
+use termcolor::{BufferedStandardStream, ColorChoice};
+use tracing::{info, info_span};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use todos::entries::{Entry, Location, SourceKind, make_relative, postprocess};
+use todos::annotate::render_annotated;
+use todos::authors::{author_stats, blame_files, mark_stale, render_authors, render_authors_json};
+use todos::cargo;
+use todos::commits;
+use todos::org;
+use todos::bump;
+use todos::rename::{self, RenamedLine, render_renamed, render_renamed_json, rename_category};
+use todos::suggest::{self, Suggestion, render_suggestions, render_suggestions_json, suggest};
+use todos::config::{CONFIG_FILES, BulletStyle, Config, FrontMatter, SortOrder};
+use todos::explain::explain;
+use todos::git;
+use todos::paths::{canonicalize, relative, resolve};
+#[cfg(feature = "report-template")]
+use todos::report;
+use todos::due::{Date, due_within, parse_window, render_due, render_due_json};
+use todos::digest::{digest, render_digest};
+use todos::diff::{diff, pr_comment, read_entries, render_diff, render_diff_json, render_resolved, render_resolved_json};
+use todos::owners::{CodeOwners, group_by_owner};
+use todos::render::{Html, Json, MarkdownStable, Renderer, Sarif, Snapshot, Terminal, group_by_assignee, group_by_file, group_by_keyword, print_rendered, render_categories, render_categories_json, render_checklists, render_files, write_rendered};
+use todos::thresholds::{FailOn, Summary, exceeded, render_failures, render_warnings};
+use todos::lint::{render_lint, render_lint_json, unknown_categories};
+use todos::template::{Template, render_template};
+use todos::schema::{Output, schema};
+use todos::scan::{Stats, add_raw_lines, read_text, resolve_exclude, scan_dir, split_scoped_exclude, scan_file, stream_dir, TEST_PATHS, walk_dir, todo_descriptions};
+use todos::scanners::{Readme, Scanners, TodoFile};
 
-pub mod scan;
-pub mod render;
-pub mod entries;
+mod hook;
+#[cfg(feature = "self-update")]
+mod update;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to your README.md file
-    #[arg(short, long, default_value = "README.md")]
+    #[arg(short, long, global = true, default_value = "README.md")]
     readme: String,
 
-    /// Path to your todo.md file
-    #[arg(short, long, default_value = "todo.md")]
-    todos: String,
+    /// Path to your todo.md file. Can be passed multiple times, e.g. `-t todo.md -t BACKLOG.md`
+    #[arg(short, long, global = true, default_values_t = Vec::from(["todo.md".to_string()]))]
+    todos: Vec<String>,
+
+    /// Don't scan the README, e.g. when only the code comments matter
+    #[arg(long, global = true)]
+    no_readme: bool,
+
+    /// Don't scan todo.md
+    #[arg(long, global = true)]
+    no_todo_file: bool,
 
     /// Paths to search
     #[arg(default_values_t = Vec::from([".".to_string()]))]
     paths: Vec<String>,
 
-    /// Paths to exclude
-    #[arg(short, long, default_values_t = Vec::from([
+    /// Paths to exclude. `root:path` only excludes `path` in one of the scanned paths, e.g. `frontend:dist`
+    #[arg(short, long, global = true, default_values_t = Vec::from([
         "node_modules".to_string(),
         "vendor".to_string(),
     ]))]
     exclude: Vec<String>,
 
+    /// Exclude conventional test locations: tests/, test/, __tests__/, spec/, fixtures/, and test
+    /// files like *_test.go or *.test.ts
+    #[arg(long, global = true)]
+    skip_tests: bool,
+
+    /// Render the title and the Other section even when they're empty
+    #[arg(long, global = true)]
+    always_show_sections: bool,
+
+    /// Print paths relative to this directory [default: the project root]
+    #[arg(long, global = true, value_name = "DIR")]
+    relative_to: Option<PathBuf>,
+
+    /// Print absolute paths
+    #[arg(long, global = true, conflicts_with = "relative_to")]
+    absolute: bool,
+
+    /// Link locations to this URL, e.g. https://github.com/org/repo/blob/main. `auto` links to
+    /// HEAD on the forge of the origin remote (GitHub, GitLab, and alike)
+    #[arg(long, global = true, value_name = "URL|auto", conflicts_with = "absolute")]
+    link_base: Option<String>,
+
+    /// Order of the entries within each section [default: location]
+    #[arg(long, global = true, value_enum)]
+    sort: Option<Sort>,
+
+    /// How entries are listed [default: checkbox]
+    #[arg(long, global = true, value_enum)]
+    bullets: Option<Bullets>,
+
+    /// Path to the config file [default: todo.toml in the project root, if it exists]
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Scan the Cargo workspace containing the current directory, grouped by crate
+    #[arg(long)]
+    cargo: bool,
+
+    /// Scan the staged content of files with staged changes instead of the working tree
+    #[arg(long)]
+    staged: bool,
+
+    /// Record the scanned entries in a SQLite database
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["staged", "changed"])]
+    db: Option<PathBuf>,
+
+    /// Only show the TODOs added, resolved, or moved on the current branch compared to this ref
+    #[arg(long, value_name = "REF", conflicts_with = "staged")]
+    compare_ref: Option<String>,
+
+    /// Only scan the files changed on the current branch compared to this ref, e.g. main,
+    /// including uncommitted and untracked files
+    #[arg(long, value_name = "REF", conflicts_with_all = ["staged", "compare_ref", "stream"])]
+    changed: Option<String>,
+
+    /// Only list the files that would be scanned, without reading them
+    #[arg(long)]
+    dry_run: bool,
+
     /// Show stats after listing TODOs
     #[arg(short, long)]
     #[clap(action = ArgAction::Count)]
     verbose: u8,
+
+    /// Only list the files containing TODOs, e.g. for `xargs $EDITOR`
+    #[arg(short = 'l', long, conflicts_with_all = ["format", "template"])]
+    files_with_matches: bool,
+
+    /// With -l, also print the number of TODOs in each file (`file:count`)
+    #[arg(short, long, requires = "files_with_matches")]
+    count: bool,
+
+    /// Render a checklist per assignee, owner, keyword, or file instead of per section, with counts
+    #[arg(long, value_enum, conflicts_with_all = ["cargo", "format", "template", "files_with_matches"])]
+    group_by: Option<GroupBy>,
+
+    /// Only list the TODOs assigned to this person, with `todo(name)` or `todo @@name` (can be repeated)
+    #[arg(long, value_name = "NAME")]
+    assignee: Vec<String>,
+
+    /// Only list the TODOs in this category or its subcategories (can be repeated)
+    #[arg(long, value_name = "CATEGORY")]
+    category: Vec<String>,
+
+    /// Leave out the TODOs in this category or its subcategories (can be repeated)
+    #[arg(long, value_name = "CATEGORY")]
+    exclude_category: Vec<String>,
+
+    /// Only list the TODOs written in code, todo.md, or the readme (can be repeated)
+    #[arg(long, value_enum)]
+    source: Vec<Source>,
+
+    /// Exit with status 1 when a condition matches, e.g. priority<=0, count>50, category=security,
+    /// keyword=fixme, or kind=unimplemented (can be repeated)
+    #[arg(long, value_name = "CONDITION", conflicts_with = "compare_ref")]
+    fail_on: Vec<FailOn>,
+
+    /// Also write a JSON summary (counts, exceeded thresholds, failed conditions, and duration)
+    /// to this file, e.g. /dev/fd/3, for CI wrappers
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["stream", "compare_ref"])]
+    summary_file: Option<PathBuf>,
+
+    /// Include the unmodified source lines of the entries (`raw` in JSON, `{raw}` in templates)
+    #[arg(long, conflicts_with = "staged")]
+    raw: bool,
+
+    /// Print the entries of each file as soon as it's scanned, instead of in sections after the whole scan
+    #[arg(long, conflicts_with_all = ["staged", "cargo", "format", "group_by", "files_with_matches", "compare_ref"])]
+    stream: bool,
+
+    /// Leave out the line numbers in `--format markdown-stable`, so moving code doesn't change the output
+    #[arg(long)]
+    no_line_numbers: bool,
+
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+
+    /// Write the report to this file instead of stdout, without colors, e.g. todo-report.md
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["stream", "template", "group_by", "files_with_matches", "compare_ref"])]
+    output: Option<PathBuf>,
+
+    /// Print each entry on its own line in this format, e.g. '{file}:{line} [{category|priority}] {text}'
+    ///
+    /// Fields: file (relative), path (absolute), line, text, kind, category, priority, assignee, raw (with --raw).
+    /// `{a|b}` uses the first field that isn't empty, `{text:40}` pads or truncates to 40 columns.
+    #[arg(long, conflicts_with = "format")]
+    template: Option<String>,
+
+    /// Render a Tera template with the grouped entries and stats instead, e.g. for wiki pages
+    #[cfg(feature = "report-template")]
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["format", "template", "files_with_matches", "stream", "output"])]
+    report_template: Option<PathBuf>,
+
+    /// Log level, overrides -v and RUST_LOG
+    #[arg(long, global = true)]
+    log_level: Option<LevelFilter>,
+
+    /// Format of the log output
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+}
+
+#[derive(Subcommand, Debug)]
+enum HookAction {
+    /// Install a hook that fails when changes add priority TODOs
+    Install {
+        /// Install a pre-commit hook checking staged changes (default)
+        #[arg(long, conflicts_with = "pre_push")]
+        pre_commit: bool,
+
+        /// Install a pre-push hook checking the commits being pushed
+        #[arg(long)]
+        pre_push: bool,
+
+        /// Overwrite an existing hook
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Check the lines added in staged changes for priority TODOs (run by the hooks)
+    Check {
+        /// Check the changes since this ref instead of the staged changes
+        #[arg(long)]
+        base: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Markdown,
+    Json,
+    /// Sorted, color-free, root-relative output for committing and comparing in tests
+    Snapshot,
+    /// Color-free markdown with root-relative paths and a deterministic order, for committing
+    MarkdownStable,
+    /// A standalone HTML page
+    Html,
+    /// A SARIF 2.1.0 log, for GitHub code scanning
+    Sarif,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SchemaOutput {
+    /// `--format json`
+    Scan,
+    Diff,
+    Resolved,
+    Categories,
+    Authors,
+    ScanLog,
+    Due,
+    Lint,
+    OrgReport,
+    Suggest,
+    RenameCategory,
+    /// `--summary-file`
+    Summary,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum GroupBy {
+    /// By the `todo(name)` assignee, with the unassigned entries last
+    Assignee,
+    /// By the owners in the repository's CODEOWNERS file, with the unowned entries last
+    Owner,
+    /// By the keyword, e.g. FIXME or HACK, with the TODOs first
+    Keyword,
+    /// By file, with each file's entries sorted by line
+    File,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Source {
+    Code,
+    TodoFile,
+    Readme,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Sort {
+    /// By file, then line
+    Location,
+    Text,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Bullets {
+    /// `- [ ] foo`
+    Checkbox,
+    /// `- foo`
+    Dash,
+    /// `1. foo`
+    Numbered,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Explain why a path would or wouldn't be scanned
+    Explain {
+        /// Path to explain
+        path: String,
+    },
+
+    /// Print a file with its TODO lines highlighted and labeled in the gutter
+    Annotate {
+        /// The file to annotate
+        file: PathBuf,
+    },
+
+    /// Show the TODOs added, resolved, and moved between two scans
+    ///
+    /// Scans are files created using `--format json`. If only one file is passed, or
+    /// `--since` is used, it's compared with a scan of the current working tree.
+    Diff {
+        /// The older scan
+        #[arg(required_unless_present = "since")]
+        old: Option<PathBuf>,
+
+        /// The newer scan
+        new: Option<PathBuf>,
+
+        /// Compare with a scan of the files at this git ref
+        #[arg(long, conflicts_with = "old")]
+        since: Option<String>,
+    },
+
+    /// List the TODOs from a baseline scan that have been resolved since, e.g. for release notes
+    ///
+    /// The baseline is a file created using `--format json`, e.g. when tagging a release.
+    /// Moved TODOs aren't considered resolved.
+    Resolved {
+        /// The baseline scan
+        #[arg(required_unless_present = "since")]
+        baseline: Option<PathBuf>,
+
+        /// Use a scan of the files at this git ref (e.g. the previous release's tag) as the baseline
+        #[arg(long, conflicts_with = "baseline")]
+        since: Option<String>,
+    },
+
+    /// Print a markdown comment summarizing the TODOs added and resolved on the current branch,
+    /// e.g. `todos pr-comment | gh pr comment --body-file -`
+    ///
+    /// Like `--compare-ref`, the branch is compared from the point where it diverged.
+    PrComment {
+        /// The branch the pull request targets
+        #[arg(long, value_name = "REF", default_value = "origin/main")]
+        compare_ref: String,
+    },
+
+    /// List the categories used in the code, todo.md, and README.md, with their entry counts
+    Categories,
+
+    /// Suggest categories for uncategorized TODOs, from `[categories] paths` and the text of
+    /// categorized TODOs, with the rewritten lines
+    Suggest,
+
+    /// Rewrite the TODOs with the categories `todos suggest` proposes
+    ///
+    /// Without --apply, only prints the suggestions.
+    Fix {
+        /// Write the rewritten lines to the files
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Rename a category in the `todo@category` markers in code and the headings of todo.md,
+    /// along with its subcategories
+    ///
+    /// Without --apply, only prints the changes.
+    RenameCategory {
+        /// The current name
+        old: String,
+
+        /// The new name
+        new: String,
+
+        /// Write the changes to the files
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Make a priority TODO one level more urgent, e.g. todo1 to todo0, rewriting its line
+    Bump {
+        /// The TODO, as `file:line` or its ID (as recorded by --db, or a unique prefix)
+        id: String,
+    },
+
+    /// Make a priority TODO one level less urgent, e.g. todo0 to todo1, rewriting its line
+    Demote {
+        /// The TODO, as `file:line` or its ID (as recorded by --db, or a unique prefix)
+        id: String,
+    },
+
+    /// Check the TODOs against the rules in todo.toml, failing if any break them
+    ///
+    /// Currently checks that categories are listed in `[categories] allowed`.
+    Lint,
+
+    /// List the TODOs introduced per author, with their average age, using git blame
+    ///
+    /// Meant for spotting areas where follow-ups never happen, not for ranking people.
+    Authors,
+
+    /// List the TODO and FIXME markers in commit messages, with the commit hash and author
+    ScanLog {
+        /// Only scan the commits after this ref
+        #[arg(long)]
+        since: Option<String>,
+    },
+
+    /// Print the JSON Schema of a JSON output, for validating it or generating types
+    ///
+    /// Outputs include a `schema_version`. Within a version, fields are only ever added;
+    /// removing, renaming, or changing a field bumps it.
+    Schema {
+        /// The output to describe
+        #[arg(value_enum, default_value_t = SchemaOutput::Scan)]
+        output: SchemaOutput,
+    },
+
+    /// List the TODOs due soon or overdue, sorted by date, e.g. for weekly planning
+    ///
+    /// Due dates are written as `due:YYYY-MM-DD` in the TODO text.
+    Due {
+        /// How far ahead to look, e.g. 7d or 2w
+        #[arg(long, default_value = "7d")]
+        within: String,
+    },
+
+    /// Summarize the TODOs added and resolved over a period, ready to paste into a team channel
+    ///
+    /// Compares the current TODOs with the ones in the last commit before the period.
+    Digest {
+        /// The period to summarize, e.g. 7d or 2w
+        #[arg(long, default_value = "7d")]
+        since: String,
+    },
+
+    /// Combine the TODOs of many repositories into one dashboard: counts per repository, the
+    /// files with the most TODOs, and trends with `--since`
+    ///
+    /// Each line of the repos file is a git remote (fetched into `--work-dir`), a local clone
+    /// (scanned at HEAD), or a scan created using `--format json`, optionally followed by a name.
+    OrgReport {
+        /// The file listing the repositories
+        #[arg(long)]
+        repos: PathBuf,
+
+        /// Also count the TODOs at the start of this period, e.g. 30d or 4w
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Where the remotes are fetched
+        #[arg(long, value_name = "DIR", default_value = "todos-repos")]
+        work_dir: PathBuf,
+    },
+
+    /// Manage git hooks blocking priority TODOs
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
+    },
+
+    /// Run a webhook server that comments on pull requests with the TODOs they add and resolve
+    ///
+    /// Reads the GitHub token from GITHUB_TOKEN and the webhook secret from WEBHOOK_SECRET.
+    #[cfg(feature = "bot")]
+    Bot {
+        /// The address to listen on
+        #[arg(long, default_value = "127.0.0.1:3000")]
+        listen: String,
+
+        /// Where the repositories are fetched
+        #[arg(long, value_name = "DIR", default_value = "todos-bot")]
+        work_dir: PathBuf,
+
+        /// The GitHub API URL, e.g. https://github.example.com/api/v3 for GitHub Enterprise
+        #[arg(long, value_name = "URL", default_value = "https://api.github.com")]
+        api_url: String,
+    },
+
+    /// Update to the latest GitHub release
+    #[cfg(feature = "self-update")]
+    SelfUpdate {
+        /// Only check whether a new version is available
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+fn init_logging(args: &Args) {
+    let filter = match (args.log_level, std::env::var("RUST_LOG")) {
+        (Some(level), _) => EnvFilter::new(level.to_string()),
+        (None, Ok(_)) => EnvFilter::from_default_env(),
+        (None, Err(_)) => EnvFilter::new(match args.verbose {
+            0 => LevelFilter::WARN,
+            1 => LevelFilter::INFO,
+            2 => LevelFilter::DEBUG,
+            _ => LevelFilter::TRACE,
+        }.to_string()),
+    };
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr);
+
+    match args.log_format {
+        LogFormat::Text => subscriber.with_target(false).init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+}
+
+fn read_scan(path: &Path) -> Vec<Entry> {
+    match read_entries(path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            eprintln!("Failed to read {}: {}", path.to_string_lossy(), err);
+            std::process::exit(1);
+        },
+    }
+}
+
+/// The formats a subcommand can print, or `None` if it doesn't print formatted output. The
+/// default command prints all of them.
+fn subcommand_formats(command: &Command) -> Option<&'static [Format]> {
+    match command {
+        Command::Diff { .. } | Command::Resolved { .. } | Command::PrComment { .. } | Command::Categories
+            | Command::Suggest | Command::Fix { .. } | Command::RenameCategory { .. } | Command::Lint
+            | Command::Authors | Command::ScanLog { .. } | Command::Due { .. } | Command::OrgReport { .. }
+            => Some(&[Format::Markdown, Format::Json]),
+        Command::Digest { .. } => Some(&[Format::Markdown]),
+        _ => None,
+    }
+}
+
+/// The name of a format, as passed to `--format`
+fn format_name(format: Format) -> String {
+    format.to_possible_value().unwrap().get_name().to_string()
+}
+
+fn print_diff(old: Vec<Entry>, new: Vec<Entry>, format: Format, config: &Config) {
+    let diff = diff(old, new, config.diff.similarity);
+
+    match format {
+        Format::Markdown => render_diff(&diff, config),
+        Format::Json => render_diff_json(&diff),
+        Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+    }
+}
+
+/// The current Unix timestamp
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs() as i64)
+}
+
+/// The permalink of `dir` at HEAD, from the origin remote
+fn forge_link_base(dir: &Path) -> Result<String, String> {
+    let remote = git::origin_url(dir).map_err(|err| err.to_string())?;
+    let commit = git::head_commit(dir).map_err(|err| err.to_string())?;
+    let link_base = git::permalink_base(&remote, &commit).ok_or_else(|| format!("unsupported remote {}", remote))?;
+
+    // Paths are printed relative to `dir`, which may be below the repository root
+    let toplevel = git::toplevel(dir).map_err(|err| err.to_string())?;
+    let subdir = relative(dir, &toplevel).to_string_lossy().replace('\\', "/");
+
+    Ok(match subdir.as_str() {
+        "" | "." => link_base,
+        subdir => format!("{}{}/", link_base, subdir),
+    })
 }
 
 fn main() {
+    let started = Instant::now();
     let args = Args::parse();
-    let root_dir: PathBuf = std::env::current_dir().unwrap();
+    let current_dir: PathBuf = std::env::current_dir().unwrap();
+    let mut root_dir = current_dir.clone();
+
+    init_logging(&args);
+
+    // A CI job uploading e.g. SARIF mustn't get markdown instead
+    let formats = match &args.command {
+        Some(command) => subcommand_formats(command),
+        None if args.compare_ref.is_some() => Some(&[Format::Markdown, Format::Json][..]),
+        None => None,
+    };
+
+    if let Some(formats) = formats.filter(|formats| ! formats.contains(&args.format)) {
+        eprintln!("Unsupported format: this command prints {}, not {}",
+            formats.iter().map(|format| format_name(*format)).collect::<Vec<_>>().join(" or "), format_name(args.format));
+        std::process::exit(1);
+    }
+
+    // Subcommands print their own results, so e.g. a `--fail-on` gate would always pass
+    let scan_only = [
+        ("--fail-on", ! args.fail_on.is_empty()),
+        ("--summary-file", args.summary_file.is_some()),
+        ("--output", args.output.is_some()),
+    ];
+
+    if let (Some(_), Some((flag, _))) = (&args.command, scan_only.iter().find(|(_, used)| *used)) {
+        eprintln!("{} is only supported when scanning, not with subcommands", flag);
+        std::process::exit(1);
+    }
+
+    #[cfg(feature = "self-update")]
+    if let Some(Command::SelfUpdate { check }) = args.command {
+        if let Err(err) = update::self_update(check) {
+            eprintln!("Update failed: {}", err);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    #[cfg(feature = "bot")]
+    if let Some(Command::Bot { listen, work_dir, api_url }) = &args.command {
+        let env = |name: &str| match std::env::var(name) {
+            Ok(value) if ! value.is_empty() => value,
+            _ => {
+                eprintln!("Failed to start the bot: {} isn't set", name);
+                std::process::exit(1);
+            },
+        };
+
+        let config = todos::bot::BotConfig {
+            token: env("GITHUB_TOKEN"),
+            secret: env("WEBHOOK_SECRET"),
+            work_dir: current_dir.join(work_dir),
+            api_url: api_url.clone(),
+        };
+
+        if let Err(err) = todos::bot::serve(listen, &config) {
+            eprintln!("Failed to run the bot: {}", err);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if let Some(Command::Schema { output }) = args.command {
+        let output = match output {
+            SchemaOutput::Scan => Output::Scan,
+            SchemaOutput::Diff => Output::Diff,
+            SchemaOutput::Resolved => Output::Resolved,
+            SchemaOutput::Categories => Output::Categories,
+            SchemaOutput::Authors => Output::Authors,
+            SchemaOutput::ScanLog => Output::ScanLog,
+            SchemaOutput::Due => Output::Due,
+            SchemaOutput::Lint => Output::Lint,
+            SchemaOutput::OrgReport => Output::OrgReport,
+            SchemaOutput::Suggest => Output::Suggest,
+            SchemaOutput::RenameCategory => Output::RenameCategory,
+            SchemaOutput::Summary => Output::Summary,
+        };
+
+        println!("{}", serde_json::to_string_pretty(&schema(output)).unwrap());
+
+        return;
+    }
+
+    if let Some(Command::OrgReport { repos, since, work_dir }) = &args.command {
+        let content = match std::fs::read_to_string(repos) {
+            Ok(content) => content,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", repos.to_string_lossy(), err);
+                std::process::exit(1);
+            },
+        };
+
+        let since_timestamp = since.as_deref().map(|since| match parse_window(since) {
+            Ok(days) => now() - days * 24 * 60 * 60,
+            Err(err) => {
+                eprintln!("Failed to parse --since: {}", err);
+                std::process::exit(1);
+            },
+        });
+
+        let base = current_dir.join(repos);
+        let repositories = org::parse_repos(&content, base.parent().unwrap_or(&current_dir));
+        let scans = org::scan_repositories(&repositories, &current_dir.join(work_dir), since_timestamp);
+        let report = org::org_report(&repositories, scans);
+
+        match args.format {
+            Format::Markdown => org::render_org_report(&report, since.as_deref()),
+            Format::Json => org::render_org_report_json(report),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
+
+        return;
+    }
+
+    if let Some(Command::Hook { action }) = args.command {
+        match action {
+            HookAction::Install { pre_push, force, .. } => {
+                let kind = if pre_push { hook::HookKind::PrePush } else { hook::HookKind::PreCommit };
+
+                match hook::install(&current_dir, kind, force) {
+                    Ok(path) => println!("Installed {}", path.to_string_lossy()),
+                    Err(err) => {
+                        eprintln!("Failed to install the hook: {}", err);
+                        std::process::exit(1);
+                    },
+                }
+            },
+            HookAction::Check { base } => match hook::check(&current_dir, base) {
+                Ok(true) => (),
+                Ok(false) => std::process::exit(1),
+                Err(err) => {
+                    eprintln!("Failed to check for TODOs: {}", err);
+                    std::process::exit(1);
+                },
+            },
+        }
+
+        return;
+    }
+
+    let template = args.template.as_deref().map(|template| match Template::parse(template) {
+        Ok(template) => template,
+        Err(err) => {
+            eprintln!("Invalid template: {}", err);
+            std::process::exit(1);
+        },
+    });
 
     let mut paths: Vec<PathBuf> = vec![];
     let mut excludes: Vec<PathBuf> = vec![];
 
+    let workspace = match args.cargo {
+        true => match cargo::workspace(&current_dir) {
+            Ok(workspace) => Some(workspace),
+            Err(err) => {
+                eprintln!("Failed to read the Cargo workspace: {}", err);
+                std::process::exit(1);
+            },
+        },
+        false => None,
+    };
+
+    if let Some(workspace) = &workspace {
+        // Everything is relative to the workspace root, same as when running `todos` from there
+        root_dir = workspace.root.clone();
+
+        if let Ok(realpath) = canonicalize(&workspace.target_dir) {
+            excludes.push(realpath);
+        }
+    }
+
+    let mut config = match &args.config {
+        Some(path) => Some(path.clone()),
+        None => CONFIG_FILES.iter().map(|file| root_dir.join(file)).find(|path| path.exists()),
+    }.map(|path| match Config::load(&path) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read {}: {}", path.to_string_lossy(), err);
+            std::process::exit(1);
+        },
+    }).unwrap_or_default();
+
+    config.sources.todo_file &= ! args.no_todo_file;
+    config.sources.readme &= ! args.no_readme;
+
+    let todos_paths: Vec<PathBuf> = args.todos.iter().map(|path| resolve(&root_dir, path)).collect();
+
+    // The todo files that are scanned
+    let todo_sources: Vec<PathBuf> = match config.sources.todo_file {
+        true => todos_paths.clone(),
+        false => vec![],
+    };
+
+    // todo.md can declare its own conventions in a front-matter block. With multiple todo
+    // files, the first one to set something wins.
+    for todos_file in &todo_sources {
+        let Ok((content, _)) = read_text(todos_file) else {
+            continue;
+        };
+
+        match FrontMatter::parse(&content) {
+            Ok(front_matter) => config.apply_front_matter(front_matter),
+            Err(err) => {
+                eprintln!("Failed to read the front matter of {}: {}", todos_file.to_string_lossy(), err);
+                std::process::exit(1);
+            },
+        }
+
+        config.apply_descriptions(todo_descriptions(&content));
+    }
+
+    config.sections.always_show |= args.always_show_sections;
+
+    if let Some(sort) = args.sort {
+        config.sections.sort = match sort {
+            Sort::Location => SortOrder::Location,
+            Sort::Text => SortOrder::Text,
+        };
+    }
+
+    if let Some(bullets) = args.bullets {
+        config.sections.bullets = match bullets {
+            Bullets::Checkbox => BulletStyle::Checkbox,
+            Bullets::Dash => BulletStyle::Dash,
+            Bullets::Numbered => BulletStyle::Numbered,
+        };
+    }
+
+    // CODEOWNERS paths are relative to the repository root, which may be above the scanned directory
+    let code_owners = match args.group_by {
+        Some(GroupBy::Owner) => {
+            let repo_root = git::toplevel(&root_dir).unwrap_or(root_dir.clone());
+
+            match CodeOwners::find(&repo_root) {
+                Ok(Some(code_owners)) => Some((code_owners, repo_root)),
+                Ok(None) => {
+                    eprintln!("No CODEOWNERS file found in {}", repo_root.to_string_lossy());
+                    std::process::exit(1);
+                },
+                Err(err) => {
+                    eprintln!("Failed to read CODEOWNERS: {}", err);
+                    std::process::exit(1);
+                },
+            }
+        },
+        _ => None,
+    };
+
+    if let Some(Command::Diff { old: Some(old), new: Some(new), .. }) = &args.command {
+        print_diff(read_scan(old), read_scan(new), args.format, &config);
+
+        return;
+    }
+
+    if let Some(Command::ScanLog { since }) = &args.command {
+        let commits = match commits::commits(&root_dir, since.as_deref()) {
+            Ok(commits) => commits,
+            Err(err) => {
+                eprintln!("Failed to read the git log: {}", err);
+                std::process::exit(1);
+            },
+        };
+
+        let entries = commits::scan_commits(&commits);
+
+        match args.format {
+            Format::Markdown => commits::render_commit_entries(&entries),
+            Format::Json => commits::render_commit_entries_json(entries),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
+
+        return;
+    }
+
+    let path_base = match (&args.relative_to, args.absolute) {
+        (_, true) => None,
+        (Some(dir), false) => match canonicalize(current_dir.join(dir)) {
+            Ok(dir) => Some(dir),
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", dir.to_string_lossy(), err);
+                std::process::exit(1);
+            },
+        },
+        (None, false) => Some(root_dir.clone()),
+    };
+
+    if let (Some(link_base), Some(base)) = (&args.link_base, &path_base) {
+        let link_base = match link_base.as_str() {
+            "auto" => match forge_link_base(base) {
+                Ok(link_base) => link_base,
+                Err(err) => {
+                    eprintln!("Failed to detect the link base: {}", err);
+                    std::process::exit(1);
+                },
+            },
+            link_base => format!("{}/", link_base.trim_end_matches('/')),
+        };
+
+        // Braces would be read as template fields
+        let url = format!("{}{{file}}#L{{line}}", link_base.replace('{', "%7B").replace('}', "%7D"));
+        config.locations.url = Some(Template::parse(&url).unwrap());
+    }
+
+    // Paths are only made relative for output, everything else works with absolute paths
+    let display = |mut entries: Vec<Entry>| -> Vec<Entry> {
+        if let Some(base) = &path_base {
+            make_relative(&mut entries, base);
+        }
+
+        entries
+    };
+
+    // Applied after the database is updated, so that the other entries aren't marked resolved
+    let source_kinds: Vec<SourceKind> = args.source.iter().map(|source| match source {
+        Source::Code => SourceKind::Code,
+        Source::TodoFile => SourceKind::TodoFile,
+        Source::Readme => SourceKind::Readme,
+    }).collect();
+
+    let filter_entries = |entries: &mut Vec<Entry>| {
+        if ! args.assignee.is_empty() {
+            entries.retain(|entry| entry.assignee.as_ref().is_some_and(|assignee| args.assignee.contains(assignee)));
+        }
+
+        if ! args.category.is_empty() {
+            entries.retain(|entry| args.category.iter().any(|category| entry.data.is_in_category(category)));
+        }
+
+        entries.retain(|entry| ! args.exclude_category.iter().any(|category| entry.data.is_in_category(category)));
+
+        if ! source_kinds.is_empty() {
+            entries.retain(|entry| source_kinds.contains(&entry.source));
+        }
+    };
+
     let mut entries: Vec<Entry> = vec![];
-    let mut stats = Stats::new(args.verbose);
+
+    // Subcommands need the complete scan
+    let stream = args.stream && args.command.is_none();
+
+    // Stats only collect what the active log level can display
+    let mut stats = Stats::new(match LevelFilter::current() {
+        LevelFilter::TRACE => 3,
+        LevelFilter::DEBUG => 2,
+        LevelFilter::INFO => 1,
+        _ => 0,
+    });
+
+    // As passed, for the excludes scoped to one of them
+    let roots = args.paths.clone();
 
     for p in args.paths {
         let mut path = root_dir.clone();
@@ -62,47 +964,576 @@ fn main() {
         }
     }
 
-    for exclude in args.exclude {
-        let mut path = root_dir.clone();
-        path.push(exclude);
+    for exclude in &args.exclude {
+        let (root, exclude) = split_scoped_exclude(exclude, &roots);
+        let root = root.map_or_else(|| root_dir.clone(), |root| root_dir.join(root));
 
-        if path.exists() {
-            if let Ok(realpath) = canonicalize(path) {
-                excludes.push(realpath);
+        excludes.push(resolve_exclude(&root, exclude));
+    }
+
+    for skipped in config.scan.skipped_files() {
+        excludes.push(resolve_exclude(&root_dir, skipped));
+    }
+
+    if args.skip_tests {
+        for test_path in TEST_PATHS {
+            excludes.push(resolve_exclude(&root_dir, test_path));
+        }
+    }
+
+    let readme_path = resolve(&root_dir, &args.readme);
+
+    // The markdown files that are scanned, even outside of the scanned paths
+    let mut sources: Vec<PathBuf> = todo_sources.clone();
+
+    if config.sources.readme {
+        sources.push(readme_path.clone());
+    }
+
+    // The keywords are validated when the config is loaded
+    let mut scanners = Scanners::new(config.scan.matcher().unwrap());
+
+    for plugin in &config.plugins {
+        scanners.register(plugin.clone());
+    }
+
+    for todos_path in &todo_sources {
+        scanners.register(TodoFile { path: todos_path.clone() });
+    }
+
+    if config.sources.readme {
+        scanners.register(Readme { path: readme_path.clone() });
+    }
+
+    // Turned off sources aren't scanned as code either. Files outside of the scanned paths
+    // aren't walked anyway.
+    for source in todos_paths.iter().chain([&readme_path]) {
+        if source.exists() && paths.iter().any(|path| source.starts_with(path)) {
+            excludes.push(source.clone());
+        }
+    }
+
+    // A previously written report would list its entries again
+    if let Some(Ok(output)) = args.output.as_ref().map(canonicalize) {
+        excludes.push(output);
+    }
+
+    if let Some(Command::Explain { path }) = args.command {
+        let mut target = current_dir.clone();
+        target.push(path);
+
+        explain(&target, &paths, &excludes);
+
+        return;
+    }
+
+    if let Some(Command::Annotate { file }) = &args.command {
+        if let Err(err) = render_annotated(&current_dir, file, &scanners, &config) {
+            eprintln!("Failed to annotate {}: {}", file.to_string_lossy(), err);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if args.dry_run {
+        for path in &sources {
+            if path.exists() {
+                println!("{}", path.to_string_lossy());
+            }
+        }
+
+        for p in &paths {
+            walk_dir(p.as_path(), &excludes, &mut stats, &mut |path, _| {
+                println!("{}", path.to_string_lossy());
+
+                Ok(())
+            }).unwrap();
+        }
+
+        return;
+    }
+
+    let start = Instant::now();
+
+    if args.staged {
+        let result = info_span!("staged").in_scope(|| {
+            git::scan_staged(&root_dir, &paths, &excludes, &sources, &scanners, &mut entries, &mut stats)
+        });
+
+        if let Err(err) = result {
+            eprintln!("Failed to read the staged changes: {}", err);
+            std::process::exit(1);
+        }
+    } else if let Some(changed) = &args.changed {
+        let result = info_span!("changed").in_scope(|| {
+            git::scan_changed(&git::changed_files(&root_dir, changed)?, &paths, &excludes, &sources, &scanners, &mut entries, &mut stats)
+        });
+
+        if let Err(err) = result {
+            eprintln!("Failed to read the changes since {}: {}", changed, err);
+            std::process::exit(1);
+        }
+    } else if stream {
+        let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+        let mut emit = |mut found: Vec<Entry>| {
+            postprocess(&mut found, &config, &root_dir, &sources);
+            filter_entries(&mut found);
+
+            if args.raw {
+                if let Err(err) = add_raw_lines(&mut found) {
+                    eprintln!("Failed to read the raw lines: {}", err);
+                    std::process::exit(1);
+                }
             }
+
+            // Numbered across the whole stream, since it isn't split into sections
+            for (index, entry) in display(found.clone()).into_iter().enumerate() {
+                match &template {
+                    Some(template) => writeln!(stdout, "{}", template.render(&entry, &root_dir)).unwrap(),
+                    None => entry.render(&mut stdout, &config, true, entries.len() + index),
+                }
+            }
+
+            stdout.flush().unwrap();
+            entries.extend(found);
+        };
+
+        info_span!("markdown").in_scope(|| {
+            let mut found: Vec<Entry> = vec![];
+
+            for path in &sources {
+                if path.exists() && scan_file(path, &scanners, &mut found).unwrap() {
+                    stats.add_transcoded_file(path.to_string_lossy().to_string());
+                }
+            }
+
+            emit(found);
+        });
+
+        info_span!("scan").in_scope(|| {
+            for p in &paths {
+                stream_dir(p.as_path(), &scanners, &excludes, &mut stats, &mut emit).unwrap();
+            }
+        });
+    } else {
+        info_span!("markdown").in_scope(|| {
+            for path in &sources {
+                if path.exists() && scan_file(path, &scanners, &mut entries).unwrap() {
+                    stats.add_transcoded_file(path.to_string_lossy().to_string());
+                }
+            }
+        });
+
+        info_span!("scan").in_scope(|| {
+            for p in &paths {
+                scan_dir(p.as_path(), &scanners, &mut entries, &excludes, &mut stats).unwrap();
+            }
+        });
+    }
+
+    if ! stream {
+        postprocess(&mut entries, &config, &root_dir, &sources);
+    }
+
+    if args.raw && ! stream {
+        if let Err(err) = add_raw_lines(&mut entries) {
+            eprintln!("Failed to read the raw lines: {}", err);
+            std::process::exit(1);
+        }
+    }
+
+    stats.add_scan_time(start.elapsed());
+    stats.add_entries(&entries);
+
+    // Streamed entries have already been printed
+    if let (Some(max_age_days), false) = (config.stale.max_age_days, stream) {
+        let blames = info_span!("blame").in_scope(|| blame_files(&root_dir, &entries));
+        mark_stale(&mut entries, &blames, now(), max_age_days);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(db) = &args.db {
+        if let Err(err) = info_span!("db").in_scope(|| todos::db::record_scan(db, &root_dir, &entries)) {
+            eprintln!("Failed to update the database: {}", err);
+            std::process::exit(1);
         }
     }
 
-    let mut todos_path = root_dir.clone();
-    todos_path.push(&args.todos);
+    filter_entries(&mut entries);
+
+    let scan_ref = |git_ref: &str| -> Vec<Entry> {
+        let mut ref_entries: Vec<Entry> = vec![];
+
+        if let Err(err) = git::scan_ref(&root_dir, git_ref, &paths, &excludes, &sources, &scanners, &mut ref_entries) {
+            eprintln!("Failed to scan {}: {}", git_ref, err);
+            std::process::exit(1);
+        }
+
+        postprocess(&mut ref_entries, &config, &root_dir, &sources);
+        filter_entries(&mut ref_entries);
 
-    let mut readme_path = root_dir.clone();
-    readme_path.push(&args.readme);
+        ref_entries
+    };
 
-    if todos_path.exists() {
-        excludes.push(todos_path.clone());
+    if let Some(Command::Diff { old, since, .. }) = &args.command {
+        let old_entries = match (old, since) {
+            (Some(old), _) => read_scan(old),
+            (None, Some(since)) => scan_ref(since),
+            (None, None) => unreachable!(),
+        };
 
-        scan_todo_file(&todos_path, &mut entries).unwrap();
+        print_diff(display(old_entries), display(entries), args.format, &config);
+
+        return;
     }
 
-    if readme_path.exists() {
-        excludes.push(readme_path.clone());
+    if let Some(Command::Categories) = &args.command {
+        match args.format {
+            Format::Markdown => render_categories(&entries),
+            Format::Json => render_categories_json(&entries),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
 
-        scan_readme_file(&readme_path, &mut entries).unwrap();
+        return;
     }
 
-    for p in &paths {
-        scan_dir(p.as_path(), &mut entries, &mut excludes, &mut stats).unwrap();
+    if let Some(Command::Suggest | Command::Fix { .. }) = &args.command {
+        // The keywords are validated when the config is loaded
+        let suggestions = suggest(&entries, &config.categories, &config.scan.matcher().unwrap(), &root_dir);
+
+        if let Some(Command::Fix { apply: true }) = &args.command {
+            match suggest::apply(&suggestions) {
+                Ok(count) => println!("Categorized {} of {} TODOs", count, suggestions.len()),
+                Err(err) => {
+                    eprintln!("Failed to rewrite the TODOs: {}", err);
+                    std::process::exit(1);
+                },
+            }
+
+            return;
+        }
+
+        let suggestions: Vec<Suggestion> = suggestions.into_iter()
+            .map(|suggestion| Suggestion { entry: display(vec![suggestion.entry]).remove(0), ..suggestion })
+            .collect();
+
+        match args.format {
+            Format::Markdown => render_suggestions(&suggestions, &config),
+            Format::Json => render_suggestions_json(suggestions),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
+
+        return;
     }
 
-    render_entries(entries);
+    if let Some(Command::RenameCategory { old, new, apply }) = &args.command {
+        // The keywords are validated when the config is loaded
+        let renamed = match rename_category(&entries, &todo_sources, &config.scan.matcher().unwrap(), old, new) {
+            Ok(renamed) => renamed,
+            Err(err) => {
+                eprintln!("Failed to rename the category: {}", err);
+                std::process::exit(1);
+            },
+        };
+
+        if *apply {
+            match rename::apply(&renamed) {
+                Ok(count) => println!("Renamed {} in {} of {} lines", old, count, renamed.len()),
+                Err(err) => {
+                    eprintln!("Failed to rename the category: {}", err);
+                    std::process::exit(1);
+                },
+            }
+
+            return;
+        }
+
+        let renamed: Vec<RenamedLine> = renamed.into_iter()
+            .map(|line| match &path_base {
+                Some(base) => RenamedLine { location: Location { file: relative(&line.location.file, base).into(), ..line.location }, ..line },
+                None => line,
+            })
+            .collect();
+
+        match args.format {
+            Format::Markdown => render_renamed(&renamed, old, new),
+            Format::Json => render_renamed_json(renamed, old, new),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
+
+        return;
+    }
+
+    if let Some(Command::Bump { id } | Command::Demote { id }) = &args.command {
+        let by = match &args.command {
+            Some(Command::Bump { .. }) => 1,
+            _ => -1,
+        };
+
+        // The keywords are validated when the config is loaded
+        let changed = bump::find(&entries, id, &root_dir, &current_dir)
+            .and_then(|entry| bump::reprioritize(entry, by, &config.scan.matcher().unwrap(), &todo_sources));
+
+        let changed = match changed {
+            Ok(changed) => changed,
+            Err(err) => {
+                eprintln!("Failed to change the priority: {}", err);
+                std::process::exit(1);
+            },
+        };
+
+        if let Err(err) = rename::apply(&changed) {
+            eprintln!("Failed to change the priority: {}", err);
+            std::process::exit(1);
+        }
+
+        for line in &changed {
+            let file = match &path_base {
+                Some(base) => relative(&line.location.file, base),
+                None => line.location.file.to_path_buf(),
+            };
+
+            println!("{}:{}: {}", file.to_string_lossy(), line.location.line, line.after.trim());
+        }
+
+        return;
+    }
+
+    if let Some(Command::Lint) = &args.command {
+        let unknown = unknown_categories(&display(entries), &config.categories);
+        let failed = ! unknown.is_empty();
+
+        match args.format {
+            Format::Markdown => render_lint(&unknown, &config),
+            Format::Json => render_lint_json(unknown),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
+
+        if failed {
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if let Some(Command::Authors) = &args.command {
+        let stats = author_stats(&entries, &blame_files(&root_dir, &entries), now());
+
+        match args.format {
+            Format::Markdown => render_authors(&stats),
+            Format::Json => render_authors_json(stats),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
+
+        return;
+    }
+
+    if let Some(Command::Due { within }) = &args.command {
+        let days = match parse_window(within) {
+            Ok(days) => days,
+            Err(err) => {
+                eprintln!("Failed to parse --within: {}", err);
+                std::process::exit(1);
+            },
+        };
+
+        let today = Date::from_timestamp(now());
+        let due = due_within(display(entries), today, days);
+
+        match args.format {
+            Format::Markdown => render_due(&due, today, &config),
+            Format::Json => render_due_json(due, today),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
+
+        return;
+    }
+
+    if let Some(Command::Digest { since }) = &args.command {
+        let days = match parse_window(since) {
+            Ok(days) => days,
+            Err(err) => {
+                eprintln!("Failed to parse --since: {}", err);
+                std::process::exit(1);
+            },
+        };
+
+        let before = match git::commit_before(&root_dir, "HEAD", now() - days * 24 * 60 * 60) {
+            Ok(Some(commit)) => scan_ref(&commit),
+            // The repository is younger than the period
+            Ok(None) => vec![],
+            Err(err) => {
+                eprintln!("Failed to read the git history: {}", err);
+                std::process::exit(1);
+            },
+        };
+
+        let blames = blame_files(&root_dir, &entries);
+        let mut digest = digest(since, before, entries, &blames, now(), config.diff.similarity);
+
+        digest.added = display(digest.added);
+        digest.resolved = display(digest.resolved);
+        let (oldest, ages): (Vec<Entry>, Vec<i64>) = digest.oldest.into_iter().unzip();
+        digest.oldest = display(oldest).into_iter().zip(ages).collect();
+
+        render_digest(&digest, &config);
+
+        return;
+    }
+
+    if let Some(Command::Resolved { baseline, since }) = &args.command {
+        let baseline_entries = match (baseline, since) {
+            (Some(baseline), _) => read_scan(baseline),
+            (None, Some(since)) => scan_ref(since),
+            (None, None) => unreachable!(),
+        };
+
+        let diff = diff(display(baseline_entries), display(entries), config.diff.similarity);
+
+        match args.format {
+            Format::Markdown => render_resolved(&diff, &config),
+            Format::Json => render_resolved_json(&diff),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
+
+        return;
+    }
+
+    // Only the changes made on the branch, not the ones made on `compare_ref` since branching off
+    let branch_base = |compare_ref: &str| -> String {
+        match git::merge_base(&root_dir, compare_ref, "HEAD") {
+            Ok(base) => base,
+            Err(err) => {
+                eprintln!("Failed to find the merge base of {} and HEAD: {}", compare_ref, err);
+                std::process::exit(1);
+            },
+        }
+    };
+
+    if let Some(Command::PrComment { compare_ref }) = &args.command {
+        let diff = diff(display(scan_ref(&branch_base(compare_ref))), display(entries), config.diff.similarity);
+
+        match args.format {
+            Format::Markdown => print!("{}", pr_comment(&diff, &config)),
+            Format::Json => render_diff_json(&diff),
+            Format::MarkdownStable | Format::Snapshot | Format::Html | Format::Sarif => unreachable!(),
+        }
+
+        return;
+    }
+
+    if let Some(compare_ref) = &args.compare_ref {
+        let base = branch_base(compare_ref);
+
+        print_diff(display(scan_ref(&base)), display(entries), args.format, &config);
+
+        return;
+    }
+
+    let start = Instant::now();
+
+    // Printed after the entries, so they're the last thing in the terminal
+    let exceeded_thresholds = exceeded(&entries, &config);
+    let failures: Vec<String> = args.fail_on.iter().filter_map(|fail_on| fail_on.failure(&entries)).collect();
+
+    // Rendering consumes the entries, so the duration is updated after it
+    let summary = args.summary_file.as_ref()
+        .map(|path| (path, Summary::new(&entries, exceeded_thresholds.clone(), failures.clone(), started.elapsed())));
+
+    info_span!("render").in_scope(|| {
+        if stream {
+            return;
+        }
+
+        #[cfg(feature = "report-template")]
+        if let Some(report_template) = &args.report_template {
+            match report::render_report(report_template, entries, &root_dir, &stats, &config) {
+                Ok(report) => print!("{}", report),
+                Err(err) => {
+                    eprintln!("Failed to render the report: {}", err);
+                    std::process::exit(1);
+                },
+            }
+
+            return;
+        }
+
+        if args.files_with_matches {
+            return render_files(&display(entries), args.count);
+        }
+
+        let groups = match (args.group_by, &code_owners) {
+            (Some(GroupBy::Assignee), _) => Some((group_by_assignee(entries.clone(), config.sections.sort), "Unassigned")),
+            (Some(GroupBy::Owner), Some((code_owners, repo_root))) => Some((group_by_owner(entries.clone(), code_owners, repo_root, config.sections.sort), "Unowned")),
+            (Some(GroupBy::Keyword), _) => Some((group_by_keyword(entries.clone(), config.sections.sort), "TODO")),
+            // Grouped by the displayed paths, which are also the headings
+            (Some(GroupBy::File), _) => Some((group_by_file(display(entries.clone())), "")),
+            _ => None,
+        };
+
+        if let Some((groups, fallback)) = groups {
+            let groups = groups.into_iter()
+                .map(|(name, entries)| (name.unwrap_or(fallback.to_string()), display(entries)))
+                .collect();
+
+            return render_checklists(groups, "TODOs", &config);
+        }
+
+        if let Some(template) = &template {
+            return render_template(&entries, template, &root_dir);
+        }
+
+        if let (Format::Markdown, Some(workspace), None) = (args.format, &workspace, &args.output) {
+            for (name, entries) in cargo::group_by_member(entries, workspace) {
+                print_rendered(&Terminal { title: format!("TODOs: {}", name) }, display(entries), &config);
+                println!();
+            }
+
+            return;
+        }
+
+        // The snapshot formats make the paths relative to the root themselves
+        let (renderer, entries): (Box<dyn Renderer>, Vec<Entry>) = match args.format {
+            Format::Markdown => (Box::new(Terminal { title: "TODOs".to_string() }), display(entries)),
+            Format::Json => (Box::new(Json), display(entries)),
+            Format::Html => (Box::new(Html { title: "TODOs".to_string() }), display(entries)),
+            Format::Snapshot => (Box::new(Snapshot { root: root_dir.clone() }), entries),
+            Format::Sarif => (Box::new(Sarif { root: root_dir.clone() }), entries),
+            Format::MarkdownStable => (Box::new(MarkdownStable { root: root_dir.clone(), line_numbers: ! args.no_line_numbers }), entries),
+        };
+
+        match &args.output {
+            Some(output) => if let Err(err) = write_rendered(&*renderer, entries, &config, output) {
+                eprintln!("Failed to write {}: {}", output.display(), err);
+                std::process::exit(1);
+            },
+            None => print_rendered(&*renderer, entries, &config),
+        }
+    });
+
+    stats.add_render_time(start.elapsed());
+
+    render_warnings(&exceeded_thresholds);
+
+    stats.print();
+    info!(count = paths.len(), paths = ?paths, "Paths");
+    info!(count = excludes.len(), excludes = ?excludes, "Excludes");
+    info!(paths = ?todos_paths, "todo.md");
+    info!(path = ?readme_path, "readme.md");
+
+    if let Some((path, summary)) = summary {
+        let summary = Summary { duration_ms: started.elapsed().as_millis() as u64, ..summary };
+
+        if let Err(err) = summary.write(path) {
+            eprintln!("Failed to write the summary: {}", err);
+            std::process::exit(1);
+        }
+    }
 
-    if args.verbose > 0 {
-        eprint!("\n\n");
-        stats.print();
-        eprintln!("Paths ({}): {:?}", &paths.len(), &paths);
-        eprintln!("Excludes ({}): {:?}", &excludes.len(), &excludes);
-        eprintln!("todo.md: {:?}", &todos_path);
-        eprintln!("readme.md: {:?}", &readme_path);
+    if ! failures.is_empty() {
+        render_failures(&failures);
+        std::process::exit(1);
     }
 }
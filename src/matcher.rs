@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use aho_corasick::{AhoCorasick, AhoCorasickKind, Input, MatchKind};
+use regex::{Captures, Regex, RegexBuilder};
+
+/// Matched like a keyword, so that `unimplemented!()` calls are found along with `todo!()`
+pub const UNIMPLEMENTED: &str = "unimplemented!(";
+
+/// Macro calls left over from development, matched like keywords: `dbg!()`, and `unreachable!()`
+/// or `panic!()` with a TODO message
+pub const MARKERS: [&str; 3] = ["dbg!(", "unreachable!(\"todo", "panic!(\"todo"];
+
+/// Markers recognized along with `todo`. Unlike `todo` and the configured keywords, they're only
+/// matched in uppercase, since words like `note` and `bug` are common in comments.
+pub const KEYWORDS: [&str; 5] = ["FIXME", "HACK", "XXX", "BUG", "NOTE"];
+
+/// Which parts of the `todo` syntax a keyword accepts, besides `KEYWORD: text` and `KEYWORD? text`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Syntax {
+    /// `KEYWORD@category`
+    pub categories: bool,
+    /// `KEYWORD0`, `KEYWORD1`, ...
+    pub priorities: bool,
+}
+
+impl Default for Syntax {
+    fn default() -> Syntax {
+        Syntax { categories: true, priorities: true }
+    }
+}
+
+/// The capture groups custom patterns can use
+pub const PATTERN_GROUPS: [&str; 3] = ["text", "category", "priority"];
+
+/// Finds the keywords that start entries (`todo`, plus any configured ones like `fixme`),
+/// in any ASCII case. All keywords are searched for in a single pass, so adding more
+/// doesn't make scanning slower.
+///
+/// Conventions that don't fit the keyword syntax can be added as regex patterns.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    automaton: AhoCorasick,
+    /// Whether each of the automaton's patterns only matches in uppercase
+    uppercase: Vec<bool>,
+    patterns: Vec<Regex>,
+    /// The syntax of the keywords that don't accept all of it, by their lowercase name
+    syntax: HashMap<String, Syntax>,
+}
+
+impl Matcher {
+    /// A matcher for `todo`, the built-in [`KEYWORDS`], and `keywords`. Keywords can only contain
+    /// ASCII letters, digits, `-`, and `_`, since the characters around them are part of the syntax,
+    /// but they can start with `@` (e.g. `@todo`, used in docblocks).
+    pub fn new<S: AsRef<str>>(keywords: &[S]) -> Result<Matcher, String> {
+        let mut patterns: Vec<&str> = vec!["todo", UNIMPLEMENTED];
+        patterns.extend(MARKERS);
+
+        for keyword in keywords {
+            let keyword = keyword.as_ref();
+
+            let name = keyword.strip_prefix('@').unwrap_or(keyword);
+
+            if name.is_empty() || ! name.chars().all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_') {
+                return Err(format!("invalid keyword {:?}, keywords can only contain ASCII letters, digits, - and _, and start with @", keyword));
+            }
+
+            patterns.push(keyword);
+        }
+
+        let mut uppercase = vec![false; patterns.len()];
+
+        // Configuring a built-in keyword makes it match in any case
+        for keyword in KEYWORDS {
+            if ! patterns.iter().any(|pattern| pattern.eq_ignore_ascii_case(keyword)) {
+                patterns.push(keyword);
+                uppercase.push(true);
+            }
+        }
+
+        let automaton = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .match_kind(MatchKind::LeftmostLongest)
+            // There are only a few short patterns, so the DFA's memory use doesn't matter, and
+            // it's considerably faster than the default NFA
+            .kind(Some(AhoCorasickKind::DFA))
+            .build(patterns)
+            .map_err(|err| err.to_string())?;
+
+        Ok(Matcher { automaton, uppercase, patterns: vec![], syntax: HashMap::new() })
+    }
+
+    /// Only accept `syntax` after `keyword`, e.g. no priorities for `review`
+    pub fn with_syntax(mut self, keyword: &str, syntax: Syntax) -> Matcher {
+        self.syntax.insert(keyword.to_ascii_lowercase(), syntax);
+
+        self
+    }
+
+    /// The syntax accepted after `keyword`, as matched in the text
+    pub fn syntax(&self, keyword: &str) -> Syntax {
+        self.syntax.get(&keyword.to_ascii_lowercase()).copied().unwrap_or_default()
+    }
+
+    /// Also match `patterns`, regexes with the named capture groups `text`, `category`,
+    /// and `priority` (e.g. `0`, or `00` for todo00). `^` and `$` match at line boundaries.
+    pub fn with_patterns<S: AsRef<str>>(mut self, patterns: &[S]) -> Result<Matcher, String> {
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            let regex = RegexBuilder::new(pattern)
+                .multi_line(true)
+                .build()
+                .map_err(|err| format!("invalid pattern {:?}: {}", pattern, err))?;
+
+            if let Some(name) = regex.capture_names().flatten().find(|name| ! PATTERN_GROUPS.contains(name)) {
+                return Err(format!("unknown capture group {:?} in pattern {:?}, the groups can be text, category, and priority", name, pattern));
+            }
+
+            self.patterns.push(regex);
+        }
+
+        Ok(self)
+    }
+
+    /// The matcher used when no keywords are configured
+    pub fn todo() -> &'static Matcher {
+        static TODO: OnceLock<Matcher> = OnceLock::new();
+
+        TODO.get_or_init(|| Matcher::new::<&str>(&[]).unwrap())
+    }
+
+    /// Whether `haystack` contains any of the keywords
+    pub fn is_match(&self, haystack: &str) -> bool {
+        self.automaton.is_match(haystack)
+    }
+
+    /// The lines (split like `str::lines`) that contain any of the keywords, with their
+    /// zero-based line numbers. The whole haystack is searched at once, which is much faster
+    /// than searching it line by line when most lines don't contain keywords.
+    pub fn lines<'a>(&'a self, haystack: &'a str) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+        let mut line_num = 0;
+        let mut pos = 0;
+
+        std::iter::from_fn(move || {
+            let found = self.automaton.find(Input::new(haystack).span(pos..haystack.len()))?;
+
+            let skipped = &haystack.as_bytes()[pos..found.start()];
+            line_num += skipped.iter().filter(|byte| **byte == b'\n').count();
+            let start = skipped.iter().rposition(|byte| *byte == b'\n').map_or(pos, |newline| pos + newline + 1);
+
+            let end = haystack[found.start()..].find('\n').map_or(haystack.len(), |newline| found.start() + newline);
+            let line = haystack[start..end].strip_suffix('\r').unwrap_or(&haystack[start..end]);
+
+            let current = line_num;
+            line_num += 1;
+            pos = (end + 1).min(haystack.len());
+
+            Some((current, line))
+        })
+    }
+
+    /// The matches of the patterns, with the zero-based line numbers where they start
+    pub fn pattern_matches<'a>(&'a self, haystack: &'a str) -> impl Iterator<Item = (usize, Captures<'a>)> + 'a {
+        self.patterns.iter().flat_map(move |regex| {
+            let mut line_num = 0;
+            let mut pos = 0;
+
+            regex.captures_iter(haystack).map(move |captures| {
+                let start = captures.get(0).unwrap().start();
+                line_num += haystack.as_bytes()[pos..start].iter().filter(|byte| **byte == b'\n').count();
+                pos = start;
+
+                (line_num, captures)
+            })
+        })
+    }
+
+    /// The words (separated by whitespace, like `str::split_whitespace`) that start with one of
+    /// the keywords, in order, with the length of the keyword. [`MARKERS`] are usually nested in
+    /// an expression (`foo(dbg!(x))`), so they also start a word after any other punctuation.
+    pub fn words<'a>(&'a self, haystack: &'a str) -> impl Iterator<Item = (&'a str, usize)> + 'a {
+        self.automaton.find_iter(haystack).filter_map(move |found| {
+            let previous = haystack[..found.start()].chars().next_back();
+            let is_marker = MARKERS.iter().any(|marker| marker.eq_ignore_ascii_case(&haystack[found.range()]));
+
+            let boundary = match is_marker {
+                // Not e.g. `my_dbg!(` or `xdbg!(`
+                true => previous.is_none_or(|char| ! char.is_alphanumeric() && char != '_' && char != '!'),
+                false => previous.is_none_or(char::is_whitespace),
+            };
+
+            if ! boundary {
+                return None;
+            }
+
+            if self.uppercase[found.pattern()] && haystack[found.range()].bytes().any(|byte| byte.is_ascii_lowercase()) {
+                return None;
+            }
+
+            let rest = &haystack[found.start()..];
+            let word = &rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())];
+
+            Some((word, found.len()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matcher_test() {
+        let matcher = Matcher::new(&["fixme", "todos"]).unwrap();
+
+        assert!(matcher.is_match("// FixMe: foo"));
+        assert!(matcher.is_match("// ToDo foo"));
+        assert!(! matcher.is_match("// foo"));
+
+        // Built-in keywords are only matched in uppercase, unless they're configured
+        assert_eq!(vec![("HACK", 4), ("FixMe", 5)], matcher.words("// hack HACK note FixMe").collect::<Vec<_>>());
+
+        assert_eq!(
+            vec![("TODO:", 4), ("todos", 5), ("FIXME0", 5), ("todo", 4)],
+            matcher.words("// TODO: todos xtodo FIXME0 ñ\ttodo").collect::<Vec<_>>(),
+        );
+
+        assert_eq!(
+            vec![(1, "// todo a"), (3, "FIXME"), (4, "todo")],
+            matcher.lines("foo\n// todo a\r\nbar\nFIXME\ntodo").collect::<Vec<_>>(),
+        );
+
+        let matcher = Matcher::todo().clone().with_patterns(&[r"NOCOMMIT:\s*(?P<text>.*)$", r"^\[(?P<category>\w+)\]"]).unwrap();
+        let matches: Vec<(usize, &str)> = matcher.pattern_matches("a\n// NOCOMMIT: b\n[ui] c\nd NOCOMMIT:e")
+            .map(|(line, captures)| (line, captures.get(0).unwrap().as_str()))
+            .collect();
+
+        assert_eq!(vec![(1, "NOCOMMIT: b"), (3, "NOCOMMIT:e"), (2, "[ui]")], matches);
+        assert!(Matcher::todo().clone().with_patterns(&["(?P<txt>.*)"]).is_err());
+        assert!(Matcher::todo().clone().with_patterns(&["("]).is_err());
+
+        assert!(Matcher::new(&[""]).is_err());
+        assert!(Matcher::new(&["fix me"]).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use napi::{Error, Result};
+use napi_derive::napi;
+
+use crate::entries::{Entry, EntryData};
+use crate::matcher::Matcher;
+use crate::scanners::Scanners;
+use crate::scan::{self, Stats};
+
+/// A single TODO, with `kind` being one of `priority`, `category`, `generic`, `someday`,
+/// `unimplemented`, or `marker`, and `source` one of `code`, `todo-file`, or `readme`
+#[napi(object, js_name = "Entry")]
+pub struct JsEntry {
+    pub text: String,
+    pub file: String,
+    pub line: u32,
+    pub kind: String,
+    pub priority: Option<i32>,
+    pub category: Option<String>,
+    pub source: String,
+    pub assignee: Option<String>,
+    pub keyword: Option<String>,
+    /// More places the same task applies to, as `file:line`
+    pub also: Vec<String>,
+    pub raw: Option<String>,
+    pub stale: bool,
+    pub informational: bool,
+    pub url: Option<String>,
+}
+
+impl From<Entry> for JsEntry {
+    fn from(entry: Entry) -> JsEntry {
+        let (kind, priority, category) = match entry.data {
+            EntryData::Priority(priority) => ("priority", Some(priority as i32), None),
+            EntryData::Category(category) => ("category", None, Some(category)),
+            EntryData::Generic => ("generic", None, None),
+            EntryData::Someday => ("someday", None, None),
+            EntryData::Unimplemented => ("unimplemented", None, None),
+            EntryData::Marker => ("marker", None, None),
+        };
+
+        JsEntry {
+            text: entry.text,
+            file: entry.location.file.to_string_lossy().to_string(),
+            line: entry.location.line as u32,
+            kind: kind.to_string(),
+            priority,
+            category,
+            source: entry.source.name().to_string(),
+            assignee: entry.assignee,
+            keyword: entry.keyword,
+            also: entry.also.iter().map(|location| format!("{}:{}", location.file.to_string_lossy(), location.line)).collect(),
+            raw: entry.raw,
+            stale: entry.stale,
+            informational: entry.informational,
+            url: entry.url,
+        }
+    }
+}
+
+/// Scan a file or a directory. Excludes are relative to the scanned path
+/// and default to `node_modules` and `vendor`.
+#[napi]
+pub fn scan_path(path: String, exclude: Option<Vec<String>>) -> Result<Vec<JsEntry>> {
+    let path = PathBuf::from(path);
+    let mut entries: Vec<Entry> = vec![];
+    let mut excludes: Vec<PathBuf> = vec![];
+    let mut stats = Stats::new(0);
+
+    for name in exclude.unwrap_or(vec!["node_modules".to_string(), "vendor".to_string()]) {
+        excludes.push(scan::resolve_exclude(&path, &name));
+    }
+
+    scan::scan_path(&path, &Scanners::new(Matcher::todo().clone()), &mut entries, &excludes, &mut stats)
+        .map_err(|err| Error::from_reason(err.to_string()))?;
+
+    Ok(entries.into_iter().map(JsEntry::from).collect())
+}
+
+/// Scan a string. `filename` is only used for the entries' locations.
+#[napi]
+pub fn scan_string(str: String, filename: Option<String>) -> Vec<JsEntry> {
+    let mut entries: Vec<Entry> = vec![];
+
+    scan::scan_string(str, PathBuf::from(filename.unwrap_or("<string>".to_string())), &mut entries);
+
+    entries.into_iter().map(JsEntry::from).collect()
+}
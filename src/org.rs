@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+
+use crate::diff::read_entries;
+use crate::entries::{Entry, EntryData};
+use crate::git;
+use crate::render::write_ansi;
+use crate::schema::print_json;
+
+/// How many of the files with the most TODOs are listed
+const WORST_COUNT: usize = 10;
+
+/// Where a repository's TODOs are read from
+#[derive(Debug, PartialEq)]
+pub enum Source {
+    /// A scan created using `--format json`, e.g. an artifact of the repository's CI
+    Scan(PathBuf),
+    /// A local clone, scanned as it is at HEAD
+    Dir(PathBuf),
+    /// A git remote, fetched into the work directory and scanned at its HEAD
+    Remote(String),
+}
+
+/// A repository listed in the repos file
+#[derive(Debug, PartialEq)]
+pub struct Repository {
+    pub name: String,
+    pub source: Source,
+}
+
+/// Parse a repos file: one source per line (a remote URL, a local clone, or a `.json` scan),
+/// optionally followed by the name to show. Relative paths are relative to `base`. Empty
+/// lines and lines starting with `#` are skipped.
+pub fn parse_repos(content: &str, base: &Path) -> Vec<Repository> {
+    content.lines()
+        .map(str::trim)
+        .filter(|line| ! line.is_empty() && ! line.starts_with('#'))
+        .map(|line| {
+            let (source, name) = match line.split_once(char::is_whitespace) {
+                Some((source, name)) => (source, Some(name.trim().to_string())),
+                None => (line, None),
+            };
+
+            let source = if source.ends_with(".json") {
+                Source::Scan(base.join(source))
+            } else if is_remote(source) {
+                Source::Remote(source.to_string())
+            } else {
+                Source::Dir(base.join(source))
+            };
+
+            let name = name.unwrap_or_else(|| match &source {
+                Source::Scan(path) => path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+                Source::Dir(path) => path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                Source::Remote(url) => remote_name(url),
+            });
+
+            Repository { name, source }
+        })
+        .collect()
+}
+
+/// URLs (`https://host/org/repo`) and scp-like remotes (`git@host:org/repo`)
+fn is_remote(source: &str) -> bool {
+    source.contains("://") || source.split_once(':').is_some_and(|(host, _)| host.contains('@'))
+}
+
+/// `org/repo` for `https://github.com/org/repo.git` or `git@github.com:org/repo`
+fn remote_name(url: &str) -> String {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').map_or("", |(_, path)| path),
+        None => url.split_once(':').map_or(url, |(_, path)| path),
+    };
+
+    path.trim_end_matches('/').trim_end_matches(".git").to_string()
+}
+
+/// The TODOs of a repository, and the ones at the start of the period for git sources
+pub struct RepositoryScan {
+    pub entries: Vec<Entry>,
+    pub before: Option<Vec<Entry>>,
+}
+
+/// Scan (or read) each repository, in parallel. Remotes are fetched into `work_dir`. With
+/// `since` (a Unix timestamp), git sources are also scanned at their last commit before it.
+pub fn scan_repositories(repositories: &[Repository], work_dir: &Path, since: Option<i64>) -> Vec<Result<RepositoryScan, String>> {
+    repositories.par_iter()
+        .map(|repository| scan_repository(repository, work_dir, since))
+        .collect()
+}
+
+fn scan_repository(repository: &Repository, work_dir: &Path, since: Option<i64>) -> Result<RepositoryScan, String> {
+    let (root, git_ref) = match &repository.source {
+        Source::Scan(path) => {
+            let entries = read_entries(path).map_err(|err| format!("failed to read {}: {}", path.to_string_lossy(), err))?;
+
+            return Ok(RepositoryScan { entries, before: None });
+        },
+        Source::Dir(path) => (git::toplevel(path).map_err(|err| err.to_string())?, "HEAD"),
+        Source::Remote(url) => (fetch(url, work_dir)?, "refs/todos/head"),
+    };
+
+    let config = git::ref_config(&root, git_ref)?;
+    let entries = git::scan_repository(&root, git_ref, &config)?;
+
+    let before = match since {
+        Some(since) => match git::commit_before(&root, git_ref, since).map_err(|err| err.to_string())? {
+            Some(commit) => Some(git::scan_repository(&root, &commit, &config)?),
+            // The repository is younger than the period
+            None => Some(vec![]),
+        },
+        None => None,
+    };
+
+    Ok(RepositoryScan { entries, before })
+}
+
+/// Fetch the HEAD of `url` into a repository of its own in `work_dir`, reusing earlier fetches
+fn fetch(url: &str, work_dir: &Path) -> Result<PathBuf, String> {
+    let name: String = remote_name(url).chars()
+        .map(|char| if char.is_ascii_alphanumeric() || char == '-' || char == '.' { char } else { '_' })
+        .collect();
+    let dir = work_dir.join(name);
+
+    if ! dir.join(".git").exists() {
+        fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+        git::git(&dir, &["init", "--quiet"]).map_err(|err| err.to_string())?;
+    }
+
+    git::git(&dir, &["fetch", "--quiet", "--no-tags", url, "+HEAD:refs/todos/head"])
+        .map_err(|err| format!("failed to fetch {}: {}", url, err))?;
+
+    Ok(dir)
+}
+
+#[derive(Serialize, JsonSchema, Debug, PartialEq)]
+pub struct RepositorySummary {
+    pub name: String,
+    pub count: usize,
+    /// Entries with a priority, e.g. `todo0`
+    pub priorities: usize,
+    /// The count at the start of the period, for git sources with `--since`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count_before: Option<usize>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, PartialEq)]
+pub struct FileCount {
+    pub repository: String,
+    pub file: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, JsonSchema, Debug, PartialEq)]
+pub struct FailedRepository {
+    pub name: String,
+    pub error: String,
+}
+
+/// The shape of `org-report --format json` output
+#[derive(Serialize, JsonSchema, Debug, PartialEq)]
+pub struct OrgReport {
+    /// Most TODOs first
+    pub repositories: Vec<RepositorySummary>,
+    /// The files with the most TODOs across all repositories
+    pub worst_files: Vec<FileCount>,
+    pub failed: Vec<FailedRepository>,
+}
+
+pub fn org_report(repositories: &[Repository], scans: Vec<Result<RepositoryScan, String>>) -> OrgReport {
+    let mut report = OrgReport { repositories: vec![], worst_files: vec![], failed: vec![] };
+
+    for (repository, scan) in repositories.iter().zip(scans) {
+        let scan = match scan {
+            Ok(scan) => scan,
+            Err(error) => {
+                report.failed.push(FailedRepository { name: repository.name.clone(), error });
+                continue;
+            },
+        };
+
+        let mut files: HashMap<String, usize> = HashMap::new();
+
+        for entry in &scan.entries {
+            *files.entry(entry.location.file.to_string_lossy().replace('\\', "/")).or_default() += 1;
+        }
+
+        report.worst_files.extend(files.into_iter().map(|(file, count)| FileCount { repository: repository.name.clone(), file, count }));
+
+        report.repositories.push(RepositorySummary {
+            name: repository.name.clone(),
+            count: scan.entries.len(),
+            priorities: scan.entries.iter().filter(|entry| matches!(entry.data, EntryData::Priority(_))).count(),
+            count_before: scan.before.map(|before| before.len()),
+        });
+    }
+
+    report.repositories.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    report.worst_files.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| (&a.repository, &a.file).cmp(&(&b.repository, &b.file))));
+    report.worst_files.truncate(WORST_COUNT);
+
+    report
+}
+
+impl OrgReport {
+    pub fn count(&self) -> usize {
+        self.repositories.iter().map(|repository| repository.count).sum()
+    }
+
+    /// The change in the total count over the period, if all repositories have a trend
+    pub fn change(&self) -> Option<i64> {
+        self.repositories.iter()
+            .map(|repository| Some(repository.count as i64 - repository.count_before? as i64))
+            .sum()
+    }
+}
+
+/// `period` is e.g. `30d`, when `--since` is used
+pub fn render_org_report(report: &OrgReport, period: Option<&str>) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, "# Organization TODOs", true);
+    write!(stdout, "\n\n{} TODOs in {} repositories", report.count(), report.repositories.len()).unwrap();
+
+    if let (Some(period), Some(change)) = (period, report.change()) {
+        write!(stdout, " ({:+} in the last {})", change, period).unwrap();
+    }
+
+    writeln!(stdout).unwrap();
+
+    writeln!(stdout).unwrap();
+    write_ansi(&mut stdout, Color::Green, "## Repositories", true);
+    writeln!(stdout).unwrap();
+
+    for repository in &report.repositories {
+        write_ansi(&mut stdout, Color::Ansi256(243), "- ", false);
+        write_ansi(&mut stdout, Color::Blue, &repository.name, true);
+        write!(stdout, ": {} TODOs, {} with a priority", repository.count, repository.priorities).unwrap();
+
+        if let Some(before) = repository.count_before {
+            let change = repository.count as i64 - before as i64;
+            let color = if change > 0 { Color::Red } else { Color::Green };
+
+            write_ansi(&mut stdout, color, &format!(" ({:+})", change), false);
+        }
+
+        writeln!(stdout).unwrap();
+    }
+
+    if ! report.worst_files.is_empty() {
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, Color::Red, "## Worst offenders", true);
+        writeln!(stdout).unwrap();
+
+        for (index, file) in report.worst_files.iter().enumerate() {
+            write_ansi(&mut stdout, Color::Ansi256(243), &format!("{}. ", index + 1), false);
+            write_ansi(&mut stdout, Color::Cyan, &format!("{}: {}", file.repository, file.file), true);
+            writeln!(stdout, " ({} TODOs)", file.count).unwrap();
+        }
+    }
+
+    if ! report.failed.is_empty() {
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, Color::Red, &format!("## Failed ({})", report.failed.len()), true);
+        writeln!(stdout).unwrap();
+
+        for failed in &report.failed {
+            write_ansi(&mut stdout, Color::Ansi256(243), "- ", false);
+            writeln!(stdout, "{}: {}", failed.name, failed.error).unwrap();
+        }
+    }
+
+    stdout.flush().unwrap();
+}
+
+pub fn render_org_report_json(report: OrgReport) {
+    print_json(report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::scan_string;
+
+    #[test]
+    fn parse_repos_test() {
+        let repos = parse_repos("# Platform\nhttps://github.com/org/api.git\ngit@github.com:org/web\n\n../billing\nscans/mobile.json Mobile app\n", Path::new("/org"));
+
+        assert_eq!(vec![
+            Repository { name: "org/api".to_string(), source: Source::Remote("https://github.com/org/api.git".to_string()) },
+            Repository { name: "org/web".to_string(), source: Source::Remote("git@github.com:org/web".to_string()) },
+            Repository { name: "billing".to_string(), source: Source::Dir(PathBuf::from("/org/../billing")) },
+            Repository { name: "Mobile app".to_string(), source: Source::Scan(PathBuf::from("/org/scans/mobile.json")) },
+        ], repos);
+    }
+
+    #[test]
+    fn org_report_test() {
+        let scan = |files: &[(&str, &str)]| {
+            let mut entries: Vec<Entry> = vec![];
+
+            for (file, content) in files {
+                scan_string(content.to_string(), PathBuf::from(file), &mut entries);
+            }
+
+            entries
+        };
+
+        let repositories = parse_repos("a.json\nb.json\nc.json\n", Path::new(""));
+        let report = org_report(&repositories, vec![
+            Ok(RepositoryScan { entries: scan(&[("x.rs", "// todo0 a\n// todo b\n"), ("y.rs", "// todo c\n")]), before: Some(scan(&[("x.rs", "// todo a\n")])) }),
+            Ok(RepositoryScan { entries: scan(&[("z.rs", "// todo a\n// todo b\n// todo c\n")]), before: None }),
+            Err("failed to fetch".to_string()),
+        ]);
+
+        // Ties are sorted by name
+        assert_eq!(vec![
+            RepositorySummary { name: "a".to_string(), count: 3, priorities: 1, count_before: Some(1) },
+            RepositorySummary { name: "b".to_string(), count: 3, priorities: 0, count_before: None },
+        ], report.repositories);
+
+        assert_eq!(vec![("b", "z.rs", 3), ("a", "x.rs", 2), ("a", "y.rs", 1)], report.worst_files.iter().map(|file| (file.repository.as_str(), file.file.as_str(), file.count)).collect::<Vec<_>>());
+        assert_eq!(vec![FailedRepository { name: "c".to_string(), error: "failed to fetch".to_string() }], report.failed);
+        assert_eq!(6, report.count());
+        assert_eq!(None, report.change());
+    }
+}
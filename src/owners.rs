@@ -0,0 +1,142 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern};
+
+use crate::config::SortOrder;
+use crate::entries::Entry;
+use crate::render::sort_entries;
+
+/// Where GitHub and GitLab look for the file, in order
+const LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+struct Rule {
+    patterns: Vec<Pattern>,
+    owners: Vec<String>,
+}
+
+/// The rules of a CODEOWNERS file. Like in git and GitHub, the last matching rule wins.
+pub struct CodeOwners {
+    rules: Vec<Rule>,
+}
+
+impl CodeOwners {
+    /// Read the CODEOWNERS file of the repository at `root`, if it has one
+    pub fn find(root: &Path) -> io::Result<Option<CodeOwners>> {
+        for location in LOCATIONS {
+            let path = root.join(location);
+
+            if path.is_file() {
+                return Ok(Some(CodeOwners::parse(&fs::read_to_string(path)?)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Lines that can't be parsed as a pattern are skipped, the same way GitHub ignores them
+    pub fn parse(content: &str) -> CodeOwners {
+        let mut rules: Vec<Rule> = vec![];
+
+        for line in content.lines() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line);
+            let mut parts = line.split_whitespace();
+
+            let Some(pattern) = parts.next() else {
+                continue;
+            };
+
+            if let Some(patterns) = patterns(pattern) {
+                rules.push(Rule { patterns, owners: parts.map(String::from).collect() });
+            }
+        }
+
+        CodeOwners { rules }
+    }
+
+    /// The owners of a path relative to the repository root, using `/` as the separator
+    pub fn owners(&self, path: &str) -> &[String] {
+        self.rules.iter()
+            .rev()
+            .find(|rule| rule.patterns.iter().any(|pattern| pattern.matches_with(path, MATCH_OPTIONS)))
+            .map_or(&[], |rule| rule.owners.as_slice())
+    }
+}
+
+/// Glob patterns matching the same paths as a gitignore-style CODEOWNERS pattern
+fn patterns(pattern: &str) -> Option<Vec<Pattern>> {
+    let directory = pattern.ends_with('/');
+    let trimmed = pattern.trim_matches('/');
+
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    // Patterns without a slash (other than a trailing one) match at any depth
+    let anchored = pattern.starts_with('/') || trimmed.contains('/');
+    let glob = if anchored { trimmed.to_string() } else { format!("**/{}", trimmed) };
+
+    // A matching directory owns everything in it
+    let mut globs = vec![format!("{}/**", glob)];
+
+    if ! directory {
+        globs.push(glob);
+    }
+
+    globs.iter().map(|glob| Pattern::new(glob).ok()).collect()
+}
+
+/// Entries grouped by their owners (e.g. `@org/frontend`), sorted by name, with the entries
+/// without owners last. Entries with several owners are grouped under all of them together.
+pub fn group_by_owner(mut entries: Vec<Entry>, owners: &CodeOwners, root: &Path, sort: SortOrder) -> Vec<(Option<String>, Vec<Entry>)> {
+    sort_entries(&mut entries, sort);
+
+    let mut groups: Vec<(Option<String>, Vec<Entry>)> = vec![];
+
+    for entry in entries {
+        let owner = Some(owners.owners(&entry.relative_path(root)).join(" ")).filter(|owner| ! owner.is_empty());
+
+        match groups.iter_mut().find(|(group, _)| *group == owner) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((owner, vec![entry])),
+        }
+    }
+
+    groups.sort_by(|a, b| a.0.is_none().cmp(&b.0.is_none()).then_with(|| a.0.cmp(&b.0)));
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owners_test() {
+        let owners = CodeOwners::parse(r#"
+            # Default owners
+            *                   @org/core
+            /docs/              @org/docs
+            *.css               @org/frontend @alice # styles
+            src/api/**          @org/backend
+            /src/api/legacy.rs
+        "#);
+
+        assert_eq!(["@org/core"], owners.owners("Cargo.toml"));
+        assert_eq!(["@org/docs"], owners.owners("docs/guide/index.md"));
+        assert_eq!(["@org/frontend", "@alice"], owners.owners("docs/theme/main.css"));
+        assert_eq!(["@org/backend"], owners.owners("src/api/users/mod.rs"));
+        assert_eq!(["@org/core"], owners.owners("src/main.rs"));
+        assert!(owners.owners("src/api/legacy.rs").is_empty());
+
+        // Directory patterns don't match files of the same name
+        assert_eq!(["@org/core"], owners.owners("docs"));
+    }
+}
@@ -0,0 +1,132 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// The longest path that works without the `\\?\` prefix on Windows
+const MAX_PATH: usize = 260;
+
+/// `std::fs::canonicalize`, but without the `\\?\` prefix it adds on Windows. The prefix
+/// breaks editor click-through and makes the paths look different from the ones in the
+/// walk, git, and user input.
+pub fn canonicalize(path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    Ok(normalize(std::fs::canonicalize(path)?))
+}
+
+/// Paths using the platform's separators (only `\` on Windows, where git and user input
+/// may use `/`), without an unnecessary verbatim prefix
+pub fn normalize(path: PathBuf) -> PathBuf {
+    if cfg!(windows) {
+        return PathBuf::from(normalize_windows(&path.to_string_lossy()));
+    }
+
+    path
+}
+
+/// `path` joined onto `base` (unless it's absolute), with `.` and `..` resolved without
+/// reading the file system, so `../shared/todo.md` is a sibling of `base`
+pub fn resolve(base: &Path, path: impl AsRef<Path>) -> PathBuf {
+    let mut resolved = PathBuf::new();
+
+    for component in base.join(path).components() {
+        match component {
+            Component::CurDir => {},
+            // The parent of the root is the root
+            Component::ParentDir if resolved.parent().is_some() => {
+                resolved.pop();
+            },
+            Component::ParentDir => {},
+            component => resolved.push(component),
+        }
+    }
+
+    resolved
+}
+
+/// `path` relative to `base`, going up with `..` if `base` isn't one of its ancestors.
+/// Relative paths, and paths that can't be made relative (e.g. on a different drive), are
+/// returned unchanged.
+pub fn relative(path: &Path, base: &Path) -> PathBuf {
+    if ! path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+
+    // Paths on different drives don't have anything in common
+    if path_components.peek() != base_components.peek() {
+        return path.to_path_buf();
+    }
+
+    while path_components.peek().is_some() && path_components.peek() == base_components.peek() {
+        path_components.next();
+        base_components.next();
+    }
+
+    let mut relative = PathBuf::new();
+
+    for component in base_components {
+        if component != Component::CurDir {
+            relative.push("..");
+        }
+    }
+
+    relative.extend(path_components);
+
+    relative
+}
+
+/// Use `\` as the only separator and strip the verbatim prefix when it isn't needed:
+/// `\\?\C:\foo` becomes `C:\foo` and `\\?\UNC\server\share` becomes `\\server\share`.
+pub fn normalize_windows(path: &str) -> String {
+    let path = path.replace('/', "\\");
+
+    let stripped = match path.strip_prefix(r"\\?\") {
+        Some(unc) if unc.starts_with(r"UNC\") => format!(r"\\{}", &unc[4..]),
+        Some(local) if local.as_bytes().get(1) == Some(&b':') => local.to_string(),
+        _ => return path,
+    };
+
+    if stripped.len() >= MAX_PATH {
+        return path;
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_test() {
+        assert_eq!(PathBuf::from("src/main.rs"), relative(Path::new("/repo/src/main.rs"), Path::new("/repo")));
+        assert_eq!(PathBuf::from("main.rs"), relative(Path::new("/repo/src/main.rs"), Path::new("/repo/src/")));
+        assert_eq!(PathBuf::from("../README.md"), relative(Path::new("/repo/README.md"), Path::new("/repo/src")));
+        assert_eq!(PathBuf::from("../../other/foo.rs"), relative(Path::new("/other/foo.rs"), Path::new("/repo/src")));
+        assert_eq!(PathBuf::from("src/main.rs"), relative(Path::new("src/main.rs"), Path::new("/repo")));
+    }
+
+    #[test]
+    fn resolve_test() {
+        assert_eq!(PathBuf::from("/repo/todo.md"), resolve(Path::new("/repo"), "todo.md"));
+        assert_eq!(PathBuf::from("/repo/docs/todo.md"), resolve(Path::new("/repo"), "./docs/todo.md"));
+        assert_eq!(PathBuf::from("/shared/todo.md"), resolve(Path::new("/repo"), "../shared/todo.md"));
+        assert_eq!(PathBuf::from("/todo.md"), resolve(Path::new("/repo"), "../../todo.md"));
+        assert_eq!(PathBuf::from("/elsewhere/README.md"), resolve(Path::new("/repo"), "/elsewhere/README.md"));
+    }
+
+    #[test]
+    fn normalize_windows_test() {
+        assert_eq!(r"C:\Users\foo\src\main.rs", normalize_windows(r"\\?\C:\Users\foo\src\main.rs"));
+        assert_eq!(r"\\server\share\src\main.rs", normalize_windows(r"\\?\UNC\server\share\src\main.rs"));
+
+        // From git: `rev-parse --show-toplevel` uses forward slashes, joined with a `/`-separated file
+        assert_eq!(r"C:\Users\foo\src\main.rs", normalize_windows(r"C:/Users/foo\src/main.rs"));
+
+        assert_eq!(r"C:\foo", normalize_windows(r"C:\foo"));
+        assert_eq!(r"\\?\Volume{1234}\foo", normalize_windows(r"\\?\Volume{1234}\foo"));
+
+        let long = format!(r"\\?\C:\{}", "a".repeat(300));
+        assert_eq!(long, normalize_windows(&long));
+    }
+}
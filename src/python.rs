@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyIOError;
+
+use crate::entries::{Entry, EntryData};
+use crate::matcher::Matcher;
+use crate::scanners::Scanners;
+use crate::scan::{self, Stats};
+
+/// A single TODO, with `kind` being one of `priority`, `category`, `generic`, `someday`,
+/// `unimplemented`, or `marker`, and `source` one of `code`, `todo-file`, or `readme`
+#[pyclass(name = "Entry", module = "todo_system", get_all, frozen)]
+pub struct PyEntry {
+    text: String,
+    file: String,
+    line: usize,
+    kind: String,
+    priority: Option<isize>,
+    category: Option<String>,
+    source: String,
+    assignee: Option<String>,
+    keyword: Option<String>,
+    /// More places the same task applies to, as `file:line`
+    also: Vec<String>,
+    raw: Option<String>,
+    stale: bool,
+    informational: bool,
+    url: Option<String>,
+}
+
+#[pymethods]
+impl PyEntry {
+    fn __repr__(&self) -> String {
+        format!("Entry(kind={:?}, text={:?}, file={:?}, line={})", self.kind, self.text, self.file, self.line)
+    }
+}
+
+impl From<Entry> for PyEntry {
+    fn from(entry: Entry) -> PyEntry {
+        let (kind, priority, category) = match entry.data {
+            EntryData::Priority(priority) => ("priority", Some(priority), None),
+            EntryData::Category(category) => ("category", None, Some(category)),
+            EntryData::Generic => ("generic", None, None),
+            EntryData::Someday => ("someday", None, None),
+            EntryData::Unimplemented => ("unimplemented", None, None),
+            EntryData::Marker => ("marker", None, None),
+        };
+
+        PyEntry {
+            text: entry.text,
+            file: entry.location.file.to_string_lossy().to_string(),
+            line: entry.location.line,
+            kind: kind.to_string(),
+            priority,
+            category,
+            source: entry.source.name().to_string(),
+            assignee: entry.assignee,
+            keyword: entry.keyword,
+            also: entry.also.iter().map(|location| format!("{}:{}", location.file.to_string_lossy(), location.line)).collect(),
+            raw: entry.raw,
+            stale: entry.stale,
+            informational: entry.informational,
+            url: entry.url,
+        }
+    }
+}
+
+/// Scan a file or a directory. Excludes are relative to the scanned path.
+#[pyfunction]
+#[pyo3(signature = (path, exclude = vec!["node_modules".to_string(), "vendor".to_string()]))]
+fn scan_path(path: PathBuf, exclude: Vec<String>) -> PyResult<Vec<PyEntry>> {
+    let mut entries: Vec<Entry> = vec![];
+    let mut excludes: Vec<PathBuf> = vec![];
+    let mut stats = Stats::new(0);
+
+    for name in exclude {
+        excludes.push(scan::resolve_exclude(&path, &name));
+    }
+
+    scan::scan_path(&path, &Scanners::new(Matcher::todo().clone()), &mut entries, &excludes, &mut stats)
+        .map_err(|err| PyIOError::new_err(err.to_string()))?;
+
+    Ok(entries.into_iter().map(PyEntry::from).collect())
+}
+
+/// Scan a string. `filename` is only used for the entries' locations.
+#[pyfunction]
+#[pyo3(signature = (str, filename = "<string>".to_string()))]
+fn scan_string(str: String, filename: String) -> Vec<PyEntry> {
+    let mut entries: Vec<Entry> = vec![];
+
+    scan::scan_string(str, PathBuf::from(filename), &mut entries);
+
+    entries.into_iter().map(PyEntry::from).collect()
+}
+
+#[pymodule]
+fn todo_system(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyEntry>()?;
+    m.add_function(wrap_pyfunction!(scan_path, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_string, m)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+
+use crate::config::FrontMatter;
+use crate::entries::{Entry, EntryData, Location};
+use crate::matcher::Matcher;
+use crate::render::write_ansi;
+use crate::scan::read_text;
+use crate::schema::print_json;
+
+/// A rewritten source line, e.g. renaming a category
+#[derive(Serialize, JsonSchema, Debug, PartialEq, Clone)]
+pub struct RenamedLine {
+    pub location: Location,
+    pub before: String,
+    pub after: String,
+}
+
+/// The shape of `rename-category --format json` output
+#[derive(Serialize, JsonSchema)]
+pub struct RenameReport {
+    pub from: String,
+    pub to: String,
+    pub lines: Vec<RenamedLine>,
+}
+
+/// Whether `category` is `from` or one of its subcategories, e.g. `backend/auth` for `backend`
+fn is_renamed(category: &str, from: &str) -> bool {
+    category.strip_prefix(from).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// The part of a category before its last `/`, e.g. `backend` for `backend/auth`
+fn parent(category: &str) -> &str {
+    category.rfind('/').map_or("", |slash| &category[..slash])
+}
+
+/// The lines renaming the category `from` (and its subcategories) to `to`: the `todo@from`
+/// markers of `entries` in code, and the `## from` headings of the `todo_files`. Subcategories
+/// are headings below their parent, so they can only be renamed within it.
+pub fn rename_category(entries: &[Entry], todo_files: &[PathBuf], matcher: &Matcher, from: &str, to: &str) -> Result<Vec<RenamedLine>, String> {
+    if from.contains('/') && parent(from) != parent(to) {
+        return Err(format!("{} can only be renamed within {}, e.g. to {}/{}", from, parent(from), parent(from), to.rsplit('/').next().unwrap()));
+    }
+
+    let mut lines: Vec<RenamedLine> = vec![];
+
+    for file in todo_files {
+        let Ok((content, _)) = read_text(file) else {
+            continue;
+        };
+
+        let file: Arc<Path> = file.as_path().into();
+
+        for (line, (before, after)) in rename_headings(&content, from, to) {
+            lines.push(RenamedLine { location: Location { file: file.clone(), line }, before, after });
+        }
+    }
+
+    // Unique and sorted, since duplicate entries are merged into one with `also` locations
+    let mut locations: BTreeSet<(Arc<Path>, usize)> = BTreeSet::new();
+
+    for entry in entries {
+        if ! matches!(&entry.data, EntryData::Category(category) if is_renamed(category, from)) {
+            continue;
+        }
+
+        for location in std::iter::once(&entry.location).chain(&entry.also) {
+            if ! todo_files.iter().any(|file| *file == *location.file) {
+                locations.insert((location.file.clone(), location.line));
+            }
+        }
+    }
+
+    let mut contents: HashMap<Arc<Path>, Option<Vec<String>>> = HashMap::new();
+
+    for (file, line) in locations {
+        let file_lines = contents.entry(file.clone())
+            .or_insert_with_key(|file| read_text(file).ok().map(|(content, _)| content.lines().map(String::from).collect()));
+
+        let Some(before) = line.checked_sub(1).and_then(|index| file_lines.as_ref()?.get(index)) else {
+            continue;
+        };
+
+        // e.g. categories from `[categories] paths` or org-mode tags
+        let Some(after) = rename_marker(before, matcher, from, to) else {
+            continue;
+        };
+
+        lines.push(RenamedLine { location: Location { file, line }, before: before.clone(), after });
+    }
+
+    Ok(lines)
+}
+
+/// `line` with the category of its `todo@from` marker renamed, e.g. `// TODO@api foo` becomes
+/// `// TODO@backend foo`. `None` if it doesn't have one.
+fn rename_marker(line: &str, matcher: &Matcher, from: &str, to: &str) -> Option<String> {
+    matcher.words(line).find_map(|(word, keyword_len)| {
+        let suffix = &word[keyword_len..];
+        let at = suffix.find('@')?;
+        let category = suffix[at + 1..].split('@').next().unwrap();
+
+        if ! is_renamed(category, from) {
+            return None;
+        }
+
+        // `word` is a slice of `line`
+        let start = word.as_ptr() as usize - line.as_ptr() as usize + keyword_len + at + 1;
+
+        Some(format!("{}{}{}", &line[..start], to, &line[start + from.len()..]))
+    })
+}
+
+/// The headings of a todo.md for the category `from`, with their line numbers, before and after
+/// renaming them to the last part of `to`
+fn rename_headings(content: &str, from: &str, to: &str) -> Vec<(usize, (String, String))> {
+    let name = to.rsplit('/').next().unwrap();
+    let mut headings: Vec<(usize, &str)> = vec![];
+    let mut renamed = vec![];
+    let front_matter_lines = FrontMatter::split(content).map_or(0, |(_, lines)| lines);
+
+    for (line_num, line) in content.lines().enumerate().skip(front_matter_lines) {
+        if ! line.starts_with('#') {
+            continue;
+        }
+
+        let level = line.len() - line.trim_start_matches('#').len();
+        let heading = line.trim_start_matches('#').trim();
+
+        headings.retain(|(parent_level, _)| *parent_level < level);
+        headings.push((level, heading));
+
+        if headings.iter().map(|(_, heading)| *heading).collect::<Vec<_>>().join("/") != from {
+            continue;
+        }
+
+        let start = level + line[level..].find(heading).unwrap();
+        let after = format!("{}{}{}", &line[..start], name, &line[start + heading.len()..]);
+
+        renamed.push((line_num + 1, (line.to_string(), after)));
+    }
+
+    renamed
+}
+
+/// Rewrite the lines, skipping the ones that changed since they were read. Returns the number
+/// of rewritten lines.
+pub fn apply(lines: &[RenamedLine]) -> io::Result<usize> {
+    let mut by_file: HashMap<&Path, Vec<&RenamedLine>> = HashMap::new();
+
+    for line in lines {
+        by_file.entry(&line.location.file).or_default().push(line);
+    }
+
+    let mut count = 0;
+
+    for (file, renamed) in by_file {
+        let content = fs::read_to_string(file)?;
+        let mut file_lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+        let mut changed = false;
+
+        for renamed in renamed {
+            let Some(line) = renamed.location.line.checked_sub(1).and_then(|index| file_lines.get_mut(index)) else {
+                continue;
+            };
+
+            let (text, ending) = line.split_at(line.trim_end_matches(['\n', '\r']).len());
+
+            if text != renamed.before {
+                continue;
+            }
+
+            *line = format!("{}{}", renamed.after, ending);
+            changed = true;
+            count += 1;
+        }
+
+        if changed {
+            fs::write(file, file_lines.concat())?;
+        }
+    }
+
+    Ok(count)
+}
+
+pub fn render_renamed(lines: &[RenamedLine], from: &str, to: &str) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, &format!("# Renaming {} to {}", from, to), true);
+    write!(stdout, "\n\n").unwrap();
+
+    if lines.is_empty() {
+        writeln!(stdout, "No TODOs use the category {}.", from).unwrap();
+    }
+
+    for line in lines {
+        write_ansi(&mut stdout, Color::Cyan, &format!("{}:{}", line.location.file.to_string_lossy(), line.location.line), true);
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, Color::Red, &format!("  - {}", line.before.trim()), false);
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, Color::Green, &format!("  + {}", line.after.trim()), false);
+        writeln!(stdout).unwrap();
+    }
+
+    stdout.flush().unwrap();
+}
+
+pub fn render_renamed_json(lines: Vec<RenamedLine>, from: &str, to: &str) {
+    print_json(RenameReport { from: from.to_string(), to: to.to_string(), lines });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn rename_marker_test() {
+        let matcher = Matcher::todo();
+
+        assert_eq!(Some("// TODO@backend foo".to_string()), rename_marker("// TODO@api foo", matcher, "api", "backend"));
+        assert_eq!(Some("# todo@backend/v2 foo".to_string()), rename_marker("# todo@api/v2 foo", matcher, "api", "backend"));
+        assert_eq!(Some("// FIXME@backend foo".to_string()), rename_marker("// FIXME@api foo", matcher, "api", "backend"));
+        assert_eq!(None, rename_marker("// todo@apis foo", matcher, "api", "backend"));
+        assert_eq!(None, rename_marker("// todo foo @api", matcher, "api", "backend"));
+        assert_eq!(None, rename_marker("// todo0 foo", matcher, "api", "backend"));
+    }
+
+    #[test]
+    fn rename_category_test() {
+        let dir = TempDir::new("rename");
+
+        let todo_file = dir.join("todo.md");
+        let code = dir.join("main.rs");
+        fs::write(&todo_file, "## api\n- foo\n\n### v2 \n- bar\n\n## apis\n- baz\n").unwrap();
+        fs::write(&code, "// todo@api foo\r\n// todo@apis bar\r\n// todo@api/v2 baz\r\n").unwrap();
+
+        let mut entries: Vec<Entry> = vec![];
+        crate::scan::scan_string(fs::read_to_string(&code).unwrap(), code.clone(), &mut entries);
+        crate::scan::scan_todo_string(&fs::read_to_string(&todo_file).unwrap(), &todo_file, &mut entries);
+
+        let renamed = rename_category(&entries, std::slice::from_ref(&todo_file), Matcher::todo(), "api", "backend").unwrap();
+
+        assert_eq!(vec![
+            (todo_file.as_path(), 1, "## backend"),
+            (code.as_path(), 1, "// todo@backend foo"),
+            (code.as_path(), 3, "// todo@backend/v2 baz"),
+        ], renamed.iter().map(|line| (&*line.location.file, line.location.line, line.after.as_str())).collect::<Vec<_>>());
+
+        let subcategory = rename_category(&entries, std::slice::from_ref(&todo_file), Matcher::todo(), "api/v2", "api/next").unwrap();
+        assert_eq!(vec!["### next ", "// todo@api/next baz"], subcategory.iter().map(|line| line.after.as_str()).collect::<Vec<_>>());
+        assert!(rename_category(&entries, &[], Matcher::todo(), "api/v2", "next").is_err());
+
+        assert_eq!(3, apply(&renamed).unwrap());
+        assert_eq!("## backend\n- foo\n\n### v2 \n- bar\n\n## apis\n- baz\n", fs::read_to_string(&todo_file).unwrap());
+        assert_eq!("// todo@backend foo\r\n// todo@apis bar\r\n// todo@backend/v2 baz\r\n", fs::read_to_string(&code).unwrap());
+
+        // Lines that changed since they were read are skipped
+        assert_eq!(0, apply(&renamed).unwrap());
+    }
+}
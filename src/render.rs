@@ -1,125 +1,1198 @@
-use std::io::Write;
-use std::collections::HashMap;
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use std::io::{self, Write};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use termcolor::{BufferedStandardStream, Color, ColorChoice, ColorSpec, WriteColor};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 use std::cmp::Ordering::{Less, Equal, Greater};
 
+use crate::config::{BulletStyle, Config, Locations, SortOrder};
 use crate::entries::{Entry, EntryData};
+use crate::schema::{print_json, versioned_json};
 
 impl Entry {
-    pub fn render(&self) {
-        let mut stdout = StandardStream::stdout(ColorChoice::Auto);
-        write_ansi(&mut stdout, Color::Ansi256(243), "- [ ] ", false);
+    /// `file:line`, with the configured prefix replaced, as a markdown link if a URL is configured
+    pub fn rendered_location(&self, config: &Locations) -> String {
+        match self.location_link(config) {
+            (location, Some(url)) => format!("[{}]({})", location, url),
+            (location, None) => location,
+        }
+    }
+
+    /// The rendered location, followed by `also at` and the entry's other locations
+    pub fn rendered_locations(&self, config: &Locations) -> String {
+        let links: Vec<String> = self.location_links(config).into_iter()
+            .map(|link| match link {
+                (location, Some(url)) => format!("[{}]({})", location, url),
+                (location, None) => location,
+            })
+            .collect();
+
+        also_at(links)
+    }
+
+    /// The location links (see [`Entry::location_link`]) of the entry's location, followed by
+    /// its other locations
+    pub fn location_links(&self, config: &Locations) -> Vec<(String, Option<String>)> {
+        let also = self.also.iter().map(|location| {
+            let mut entry = self.clone();
+            entry.location = location.clone();
+            entry.location_link(config)
+        });
+
+        std::iter::once(self.location_link(config)).chain(also).collect()
+    }
+
+    /// `file:line` with the configured prefix replaced, and the configured URL of the location
+    pub fn location_link(&self, config: &Locations) -> (String, Option<String>) {
+        let mut file = self.location.file.to_string_lossy().to_string();
+
+        let prefix = config.prefixes.keys()
+            .filter(|prefix| file.starts_with(prefix.as_str()))
+            .max_by_key(|prefix| prefix.len());
+
+        if let Some(prefix) = prefix {
+            file = format!("{}{}", config.prefixes[prefix], &file[prefix.len()..]);
+        }
+
+        let location = format!("{}:{}", file, self.location.line);
+
+        let Some(url) = &config.url else {
+            return (location, None);
+        };
+
+        let mut entry = self.clone();
+        entry.location.file = PathBuf::from(file.replace('\\', "/")).into();
+
+        let url = url.render(&entry, Path::new(""));
 
-        let location = format!("{}:{}", self.location.file.to_string_lossy(), self.location.line);
+        (location, Some(url))
+    }
+
+    /// The color of the entry's section heading
+    pub fn color(&self, config: &Config) -> Color {
+        let (section, default_color) = match &self.data {
+            EntryData::Priority(priority) => (priority_notation(*priority), Color::Red),
+            EntryData::Category(category) => (category.clone(), Color::Green),
+            EntryData::Generic => ("other".to_string(), Color::White),
+            EntryData::Someday => ("someday".to_string(), Color::Ansi256(243)),
+            EntryData::Unimplemented => ("unimplemented".to_string(), Color::Magenta),
+            EntryData::Marker => ("markers".to_string(), Color::Magenta),
+        };
+
+        config.color(&section).unwrap_or(default_color)
+    }
 
-        if self.text.len() > 0 {
-            write_ansi(&mut stdout, Color::Blue, self.text.as_str(), true);
-            write_ansi(&mut stdout, Color::Ansi256(243), format!(" ({})", location).as_str(), false);
+    /// The color of the entry's badge: the color of its keyword, if it isn't `todo`
+    fn badge_color(&self, config: &Config) -> Color {
+        if let Some(color) = self.keyword.as_deref().and_then(|keyword| config.keyword_color(keyword)) {
+            return color;
+        }
+
+        match self.keyword.as_deref() {
+            Some("fixme" | "bug") => Color::Red,
+            Some("hack" | "xxx") => Color::Yellow,
+            Some("note") => Color::Cyan,
+            Some(_) => Color::Magenta,
+            None => self.color(config),
+        }
+    }
+
+    /// Write the entry as a checklist item (or another configured bullet style), at `index` in
+    /// its list. The parsed marker (e.g. `todo0` or `todo@ui`) is highlighted before the text with
+    /// `show_kind`, e.g. when the section heading doesn't already show it, and the assignee and
+    /// keyword (e.g. `FIXME`) always are.
+    pub fn render(&self, out: &mut (impl WriteColor + ?Sized), config: &Config, show_kind: bool, index: usize) {
+        write_ansi(out, Color::Ansi256(243), &config.sections.bullets.bullet(index), false);
+
+        if self.stale {
+            write_ansi(out, Color::Yellow, "⚠ ", true);
+        }
+
+        // Section headings never show the keyword
+        let show_badge = (show_kind && self.data != EntryData::Generic) || self.keyword.is_some();
+
+        if show_badge {
+            write_ansi(out, self.badge_color(config), &self.badge(), true);
+        }
+
+        if let Some(assignee) = &self.assignee {
+            write_ansi(out, Color::Magenta, &format!("({})", assignee), true);
+        }
+
+        if show_badge || self.assignee.is_some() {
+            write!(out, " ").unwrap();
+        }
+
+        let location = self.rendered_locations(&config.locations);
+
+        if ! self.text.is_empty() {
+            write_ansi(out, Color::Blue, self.text.as_str(), true);
+            write_ansi(out, Color::Ansi256(243), format!(" ({})", location).as_str(), false);
         } else {
-            write_ansi(&mut stdout, Color::Cyan, &location.as_str(), true);
+            write_ansi(out, Color::Cyan, location.as_str(), true);
         }
 
-        write!(&mut stdout, "\n").unwrap();
+        writeln!(out).unwrap();
     }
 }
 
 
-pub fn write_ansi(stdout: &mut StandardStream, color: Color, text: &str, bold: bool) {
-    stdout.set_color(
+/// `primary, also at second, third`
+fn also_at(mut locations: Vec<String>) -> String {
+    let primary = locations.remove(0);
+
+    match locations.is_empty() {
+        true => primary,
+        false => format!("{}, also at {}", primary, locations.join(", ")),
+    }
+}
+
+pub fn write_ansi(out: &mut (impl WriteColor + ?Sized), color: Color, text: &str, bold: bool) {
+    out.set_color(
         ColorSpec::new()
             .set_fg(Some(color))
             .set_bold(bold)
     ).unwrap();
 
-    write!(stdout, "{text}").unwrap();
+    write!(out, "{text}").unwrap();
+
+    out.reset().unwrap();
+}
+
+/// Pad or truncate `text` to exactly `width` terminal columns. Wide characters (CJK, emoji)
+/// take up two columns, and truncation never splits a grapheme cluster. Truncated text ends with `…`.
+pub fn fit(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return format!("{}{}", text, " ".repeat(width - text.width()));
+    }
+
+    let mut fitted = String::new();
+    let mut fitted_width = 0;
+
+    // Leave a column for the ellipsis
+    for grapheme in text.graphemes(true) {
+        if fitted_width + grapheme.width() + 1 > width {
+            break;
+        }
+
+        fitted.push_str(grapheme);
+        fitted_width += grapheme.width();
+    }
+
+    if width > 0 {
+        fitted.push('…');
+        fitted_width += 1;
+    }
+
+    fitted + &" ".repeat(width - fitted_width)
+}
+
+/// The way a priority is written in the code, e.g. `todo00` for -1
+pub fn priority_notation(priority: isize) -> String {
+    match priority.cmp(&0) {
+        Less => {
+            let mut str = "todo0".to_string();
+
+            // todo0 -> 0
+            // todo00 -> -1
+            // Therefore: 'todo0' + priority.abs() * '0'
+            str.push_str(String::from_utf8(vec![b'0'; priority.unsigned_abs()]).unwrap().as_str());
+
+            str
+        },
+        Equal => "todo0".to_string(),
+        Greater => format!("todo{}", priority),
+    }
+}
 
-    stdout.reset().unwrap();
+/// Entries with the same priority
+#[derive(Serialize)]
+pub struct PriorityGroup {
+    pub priority: isize,
+    /// e.g. `todo00`
+    pub notation: String,
+    pub entries: Vec<Entry>,
 }
 
-pub fn render_entries(entries: Vec<Entry>) {
+/// Entries with the same category
+#[derive(Serialize)]
+pub struct CategoryGroup {
+    pub category: String,
+    pub entries: Vec<Entry>,
+}
+
+/// Entries grouped the way they're rendered: priorities from the most important and
+/// categories alphabetically, with the entries in each group sorted by `sort`
+#[derive(Serialize)]
+pub struct Groups {
+    pub priorities: Vec<PriorityGroup>,
+    pub categories: Vec<CategoryGroup>,
+    pub other: Vec<Entry>,
+    /// `todo?` entries
+    pub someday: Vec<Entry>,
+    /// `todo!()` and `unimplemented!()` calls
+    pub unimplemented: Vec<Entry>,
+    /// `dbg!()` calls, and `unreachable!()`/`panic!()` with a TODO message
+    pub markers: Vec<Entry>,
+    /// Entries older than `stale.max_age_days`, also listed in their own groups
+    pub stale: Vec<Entry>,
+    /// Entries in the `[severity] informational` paths, only listed here
+    pub informational: Vec<Entry>,
+}
+
+/// Sort entries the way they are listed within a section
+pub fn sort_entries(entries: &mut [Entry], sort: SortOrder) {
+    match sort {
+        SortOrder::Location => entries.sort_by(|a, b| (&a.location.file, a.location.line).cmp(&(&b.location.file, b.location.line))),
+        SortOrder::Text => entries.sort_by(|a, b| a.text.cmp(&b.text)),
+    }
+}
+
+pub fn group_entries(mut entries: Vec<Entry>, sort: SortOrder) -> Groups {
+    sort_entries(&mut entries, sort);
+
+    let (informational, entries): (Vec<Entry>, Vec<Entry>) = entries.into_iter().partition(|entry| entry.informational);
+    let stale: Vec<Entry> = entries.iter().filter(|entry| entry.stale).cloned().collect();
+
     let mut priority_entries: HashMap<isize, Vec<Entry>> = HashMap::new();
     let mut category_entries: HashMap<String, Vec<Entry>> = HashMap::new();
     let mut generic_entries: Vec<Entry> = Vec::new();
-
-    let mut stdout = StandardStream::stdout(ColorChoice::Auto);
+    let mut someday_entries: Vec<Entry> = Vec::new();
+    let mut unimplemented_entries: Vec<Entry> = Vec::new();
+    let mut marker_entries: Vec<Entry> = Vec::new();
 
     for entry in entries {
         match entry.data {
             EntryData::Priority(priority) => {
-                if ! priority_entries.contains_key(&priority) {
-                    priority_entries.insert(priority, vec![]);
-                }
-
-                let vec = priority_entries.get_mut(&priority).unwrap();
-                vec.push(entry);
+                priority_entries.entry(priority).or_default().push(entry);
             },
             EntryData::Category(ref category) => {
-                if ! category_entries.contains_key(category) {
-                    category_entries.insert(category.clone(), vec![]);
-                }
-
-                let vec = category_entries.get_mut(category).unwrap();
-                vec.push(entry);
+                category_entries.entry(category.clone()).or_default().push(entry);
             },
             EntryData::Generic => {
                 generic_entries.push(entry);
+            },
+            EntryData::Someday => {
+                someday_entries.push(entry);
+            },
+            EntryData::Unimplemented => {
+                unimplemented_entries.push(entry);
+            },
+            EntryData::Marker => {
+                marker_entries.push(entry);
+            },
+        }
+    }
+
+    let mut priorities: Vec<PriorityGroup> = priority_entries.into_iter()
+        .map(|(priority, entries)| PriorityGroup { priority, notation: priority_notation(priority), entries })
+        .collect();
+    priorities.sort_by_key(|group| group.priority);
+
+    let mut categories: Vec<CategoryGroup> = category_entries.into_iter()
+        .map(|(category, entries)| CategoryGroup { category, entries })
+        .collect();
+    categories.sort_by(|a, b| a.category.cmp(&b.category));
+
+    Groups { priorities, categories, other: generic_entries, someday: someday_entries, unimplemented: unimplemented_entries, markers: marker_entries, stale, informational }
+}
+
+enum Section {
+    Priority(PriorityGroup),
+    Category(CategoryGroup),
+    Other(Vec<Entry>),
+    Stale(Vec<Entry>),
+    Someday(Vec<Entry>),
+    Unimplemented(Vec<Entry>),
+    Markers(Vec<Entry>),
+    /// Entries with categories missing from `[categories] allowed`
+    Unknown(Vec<Entry>),
+    Informational(Vec<Entry>),
+}
+
+const GROUPS: [&str; 9] = ["priorities", "unimplemented", "markers", "categories", "unknown", "other", "stale", "someday", "informational"];
+
+impl Section {
+    fn name(&self) -> &str {
+        match self {
+            Section::Priority(group) => &group.notation,
+            Section::Category(group) => &group.category,
+            Section::Other(_) => "other",
+            Section::Stale(_) => "stale",
+            Section::Someday(_) => "someday",
+            Section::Unimplemented(_) => "unimplemented",
+            Section::Markers(_) => "markers",
+            Section::Unknown(_) => "unknown",
+            Section::Informational(_) => "informational",
+        }
+    }
+
+    fn heading(&self) -> String {
+        match self {
+            Section::Priority(group) => group.notation.clone(),
+            Section::Category(group) => group.category.clone(),
+            Section::Other(_) => "Other".to_string(),
+            Section::Stale(_) => "Stale".to_string(),
+            Section::Someday(_) => "Someday".to_string(),
+            Section::Unimplemented(_) => "Unimplemented".to_string(),
+            Section::Markers(_) => "Code markers".to_string(),
+            Section::Unknown(_) => "Unknown categories".to_string(),
+            Section::Informational(_) => "Informational".to_string(),
+        }
+    }
+
+    /// The heading with the configured label and owner, e.g. `todo0: Now` or `auth (@backend-team)`
+    fn labeled_heading(&self, config: &Config) -> String {
+        let heading = match config.labels.get(self.name()) {
+            Some(label) => format!("{}: {}", self.heading(), label),
+            None => self.heading(),
+        };
+
+        match self.owner(config) {
+            Some(owner) => format!("{} ({})", heading, owner),
+            None => heading,
+        }
+    }
+
+    fn owner<'a>(&self, config: &'a Config) -> Option<&'a str> {
+        match self {
+            Section::Category(group) => config.categories.owners.get(&group.category).map(String::as_str),
+            _ => None,
+        }
+    }
+
+    fn description<'a>(&self, config: &'a Config) -> Option<&'a str> {
+        match self {
+            Section::Category(group) => config.descriptions.get(&group.category).map(String::as_str),
+            _ => None,
+        }
+    }
+
+    fn entries(&self) -> &[Entry] {
+        match self {
+            Section::Priority(group) => &group.entries,
+            Section::Category(group) => &group.entries,
+            Section::Other(entries) | Section::Stale(entries) | Section::Someday(entries) | Section::Unimplemented(entries) | Section::Markers(entries) | Section::Unknown(entries) | Section::Informational(entries) => entries,
+        }
+    }
+
+    fn group(&self) -> &str {
+        match self {
+            Section::Priority(_) => "priorities",
+            Section::Category(_) => "categories",
+            Section::Other(_) => "other",
+            Section::Stale(_) => "stale",
+            Section::Someday(_) => "someday",
+            Section::Unimplemented(_) => "unimplemented",
+            Section::Markers(_) => "markers",
+            Section::Unknown(_) => "unknown",
+            Section::Informational(_) => "informational",
+        }
+    }
+}
+
+/// The sections to render, in the configured order and without the hidden ones.
+/// Groups missing from the order are rendered after the listed ones, in the default order.
+fn sections(groups: Groups, config: &Config) -> Vec<Section> {
+    let (categories, unknown): (Vec<CategoryGroup>, Vec<CategoryGroup>) = groups.categories.into_iter()
+        .partition(|group| ! config.categories.unknown_section || config.categories.is_allowed(&group.category));
+    let unknown: Vec<Entry> = unknown.into_iter().flat_map(|group| group.entries).collect();
+
+    let config = &config.sections;
+    let mut order: Vec<&str> = config.order.iter().map(String::as_str).collect();
+
+    for group in GROUPS {
+        if ! order.contains(&group) {
+            order.push(group);
+        }
+    }
+
+    let mut remaining: Vec<Section> = groups.priorities.into_iter().map(Section::Priority)
+        .chain(categories.into_iter().map(Section::Category))
+        .chain([Section::Unimplemented(groups.unimplemented), Section::Markers(groups.markers), Section::Unknown(unknown), Section::Other(groups.other), Section::Stale(groups.stale), Section::Someday(groups.someday), Section::Informational(groups.informational)])
+        .collect();
+
+    let pinned: Vec<&str> = order.iter().copied().filter(|item| ! GROUPS.contains(item)).collect();
+    let mut sections: Vec<Section> = vec![];
+
+    for item in &order {
+        let belongs = |section: &Section| if GROUPS.contains(item) {
+            // Sections listed individually are rendered in their own position
+            section.group() == *item && ! pinned.contains(&section.name())
+        } else {
+            matches!(section, Section::Priority(_) | Section::Category(_)) && section.name() == *item
+        };
+
+        let (taken, rest): (Vec<Section>, Vec<Section>) = remaining.into_iter().partition(belongs);
+        sections.extend(taken);
+        remaining = rest;
+    }
+
+    sections.retain(|section| ! config.hidden.iter().any(|hidden| hidden == section.name() || hidden == section.group()));
+
+    if ! config.always_show {
+        sections.retain(|section| ! matches!(section, Section::Other(entries) if entries.is_empty()));
+    }
+
+    sections.retain(|section| ! matches!(section, Section::Someday(entries) | Section::Stale(entries) | Section::Unimplemented(entries) | Section::Markers(entries) | Section::Unknown(entries) | Section::Informational(entries) if entries.is_empty()));
+
+    sections
+}
+
+/// Renders the entries of a scan. The `--format`s are implemented as renderers, and
+/// tools using the library can implement their own.
+pub trait Renderer {
+    /// Write `entries` to `out`. Colors are only shown if `out` supports them.
+    fn render(&self, entries: Vec<Entry>, config: &Config, out: &mut dyn WriteColor) -> io::Result<()>;
+}
+
+/// Render `entries` to stdout, with colors if it's a terminal
+pub fn print_rendered(renderer: &dyn Renderer, entries: Vec<Entry>, config: &Config) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    renderer.render(entries, config, &mut stdout).unwrap();
+    stdout.flush().unwrap();
+}
+
+/// Render `entries` to the file at `path` without colors, e.g. to commit the markdown report
+pub fn write_rendered(renderer: &dyn Renderer, entries: Vec<Entry>, config: &Config, path: &Path) -> io::Result<()> {
+    let mut out = termcolor::NoColor::new(io::BufWriter::new(std::fs::File::create(path)?));
+
+    renderer.render(entries, config, &mut out)?;
+    out.into_inner().flush()
+}
+
+/// The default output: colored markdown sections under a `title` heading
+pub struct Terminal {
+    pub title: String,
+}
+
+impl Renderer for Terminal {
+    fn render(&self, entries: Vec<Entry>, config: &Config, mut out: &mut dyn WriteColor) -> io::Result<()> {
+        let sections = sections(group_entries(entries, config.sections.sort), config);
+        let count = sections.len();
+
+        if count == 0 && ! config.sections.always_show {
+            return Ok(());
+        }
+
+        write_ansi(&mut out, Color::Yellow, format!("# {}", self.title).as_str(), true);
+        write!(out, "\n\n")?;
+
+        for (i, section) in sections.into_iter().enumerate() {
+            let color = config.color(section.name());
+            let heading = section.labeled_heading(config);
+
+            // These sections mix entries of different kinds or categories
+            let show_kind = matches!(section, Section::Stale(_) | Section::Unknown(_) | Section::Informational(_));
+
+            let default_color = match section {
+                Section::Priority(_) => Color::Red,
+                Section::Category(_) => Color::Green,
+                Section::Other(_) => Color::White,
+                Section::Stale(_) | Section::Unknown(_) => Color::Yellow,
+                Section::Someday(_) | Section::Informational(_) => Color::Ansi256(243),
+                Section::Unimplemented(_) | Section::Markers(_) => Color::Magenta,
+            };
+
+            write_ansi(&mut out, color.unwrap_or(default_color), format!("## {}", heading).as_str(), true);
+            writeln!(out)?;
+
+            if let Some(description) = section.description(config) {
+                write_ansi(&mut out, Color::Ansi256(243), description, false);
+                writeln!(out)?;
+            }
+
+            for (index, item) in section.entries().iter().enumerate() {
+                item.render(&mut out, config, show_kind, index);
+            }
+
+            if i + 1 < count {
+                writeln!(out)?;
             }
         }
+
+        Ok(())
     }
+}
 
-    write_ansi(&mut stdout, Color::Yellow, "# TODOs", true);
-    write!(stdout, "\n\n").unwrap();
+/// `--format markdown-stable`, see [`markdown_stable`]
+pub struct MarkdownStable {
+    pub root: PathBuf,
+    pub line_numbers: bool,
+}
 
-    let mut priority_keys = priority_entries.keys().collect::<Vec<&isize>>();
-    priority_keys.sort_by(|a, b| a.partial_cmp(b).unwrap());
+impl Renderer for MarkdownStable {
+    fn render(&self, entries: Vec<Entry>, config: &Config, out: &mut dyn WriteColor) -> io::Result<()> {
+        write!(out, "{}", markdown_stable(entries, &self.root, config, self.line_numbers))
+    }
+}
 
-    for priority in priority_keys {
-        let priority_notation = match priority.cmp(&0) {
-            Less => {
-                let mut str = "todo0".to_string();
+/// `--format json`, see [`JsonReport`]
+pub struct Json;
 
-                // todo0 -> 0
-                // todo00 -> -1
-                // Therefore: 'todo0' + priority.abs() * '0'
-                str.push_str(String::from_utf8(vec![b'0'; priority.abs() as usize]).unwrap().as_str());
+impl Renderer for Json {
+    fn render(&self, entries: Vec<Entry>, config: &Config, out: &mut dyn WriteColor) -> io::Result<()> {
+        let entries = entries.into_iter()
+            .map(|entry| Entry { url: entry.location_link(&config.locations).1, ..entry })
+            .collect();
 
-                str
-            },
-            Equal => "todo0".to_string(),
-            Greater => format!("todo{}", priority),
+        writeln!(out, "{}", versioned_json(JsonReport { entries, descriptions: config.descriptions.clone(), owners: config.categories.owners.clone() }))
+    }
+}
+
+/// `--format snapshot`, see [`snapshot`]
+pub struct Snapshot {
+    pub root: PathBuf,
+}
+
+impl Renderer for Snapshot {
+    fn render(&self, entries: Vec<Entry>, _config: &Config, out: &mut dyn WriteColor) -> io::Result<()> {
+        write!(out, "{}", snapshot(&entries, &self.root))
+    }
+}
+
+/// `--format html`: a standalone page with the same sections as the default output, e.g. for
+/// publishing the report from CI. Locations are linked if `locations.url` is configured.
+pub struct Html {
+    pub title: String,
+}
+
+impl Renderer for Html {
+    fn render(&self, entries: Vec<Entry>, config: &Config, out: &mut dyn WriteColor) -> io::Result<()> {
+        let title = escape_html(&self.title);
+
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>", title)?;
+        writeln!(out, "<h1>{}</h1>", title)?;
+
+        let list = match config.sections.bullets {
+            BulletStyle::Numbered => "ol",
+            BulletStyle::Checkbox | BulletStyle::Dash => "ul",
         };
 
-        write_ansi(&mut stdout, Color::Red, format!("## {}", &priority_notation).as_str(), true);
-        write!(stdout, "\n").unwrap();
+        for section in sections(group_entries(entries, config.sections.sort), config) {
+            writeln!(out, "<h2>{}</h2>", escape_html(&section.labeled_heading(config)))?;
+
+            if let Some(description) = section.description(config) {
+                writeln!(out, "<p>{}</p>", escape_html(description))?;
+            }
+
+            writeln!(out, "<{}>", list)?;
+
+            for entry in section.entries() {
+                let location = also_at(entry.location_links(&config.locations).into_iter()
+                    .map(|link| match link {
+                        (location, Some(url)) => format!("<a href=\"{}\">{}</a>", escape_html(&url), escape_html(&location)),
+                        (location, None) => format!("<code>{}</code>", escape_html(&location)),
+                    })
+                    .collect());
+
+                match entry.text.is_empty() {
+                    true => writeln!(out, "<li>{}</li>", location)?,
+                    false => writeln!(out, "<li>{} ({})</li>", escape_html(&entry.text), location)?,
+                }
+            }
+
+            writeln!(out, "</{}>", list)?;
+        }
+
+        writeln!(out, "</body>\n</html>")
+    }
+}
+
+/// The SARIF rules, one per kind of entry
+const SARIF_RULES: [(&str, &str); 6] = [
+    ("priority", "Priority TODO"),
+    ("category", "Categorized TODO"),
+    ("generic", "TODO"),
+    ("someday", "Someday TODO"),
+    ("unimplemented", "Unimplemented code"),
+    ("marker", "Debugging code marker"),
+];
+
+/// `--format sarif`: a SARIF 2.1.0 log for GitHub code scanning, with a result per location.
+/// todo0 and more urgent priorities are errors, todo1, `todo!()`, and code markers are
+/// warnings, and the rest are notes. Paths are relative to `root`.
+pub struct Sarif {
+    pub root: PathBuf,
+}
+
+impl Sarif {
+    fn level(data: &EntryData) -> &'static str {
+        match data {
+            EntryData::Priority(priority) if *priority <= 0 => "error",
+            EntryData::Priority(1) | EntryData::Unimplemented | EntryData::Marker => "warning",
+            _ => "note",
+        }
+    }
+}
+
+impl Renderer for Sarif {
+    fn render(&self, entries: Vec<Entry>, _config: &Config, out: &mut dyn WriteColor) -> io::Result<()> {
+        let rules: Vec<serde_json::Value> = SARIF_RULES.iter()
+            .map(|(id, description)| serde_json::json!({ "id": id, "shortDescription": { "text": description } }))
+            .collect();
+
+        let results: Vec<serde_json::Value> = entries.iter()
+            .flat_map(|entry| std::iter::once(&entry.location).chain(&entry.also).map(move |location| (entry, location)))
+            .map(|(entry, location)| {
+                let message = match entry.text.is_empty() {
+                    true => entry.badge(),
+                    false => format!("{}: {}", entry.badge(), entry.text),
+                };
+
+                serde_json::json!({
+                    "ruleId": entry.data.key().split(':').next().unwrap(),
+                    "level": Sarif::level(&entry.data),
+                    "message": { "text": message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": location.relative_path(&self.root), "uriBaseId": "%SRCROOT%" },
+                            "region": { "startLine": location.line },
+                        },
+                    }],
+                })
+            })
+            .collect();
+
+        let log = serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "todos",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        });
+
+        writeln!(out, "{}", serde_json::to_string_pretty(&log).unwrap())
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The `--format markdown-stable` output, meant to be committed: the same sections as the
+/// default output, but without colors, with paths relative to `root`, and sorted by file, line,
+/// and text. Without `line_numbers`, entries are sorted by file and text instead, so moving
+/// code around doesn't change the output.
+pub fn markdown_stable(entries: Vec<Entry>, root: &Path, config: &Config, line_numbers: bool) -> String {
+    let sections = sections(group_entries(entries, SortOrder::Location), config);
+
+    if sections.is_empty() && ! config.sections.always_show {
+        return String::new();
+    }
+
+    let mut output = String::from("# TODOs\n");
+
+    for section in sections {
+        output.push_str(&format!("\n## {}\n", section.labeled_heading(config)));
+
+        if let Some(description) = section.description(config) {
+            output.push_str(&format!("\n{}\n\n", description));
+        }
+
+        let mut entries: Vec<&Entry> = section.entries().iter().collect();
+
+        match line_numbers {
+            true => entries.sort_by_key(|entry| (entry.relative_path(root), entry.location.line, entry.text.clone())),
+            false => entries.sort_by_key(|entry| (entry.relative_path(root), entry.text.clone())),
+        }
+
+        for (index, entry) in entries.into_iter().enumerate() {
+            let bullet = config.sections.bullets.bullet(index);
+            let location = also_at(std::iter::once(&entry.location).chain(&entry.also)
+                .map(|location| match line_numbers {
+                    true => format!("{}:{}", location.relative_path(root), location.line),
+                    false => location.relative_path(root),
+                })
+                .collect());
+
+            // The section doesn't tell the categories apart
+            let badge = match section {
+                Section::Unknown(_) => format!("{} ", entry.badge()),
+                _ => String::new(),
+            };
+
+            match entry.text.is_empty() {
+                true => output.push_str(&format!("{}{}{}\n", bullet, badge, location)),
+                false => output.push_str(&format!("{}{}{} ({})\n", bullet, badge, entry.text, location)),
+            }
+        }
+    }
 
-        for item in priority_entries.get(priority).unwrap() {
-            item.render();
+    output
+}
+
+/// Entries grouped by assignee, sorted by name, with the unassigned entries last
+pub fn group_by_assignee(mut entries: Vec<Entry>, sort: SortOrder) -> Vec<(Option<String>, Vec<Entry>)> {
+    sort_entries(&mut entries, sort);
+
+    let mut groups: Vec<(Option<String>, Vec<Entry>)> = vec![];
+
+    for entry in entries {
+        match groups.iter_mut().find(|(assignee, _)| *assignee == entry.assignee) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((entry.assignee.clone(), vec![entry])),
         }
+    }
+
+    // None sorts first, but the unassigned entries should come after everyone's
+    groups.sort_by(|a, b| a.0.is_none().cmp(&b.0.is_none()).then_with(|| a.0.cmp(&b.0)));
+
+    groups
+}
+
+/// Entries grouped by their uppercase keyword (e.g. `FIXME`), sorted by it, with the `todo`
+/// entries (`None`) first
+pub fn group_by_keyword(mut entries: Vec<Entry>, sort: SortOrder) -> Vec<(Option<String>, Vec<Entry>)> {
+    sort_entries(&mut entries, sort);
+
+    let mut groups: BTreeMap<Option<String>, Vec<Entry>> = BTreeMap::new();
+
+    for entry in entries {
+        groups.entry(entry.keyword.as_deref().map(str::to_uppercase)).or_default().push(entry);
+    }
+
+    groups.into_iter().collect()
+}
+
+/// Entries grouped by their file, sorted by path, with each file's entries sorted by line
+pub fn group_by_file(mut entries: Vec<Entry>) -> Vec<(Option<String>, Vec<Entry>)> {
+    sort_entries(&mut entries, SortOrder::Location);
+
+    let mut groups: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+
+    for entry in entries {
+        groups.entry(entry.location.file.to_string_lossy().to_string()).or_default().push(entry);
+    }
+
+    groups.into_iter().map(|(file, entries)| (Some(file), entries)).collect()
+}
+
+/// A checklist per group, with the entry counts in the headings, e.g. for standup notes
+pub fn render_checklists(groups: Vec<(String, Vec<Entry>)>, title: &str, config: &Config) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+    let count = groups.len();
+
+    if count == 0 && ! config.sections.always_show {
+        return;
+    }
+
+    write_ansi(&mut stdout, Color::Yellow, format!("# {}", title).as_str(), true);
+    write!(stdout, "\n\n").unwrap();
+
+    for (i, (heading, entries)) in groups.into_iter().enumerate() {
+        write_ansi(&mut stdout, Color::Green, format!("## {} ({})", heading, entries.len()).as_str(), true);
+        writeln!(stdout).unwrap();
+
+        for (index, item) in entries.iter().enumerate() {
+            item.render(&mut stdout, config, true, index);
+        }
+
+        if i + 1 < count {
+            writeln!(stdout).unwrap();
+        }
+    }
+
+    stdout.flush().unwrap();
+}
+
+/// Files containing entries, sorted, with their entry counts
+pub fn files_with_matches(entries: &[Entry]) -> Vec<(&Path, usize)> {
+    let mut counts: HashMap<&Path, usize> = HashMap::new();
+
+    for entry in entries {
+        *counts.entry(&entry.location.file).or_default() += 1;
+    }
+
+    let mut files: Vec<(&Path, usize)> = counts.into_iter().collect();
+    files.sort();
+
+    files
+}
 
-        println!("");
+/// Print the files containing entries, one per line, like `grep -l` (or `grep -c` with `count`)
+pub fn render_files(entries: &[Entry], count: bool) {
+    let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+
+    for (file, entry_count) in files_with_matches(entries) {
+        if count {
+            writeln!(stdout, "{}:{}", file.to_string_lossy(), entry_count).unwrap();
+        } else {
+            writeln!(stdout, "{}", file.to_string_lossy()).unwrap();
+        }
     }
 
-    let mut category_keys = category_entries.keys().collect::<Vec<&String>>();
-    category_keys.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    stdout.flush().unwrap();
+}
 
-    for category in category_keys {
-        write_ansi(&mut stdout, Color::Green, format!("## {}", &category).as_str(), true);
-        write!(stdout, "\n").unwrap();
+/// Categories with their entry counts, the most used first
+pub fn category_counts(entries: &[Entry]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
 
-        for item in category_entries.get(category).unwrap() {
-            item.render();
+    for entry in entries {
+        if let EntryData::Category(category) = &entry.data {
+            *counts.entry(category).or_default() += 1;
         }
+    }
+
+    let mut counts: Vec<(String, usize)> = counts.into_iter().map(|(category, count)| (category.to_string(), count)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    counts
+}
+
+pub fn render_categories(entries: &[Entry]) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, "# Categories", true);
+    write!(stdout, "\n\n").unwrap();
+
+    for (category, count) in category_counts(entries) {
+        write_ansi(&mut stdout, Color::Ansi256(243), "- ", false);
+        write_ansi(&mut stdout, Color::Green, &category, true);
+        writeln!(stdout, " ({})", count).unwrap();
+    }
+
+    stdout.flush().unwrap();
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct CategoryCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// The shape of `categories --format json` output
+#[derive(Serialize, JsonSchema)]
+pub struct CategoriesReport {
+    pub categories: Vec<CategoryCount>,
+}
+
+pub fn render_categories_json(entries: &[Entry]) {
+    let categories: Vec<CategoryCount> = category_counts(entries).into_iter()
+        .map(|(name, count)| CategoryCount { name, count })
+        .collect();
+
+    print_json(CategoriesReport { categories });
+}
+
+/// The shape of `--format json` output
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct JsonReport {
+    pub entries: Vec<Entry>,
+    /// Category descriptions from todo.md and todo.toml
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub descriptions: BTreeMap<String, String>,
+    /// Category owners from todo.toml
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub owners: BTreeMap<String, String>,
+}
+
+/// The `--format snapshot` output: one `file:line kind text` line per entry, without
+/// colors or headings. Paths are relative to `root` and use `/` on all platforms, and
+/// lines are sorted by path, line, kind, and text, so the output only depends on the
+/// scanned content and can be committed and compared in tests.
+pub fn snapshot(entries: &[Entry], root: &Path) -> String {
+    let mut lines: Vec<(String, usize, String, &str)> = entries.iter()
+        .map(|entry| (entry.relative_path(root), entry.location.line, entry.data.key(), entry.text.as_str()))
+        .collect();
+
+    lines.sort();
+
+    lines.into_iter()
+        .map(|(file, line, key, text)| if text.is_empty() {
+            format!("{}:{} {}\n", file, line, key)
+        } else {
+            format!("{}:{} {} {}\n", file, line, key, text)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{TempDir, entry};
+    use std::path::PathBuf;
+    use crate::entries::Location;
+    use crate::config::Sections;
+    use crate::template::Template;
+
+    #[test]
+    fn group_by_assignee_test() {
+        let assigned = |file: &str, assignee: Option<&str>| Entry {
+            assignee: assignee.map(String::from),
+            ..entry(file, 1, EntryData::Generic, "foo")
+        };
+
+        let groups = group_by_assignee(vec![
+            assigned("c.rs", None),
+            assigned("b.rs", Some("bob")),
+            assigned("a.rs", None),
+            assigned("a.rs", Some("bob")),
+            assigned("d.rs", Some("alice")),
+        ], SortOrder::Location);
+
+        let groups: Vec<(Option<&str>, Vec<&str>)> = groups.iter()
+            .map(|(assignee, entries)| (assignee.as_deref(), entries.iter().map(|entry| entry.location.file.to_str().unwrap()).collect()))
+            .collect();
+
+        assert_eq!(vec![
+            (Some("alice"), vec!["d.rs"]),
+            (Some("bob"), vec!["a.rs", "b.rs"]),
+            (None, vec!["a.rs", "c.rs"]),
+        ], groups);
+    }
+
+    #[test]
+    fn group_by_file_test() {
+        let groups = group_by_file(vec![
+            entry("src/b.rs", 3, EntryData::Generic, "foo"),
+            entry("src/a.rs", 10, EntryData::Priority(0), "bar"),
+            entry("src/b.rs", 1, EntryData::Generic, "baz"),
+            entry("src/a.rs", 2, EntryData::Generic, "qux"),
+        ]);
+
+        assert_eq!(vec![
+            (Some("src/a.rs".to_string()), vec![2, 10]),
+            (Some("src/b.rs".to_string()), vec![1, 3]),
+        ], groups.into_iter().map(|(file, entries)| (file, entries.iter().map(|entry| entry.location.line).collect::<Vec<_>>())).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn fit_test() {
+        assert_eq!("abc  ", fit("abc", 5));
+        assert_eq!("abcd…", fit("abcdefgh", 5));
+        assert_eq!("日本 ", fit("日本", 5));
+        assert_eq!("日本…", fit("日本語です", 5));
+        assert_eq!("日… ", fit("日本語", 4));
+        assert_eq!("👍🏽…", fit("👍🏽👍🏽", 3));
+        assert_eq!("", fit("abc", 0));
+    }
+
+    #[test]
+    fn rendered_location_test() {
+        let entry = entry("/home/me/work/app/src/main.rs", 12, EntryData::Generic, "");
 
-        println!("");
+        let mut config = Locations::default();
+        assert_eq!("/home/me/work/app/src/main.rs:12", entry.rendered_location(&config));
+
+        config.prefixes.insert("/home/me/".to_string(), "~/".to_string());
+        config.prefixes.insert("/home/me/work/app/".to_string(), "".to_string());
+        assert_eq!("src/main.rs:12", entry.rendered_location(&config));
+
+        config.url = Some(Template::parse("https://github.com/acme/app/blob/main/{file}#L{line}").unwrap());
+        assert_eq!("[src/main.rs:12](https://github.com/acme/app/blob/main/src/main.rs#L12)", entry.rendered_location(&config));
+    }
+
+    #[test]
+    fn files_with_matches_test() {
+        let entries = vec![
+            entry("b.rs", 1, EntryData::Generic, ""),
+            entry("a.rs", 1, EntryData::Generic, ""),
+            entry("b.rs", 2, EntryData::Priority(0), ""),
+        ];
+
+        assert_eq!(vec![(Path::new("a.rs"), 1), (Path::new("b.rs"), 2)], files_with_matches(&entries));
+    }
+
+    #[test]
+    fn category_counts_test() {
+        let entries = vec![
+            entry("a.rs", 1, EntryData::Category("foo".to_string()), ""),
+            entry("a.rs", 2, EntryData::Category("bar".to_string()), ""),
+            entry("a.rs", 3, EntryData::Category("baz".to_string()), ""),
+            entry("b.rs", 1, EntryData::Category("baz".to_string()), ""),
+            entry("b.rs", 2, EntryData::Generic, ""),
+        ];
+
+        assert_eq!(vec![
+            ("baz".to_string(), 2),
+            ("bar".to_string(), 1),
+            ("foo".to_string(), 1),
+        ], category_counts(&entries));
+    }
+
+    #[test]
+    fn snapshot_test() {
+        let entries = vec![
+            entry("/repo/src/b.rs", 1, EntryData::Generic, ""),
+            entry("/repo/src/a.rs", 10, EntryData::Category("foo".to_string()), "bar"),
+            entry("/repo/src/a.rs", 2, EntryData::Priority(-1), "baz"),
+            entry("/repo/src/a.rs", 2, EntryData::Generic, "abc"),
+        ];
+
+        assert_eq!(concat!(
+            "src/a.rs:2 generic abc\n",
+            "src/a.rs:2 priority:-1 baz\n",
+            "src/a.rs:10 category:foo bar\n",
+            "src/b.rs:1 generic\n",
+        ), snapshot(&entries, Path::new("/repo")));
     }
 
-    write_ansi(&mut stdout, Color::White, "## Other", true);
-    write!(stdout, "\n").unwrap();
+    #[test]
+    fn markdown_stable_test() {
+        let entries = vec![
+            entry("/repo/src/b.rs", 3, EntryData::Generic, "zzz"),
+            entry("/repo/src/b.rs", 9, EntryData::Generic, "aaa"),
+            entry("/repo/src/a.rs", 5, EntryData::Priority(0), "foo"),
+            entry("/repo/src/a.rs", 1, EntryData::Generic, ""),
+        ];
+
+        assert_eq!(
+            "# TODOs\n\n## todo0\n- [ ] foo (src/a.rs:5)\n\n## Other\n- [ ] src/a.rs:1\n- [ ] zzz (src/b.rs:3)\n- [ ] aaa (src/b.rs:9)\n",
+            markdown_stable(entries.clone(), Path::new("/repo"), &Config::default(), true),
+        );
+
+        assert_eq!(
+            "# TODOs\n\n## todo0\n- [ ] foo (src/a.rs)\n\n## Other\n- [ ] src/a.rs\n- [ ] aaa (src/b.rs)\n- [ ] zzz (src/b.rs)\n",
+            markdown_stable(entries, Path::new("/repo"), &Config::default(), false),
+        );
+
+        assert_eq!("", markdown_stable(vec![], Path::new("/repo"), &Config::default(), true));
+
+        let mut config = Config::default();
+        config.descriptions.insert("backend".to_string(), "Server-side work".to_string());
+
+        assert_eq!(
+            "# TODOs\n\n## backend\n\nServer-side work\n\n- [ ] foo (src/a.rs:5)\n",
+            markdown_stable(vec![entry("/repo/src/a.rs", 5, EntryData::Category("backend".to_string()), "foo")], Path::new("/repo"), &config, true),
+        );
+
+        config.categories.owners.insert("backend".to_string(), "@api-team".to_string());
+
+        assert_eq!(
+            "# TODOs\n\n## backend (@api-team)\n\nServer-side work\n\n- [ ] foo (src/a.rs:5)\n",
+            markdown_stable(vec![entry("/repo/src/a.rs", 5, EntryData::Category("backend".to_string()), "foo")], Path::new("/repo"), &config, true),
+        );
+
+        config.sections.bullets = BulletStyle::Numbered;
+
+        assert_eq!(
+            "# TODOs\n\n## Other\n1. src/a.rs:1\n2. zzz (src/b.rs:3)\n",
+            markdown_stable(vec![entry("/repo/src/b.rs", 3, EntryData::Generic, "zzz"), entry("/repo/src/a.rs", 1, EntryData::Generic, "")], Path::new("/repo"), &config, true),
+        );
+
+        let json = rendered(&Json, vec![], &config);
+        assert!(json.contains("\"owners\": {\n    \"backend\": \"@api-team\"\n  }"));
+    }
 
-    generic_entries.sort_by(|a, b| a.text.partial_cmp(&b.text).unwrap());
+    fn rendered(renderer: &dyn Renderer, entries: Vec<Entry>, config: &Config) -> String {
+        let mut out = termcolor::NoColor::new(vec![]);
+        renderer.render(entries, config, &mut out).unwrap();
 
-    for item in generic_entries {
-        item.render();
+        String::from_utf8(out.into_inner()).unwrap()
     }
 
+    #[test]
+    fn renderer_test() {
+        let entries = vec![
+            entry("src/a.rs", 5, EntryData::Priority(0), "foo <bar>"),
+            entry("src/b.rs", 1, EntryData::Category("ui".to_string()), ""),
+        ];
+
+        let mut config = Config::default();
+        config.descriptions.insert("ui".to_string(), "Frontend & design".to_string());
+
+        assert_eq!(
+            "# TODOs\n\n## todo0\n- [ ] foo <bar> (src/a.rs:5)\n\n## ui\nFrontend & design\n- [ ] src/b.rs:1\n",
+            rendered(&Terminal { title: "TODOs".to_string() }, entries.clone(), &config),
+        );
+
+        let dir = TempDir::new("report");
+        let file = dir.join("report.md");
+        write_rendered(&Terminal { title: "TODOs".to_string() }, entries.clone(), &config, &file).unwrap();
+        assert_eq!(rendered(&Terminal { title: "TODOs".to_string() }, entries.clone(), &config), std::fs::read_to_string(&file).unwrap());
+
+        let html = rendered(&Html { title: "TODOs".to_string() }, entries.clone(), &config);
+        assert!(html.contains("<h2>todo0</h2>\n<ul>\n<li>foo &lt;bar&gt; (<code>src/a.rs:5</code>)</li>\n</ul>"));
+        assert!(html.contains("<h2>ui</h2>\n<p>Frontend &amp; design</p>"));
+
+        config.locations.url = Some(Template::parse("https://example.com/{file}#L{line}").unwrap());
+        let html = rendered(&Html { title: "TODOs".to_string() }, entries, &config);
+        assert!(html.contains("<a href=\"https://example.com/src/a.rs#L5\">src/a.rs:5</a>"));
+    }
+
+    #[test]
+    fn sarif_test() {
+        let mut entries = vec![
+            entry("/repo/src/a.rs", 5, EntryData::Priority(0), "foo"),
+            entry("/repo/src/b.rs", 1, EntryData::Category("ui".to_string()), ""),
+        ];
+        entries[1].also.push(Location { file: PathBuf::from("/repo/src/c.rs").into(), line: 2 });
+
+        let sarif: serde_json::Value = serde_json::from_str(&rendered(&Sarif { root: PathBuf::from("/repo") }, entries, &Config::default())).unwrap();
+        let results = &sarif["runs"][0]["results"];
+
+        assert_eq!("2.1.0", sarif["version"]);
+        assert_eq!(3, results.as_array().unwrap().len());
+        assert_eq!(serde_json::json!({
+            "ruleId": "priority",
+            "level": "error",
+            "message": { "text": "todo0: foo" },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": "src/a.rs", "uriBaseId": "%SRCROOT%" },
+                    "region": { "startLine": 5 },
+                },
+            }],
+        }), results[0]);
+        assert_eq!(("note", "@ui"), (results[1]["level"].as_str().unwrap(), results[1]["message"]["text"].as_str().unwrap()));
+        assert_eq!("src/c.rs", results[2]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]);
+    }
+
+    #[test]
+    fn sections_test() {
+        let entries = || vec![
+            entry("a.rs", 1, EntryData::Priority(0), ""),
+            entry("a.rs", 2, EntryData::Priority(1), ""),
+            entry("a.rs", 3, EntryData::Category("foo".to_string()), ""),
+            entry("a.rs", 4, EntryData::Category("security".to_string()), ""),
+            entry("a.rs", 5, EntryData::Generic, ""),
+        ];
+
+        let names = |config: &Config| sections(group_entries(entries(), SortOrder::Location), config)
+            .iter()
+            .map(|section| section.name().to_string())
+            .collect::<Vec<String>>();
+
+        let with_sections = |sections: Sections| Config { sections, ..Config::default() };
+
+        assert_eq!(vec!["todo0", "todo1", "foo", "security", "other"], names(&Config::default()));
+
+        assert_eq!(vec!["security", "other", "foo", "todo0"], names(&with_sections(Sections {
+            order: vec!["security".to_string(), "other".to_string(), "categories".to_string()],
+            hidden: vec!["todo1".to_string()],
+            ..Sections::default()
+        })));
+
+        assert_eq!(vec!["todo0", "todo1"], names(&with_sections(Sections {
+            order: vec![],
+            hidden: vec!["categories".to_string(), "other".to_string()],
+            ..Sections::default()
+        })));
+
+        let empty_other = |always_show: bool| sections(group_entries(vec![], SortOrder::Location), &with_sections(Sections { always_show, ..Sections::default() })).len();
+
+        assert_eq!(0, empty_other(false));
+        assert_eq!(1, empty_other(true));
+
+        let mut config = Config::default();
+        config.categories.allowed = vec!["foo".to_string()];
+        assert_eq!(vec!["todo0", "todo1", "foo", "security", "other"], names(&config));
+
+        config.categories.unknown_section = true;
+        assert_eq!(vec!["todo0", "todo1", "foo", "unknown", "other"], names(&config));
+    }
 }
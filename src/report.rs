@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tera::{Context, Tera};
+
+use crate::config::Config;
+use crate::entries::{Entry, EntryData};
+use crate::render::{Groups, group_entries};
+use crate::scan::Stats;
+
+#[derive(Serialize)]
+struct ReportStats {
+    /// Excluding `todo?` entries
+    entries: usize,
+    someday: usize,
+    files: usize,
+    folders: usize,
+}
+
+/// The context templates are rendered with
+#[derive(Serialize)]
+struct Report {
+    root: String,
+    entries: Vec<Entry>,
+    groups: Groups,
+    stats: ReportStats,
+    /// Category owners, see [`crate::config::Categories::owners`]
+    owners: BTreeMap<String, String>,
+}
+
+/// Render a Tera template with the scanned entries, both as a flat list (`entries`) and grouped
+/// the same way as the markdown output (`groups.priorities`, `groups.categories`, `groups.other`),
+/// along with `stats`, the category `owners`, and the project `root`. Paths in the entries are relative to `root`.
+///
+/// Templates with an `.html` or `.htm` extension are autoescaped.
+pub fn render_report(template: &Path, entries: Vec<Entry>, root: &Path, stats: &Stats, config: &Config) -> Result<String, Box<dyn Error>> {
+    let source = fs::read_to_string(template)?;
+
+    let entries: Vec<Entry> = entries.into_iter().map(|mut entry| {
+        entry.location.file = PathBuf::from(entry.relative_path(root)).into();
+        entry
+    }).collect();
+
+    let report = Report {
+        root: root.to_string_lossy().to_string(),
+        stats: ReportStats {
+            entries: entries.iter().filter(|entry| entry.data != EntryData::Someday).count(),
+            someday: entries.iter().filter(|entry| entry.data == EntryData::Someday).count(),
+            files: stats.visited_file_count(),
+            folders: stats.visited_folder_count(),
+        },
+        groups: group_entries(entries.clone(), config.sections.sort),
+        owners: config.categories.owners.clone(),
+        entries,
+    };
+
+    let autoescape = matches!(template.extension().and_then(|extension| extension.to_str()), Some("html" | "htm"));
+
+    Tera::one_off(&source, &Context::from_serialize(report)?, autoescape).map_err(|err| {
+        // Tera's own message is generic, the details are in the source chain
+        let mut message = err.to_string();
+        let mut source = err.source();
+
+        while let Some(err) = source {
+            message.push_str(&format!(": {}", err));
+            source = err.source();
+        }
+
+        message.into()
+    })
+}
@@ -1,49 +1,163 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::io;
-use std::fs::{self, canonicalize};
+use std::fs;
 use std::path::{Path, PathBuf};
-use glob::glob;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use glob::{MatchOptions, Pattern};
+use rayon::prelude::*;
+use tracing::{debug, info, trace};
 
 const PRIORITY_CHARS: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
 
-use crate::entries::{Entry, EntryData, Location};
+/// How many of the slowest files are listed in stats
+const SLOWEST_FILES_COUNT: usize = 10;
+
+/// Files that are skipped unless `[scan] skip` is configured: lockfiles, images, fonts,
+/// archives, media, and source maps. They never contain TODOs worth listing, and front-end
+/// repos tend to have a lot of them.
+pub const SKIPPED_FILES: [&str; 32] = [
+    "*.lock", "package-lock.json", "npm-shrinkwrap.json", "pnpm-lock.yaml",
+    "*.png", "*.jpg", "*.jpeg", "*.gif", "*.webp", "*.avif", "*.ico", "*.bmp", "*.svg",
+    "*.woff", "*.woff2", "*.ttf", "*.otf", "*.eot",
+    "*.zip", "*.gz", "*.tgz", "*.tar", "*.bz2", "*.xz", "*.7z", "*.rar",
+    "*.pdf", "*.mp3", "*.mp4", "*.webm", "*.wasm",
+    "*.map",
+];
+
+/// Conventional test and fixture locations, excluded by `--skip-tests`: directories, and the
+/// file names of test files next to the code they test (Go, JS/TS, Python, Ruby, Java, PHP)
+pub const TEST_PATHS: [&str; 22] = [
+    "tests", "test", "__tests__", "spec", "testdata", "fixtures", "__fixtures__", "__mocks__",
+    "*_test.go",
+    "*.test.js", "*.test.jsx", "*.test.ts", "*.test.tsx",
+    "*.spec.js", "*.spec.jsx", "*.spec.ts", "*.spec.tsx",
+    "*_test.py",
+    "*_spec.rb",
+    "*Test.java", "*Tests.java",
+    "*Test.php",
+];
+
+use crate::config::FrontMatter;
+use crate::gitignore::{IgnoreCache, Ignores};
+use crate::entries::{Entry, EntryData, Location, SourceKind};
+use crate::matcher::{Matcher, MARKERS, UNIMPLEMENTED};
+use crate::paths::canonicalize;
+use crate::render::priority_notation;
+use crate::scanners::Scanners;
 
 pub struct Stats {
     visited_folder_count: usize,
     visited_file_count: usize,
+    transcoded_file_count: usize,
+    /// Entry counts by priority, most urgent first
+    priority_counts: BTreeMap<isize, usize>,
+    category_count: usize,
+    generic_count: usize,
+    someday_count: usize,
+    unimplemented_count: usize,
+    marker_count: usize,
     visited_folders: Vec<String>,
     visited_files: Vec<String>,
+    file_timings: Vec<FileTiming>,
+    scan_time: Duration,
+    read_time: Duration,
+    parse_time: Duration,
+    render_time: Duration,
     verbosity: u8,
 }
 
+struct FileTiming {
+    file: String,
+    read: Duration,
+    parse: Duration,
+}
+
 impl Stats {
     pub fn new(verbosity: u8) -> Stats {
         Stats {
             visited_folder_count: 0,
             visited_file_count: 0,
+            transcoded_file_count: 0,
+            priority_counts: BTreeMap::new(),
+            category_count: 0,
+            generic_count: 0,
+            someday_count: 0,
+            unimplemented_count: 0,
+            marker_count: 0,
             visited_folders: vec![],
             visited_files: vec![],
+            file_timings: vec![],
+            scan_time: Duration::ZERO,
+            read_time: Duration::ZERO,
+            parse_time: Duration::ZERO,
+            render_time: Duration::ZERO,
             verbosity,
         }
     }
 
+    pub fn visited_file_count(&self) -> usize {
+        self.visited_file_count
+    }
+
+    pub fn visited_folder_count(&self) -> usize {
+        self.visited_folder_count
+    }
+
+    pub fn add_file_timing(&mut self, file: String, read: Duration, parse: Duration) {
+        self.read_time += read;
+        self.parse_time += parse;
+
+        if self.verbosity >= 2 {
+            self.file_timings.push(FileTiming { file, read, parse });
+        }
+    }
+
+    /// Total time spent in `scan_dir`, including reading and parsing files
+    pub fn add_scan_time(&mut self, time: Duration) {
+        self.scan_time += time;
+    }
+
+    pub fn add_render_time(&mut self, time: Duration) {
+        self.render_time += time;
+    }
+
     pub fn add_file(&mut self, file: String) {
         self.visited_file_count += 1;
 
-        if self.verbosity >= 3 {
-            eprintln!("[INFO] Visited file: {}", &file);
-        }
+        trace!(file = %file, "Visited file");
 
         if self.verbosity >= 2 {
             self.visited_files.push(file);
         }
     }
 
+    /// A file that was converted to UTF-8 before scanning
+    pub fn add_transcoded_file(&mut self, file: String) {
+        self.transcoded_file_count += 1;
+
+        debug!(file = %file, "Transcoded file");
+    }
+
+    /// Count the scanned entries by kind and priority
+    pub fn add_entries(&mut self, entries: &[Entry]) {
+        for entry in entries {
+            match entry.data {
+                EntryData::Priority(priority) => *self.priority_counts.entry(priority).or_default() += 1,
+                EntryData::Category(_) => self.category_count += 1,
+                EntryData::Generic => self.generic_count += 1,
+                EntryData::Someday => self.someday_count += 1,
+                EntryData::Unimplemented => self.unimplemented_count += 1,
+                EntryData::Marker => self.marker_count += 1,
+            }
+        }
+    }
+
     pub fn add_folder(&mut self, folder: String) {
         self.visited_folder_count += 1;
 
-        if self.verbosity >= 3 {
-            eprintln!("[INFO] Visited folder: {}", &folder);
-        }
+        trace!(folder = %folder, "Visited folder");
 
         if self.verbosity >= 2 {
             self.visited_folders.push(folder);
@@ -52,161 +166,281 @@ impl Stats {
 
     pub fn print(&self) {
         if self.verbosity >= 2 {
-            eprintln!("[INFO] Visited folders:");
-
             for folder in &self.visited_folders {
-                println!("{}", folder);
+                debug!(folder = %folder, "Visited folder");
             }
 
-            eprint!("\n\n");
+            for file in &self.visited_files {
+                debug!(file = %file, "Visited file");
+            }
 
-            eprintln!("[INFO] Visited files:");
+            let mut timings = self.file_timings.iter().collect::<Vec<&FileTiming>>();
+            timings.sort_by_key(|timing| std::cmp::Reverse(timing.read + timing.parse));
 
-            for file in &self.visited_files {
-                println!("{}", file);
+            for timing in timings.iter().take(SLOWEST_FILES_COUNT) {
+                debug!(file = %timing.file, total = ?(timing.read + timing.parse), read = ?timing.read, parse = ?timing.parse, "Slow file");
             }
 
-            eprint!("\n\n");
+            // Files are read and parsed in parallel, so these are summed across threads and
+            // can add up to more than the scan itself
+            debug!(
+                scan = ?self.scan_time,
+                read = ?self.read_time,
+                parse = ?self.parse_time,
+                render = ?self.render_time,
+                "Phase timings",
+            );
         }
 
-        eprintln!("[INFO] Visited folders: {}", self.visited_folder_count);
-        eprintln!("[INFO] Visited files: {}", self.visited_file_count);
+        info!(count = self.visited_folder_count, "Visited folders");
+        info!(count = self.visited_file_count, "Visited files");
+        info!(count = self.transcoded_file_count, "Transcoded files");
+
+        info!(
+            priority = self.priority_counts.values().sum::<usize>(),
+            category = self.category_count,
+            generic = self.generic_count,
+            someday = self.someday_count,
+            unimplemented = self.unimplemented_count,
+            markers = self.marker_count,
+            "Entries",
+        );
+
+        for (priority, count) in &self.priority_counts {
+            info!(priority = %priority_notation(*priority), count, "Priority entries");
+        }
     }
 }
 
-fn parse_priority(word: &str) -> Option<isize> {
-    let lowercase_word = word.to_lowercase();
-    let priority_substr = lowercase_word.split("todo").nth(1).unwrap();
+/// The byte offset of the first `todo` in `str`, in any ASCII case. The offset is always
+/// a char boundary, since ASCII bytes never occur inside multi-byte UTF-8 sequences.
+fn find_todo(str: &str) -> Option<usize> {
+    str.as_bytes().windows(4).position(|window| window.eq_ignore_ascii_case(b"todo"))
+}
+
+/// Whether `word` starts with `todo`, in any ASCII case
+fn starts_with_todo(word: &str) -> bool {
+    word.len() >= 4 && word.as_bytes()[..4].eq_ignore_ascii_case(b"todo")
+}
+
+/// The priority in the part of a marker after the keyword, e.g. `00` in `todo00`
+fn parse_priority(suffix: &str) -> Option<isize> {
+    let priority_substr = &suffix[..find_todo(suffix).unwrap_or(suffix.len())];
 
     if priority_substr.len() == 1 {
-        return Some(priority_substr.to_string().parse::<isize>().unwrap());
+        Some(priority_substr.to_string().parse::<isize>().unwrap())
     } else if priority_substr.chars().all(|ch| ch == '0') {
         // todo0: 1 - 1 = 0
         // todo00: 1 - 2 = -1
-        return Some(1 - priority_substr.len() as isize);
+        Some(1 - priority_substr.len() as isize)
     } else {
-        return None; // invalid syntax like todo11
+        None // invalid syntax like todo11
     }
 }
 
+/// Comment and tag closers removed from the end of entry text, e.g. the `*/` in `/* todo foo */`.
+/// More can be added using `scan.closers` in todo.toml.
+pub const CLOSERS: [&str; 9] = ["*/", "-->", "--}}", "/>", "#}", "%}", "?>", "*)", "\"\"\""];
+
 /// Remove closing tags, comments, and whitespace
 fn clean_line<'a>(line: &'a str, delimiter_word: &str) -> &'a str {
-    return line.split_once(delimiter_word).unwrap().1
-        .trim()
-        .trim_end_matches("*/")
-        .trim_end_matches("-->")
-        .trim_end_matches("--}}")
-        .trim_end_matches("/>")
-        .trim();
+    strip_closers(line.split_once(delimiter_word).unwrap().1, &CLOSERS)
 }
 
-pub fn add_excludes_from_gitignore(base_dir: &PathBuf, excludes: &mut Vec<PathBuf>) {
-    let mut gitignore = base_dir.clone();
-    gitignore.push(".gitignore");
+/// Remove any of `closers`, and whitespace, from the end of `text`
+pub fn strip_closers<'a, S: AsRef<str>>(text: &'a str, closers: &[S]) -> &'a str {
+    let mut text = text.trim();
 
-    if ! gitignore.exists() {
-        return;
+    while let Some(closer) = closers.iter().map(AsRef::as_ref).find(|closer| ! closer.is_empty() && text.ends_with(closer)) {
+        text = text[..text.len() - closer.len()].trim_end();
     }
 
-    for line in std::fs::read_to_string(gitignore).unwrap().lines() {
-        if line.trim().is_empty() {
-            continue;
-        }
+    text
+}
 
-        if line.trim() == "*" {
-            if let Ok(realpath) = canonicalize(&base_dir) {
-                excludes.push(realpath);
+/// Whether `name` can be an assignee, e.g. `alice` or `alice.smith`
+fn is_assignee(name: &str) -> bool {
+    ! name.is_empty() && name.chars().all(|ch| ch.is_alphanumeric() || "-_.".contains(ch))
+}
+
+/// Remove a `todo(alice)` style assignee (e.g. `TODO(alice):` or `todo0(alice)`), or an
+/// `@@alice` word after the marker, so the rest of the scanning doesn't need to know about it
+fn extract_assignee<'a>(line: &'a str, matcher: &Matcher) -> (Cow<'a, str>, Option<String>) {
+    if line.contains('(') {
+        for (word, keyword_len) in matcher.words(line) {
+            let Some((marker, rest)) = word.split_once('(') else {
+                continue;
+            };
+
+            let Some((name, _)) = rest.split_once(')') else {
+                continue;
+            };
+
+            // `unimplemented!(` ends after the `(`
+            let Some(kind) = marker.get(keyword_len..) else {
+                continue;
+            };
+
+            let valid_marker = kind.is_empty() || kind == "?" || kind.starts_with('@') || kind.chars().all(|ch| PRIORITY_CHARS.contains(&ch));
+
+            if ! valid_marker || ! is_assignee(name) {
+                continue;
             }
 
-            break;
-        }
+            // `word` is a slice of `line`
+            let start = word.as_ptr() as usize - line.as_ptr() as usize + marker.len();
+            let end = start + name.len() + 2;
 
-        if line.trim().starts_with('!') {
-            continue;
+            return (Cow::Owned(format!("{}{}", &line[..start], &line[end..])), Some(name.to_string()));
         }
+    }
 
-        if line.trim().starts_with('#') {
-            continue;
-        }
+    if line.contains("@@") {
+        let Some((marker, _)) = matcher.words(line).next() else {
+            return (Cow::Borrowed(line), None);
+        };
 
-        let mut pattern = base_dir.clone();
-        pattern.push(line.trim_end_matches("*/").trim_matches('/'));
+        let after_marker = marker.as_ptr() as usize - line.as_ptr() as usize + marker.len();
 
-        if let Some(pattern_str) = pattern.to_str() {
-            for path in glob(pattern_str).unwrap() {
-                if let Ok(exclude) = canonicalize(path.unwrap()) {
-                    excludes.push(exclude);
-                }
-            }
+        let assignee = line[after_marker..].split_whitespace()
+            .find(|word| word.strip_prefix("@@").is_some_and(is_assignee));
+
+        if let Some(word) = assignee {
+            // The whitespace before the word is removed with it
+            let end = word.as_ptr() as usize - line.as_ptr() as usize + word.len();
+            let start = line[..end - word.len()].trim_end().len();
+
+            return (Cow::Owned(format!("{}{}", &line[..start], &line[end..])), Some(word[2..].to_string()));
         }
     }
+
+    (Cow::Borrowed(line), None)
+}
+
+/// The `keyword` of entries found using `keyword`, which is `None` for `todo`
+fn keyword_name(keyword: &str) -> Option<String> {
+    (! keyword.eq_ignore_ascii_case("todo")).then(|| keyword.to_ascii_lowercase())
 }
 
 pub fn scan_string(str: String, filename: PathBuf, entries: &mut Vec<Entry>) {
-    for (line_num, line) in str.lines().enumerate() {
-        if ! line.to_lowercase().contains("todo") {
-            continue;
-        }
+    scan_string_with(str, filename, Matcher::todo(), entries)
+}
 
-        for word in line.split_whitespace() {
-            if ! word.to_lowercase().starts_with("todo") {
-                continue;
-            }
+/// Scan a string like `scan_string`, also treating the matcher's keywords (e.g. `fixme`) like `todo`
+pub fn scan_string_with(str: String, filename: PathBuf, matcher: &Matcher, entries: &mut Vec<Entry>) {
+    let filename: Arc<Path> = filename.into();
+    let first = entries.len();
+
+    for (line_num, line) in matcher.lines(&str) {
+        let (line, assignee) = extract_assignee(line, matcher);
+        let line = line.as_ref();
+
+        for (word, keyword_len) in matcher.words(line) {
+            let suffix = &word[keyword_len..];
 
             let text = clean_line(line, word);
 
-            if word.starts_with("todo!(") {
+            if word.starts_with("todo!(") || word.starts_with("unimplemented!(") {
+                entries.push(Entry {
+                    assignee: assignee.clone(),
+                    ..Entry::new(
+                        line.trim().to_string(),
+                        Location { file: filename.clone(), line: line_num + 1 },
+                        EntryData::Unimplemented,
+                    )
+                });
+
+                break;
+            }
+
+            // The TODO in the message can be in any case
+            if word.starts_with("dbg!(") || word.starts_with("unreachable!(\"") || word.starts_with("panic!(\"") {
                 entries.push(Entry {
-                    text: line.trim().to_string(),
-                    location: Location {
-                        file: filename.clone(),
-                        line: line_num + 1,
-                    },
-                    data: EntryData::Generic,
+                    assignee: assignee.clone(),
+                    ..Entry::new(line.trim().to_string(), Location { file: filename.clone(), line: line_num + 1 }, EntryData::Marker)
                 });
 
                 break;
             }
 
-            // Handles: `todo`, `TODO`, `todo:`, `TODO:`
+            // Macros are case-sensitive
+            let keyword = &word[..keyword_len];
+            if keyword.eq_ignore_ascii_case(UNIMPLEMENTED) || MARKERS.iter().any(|marker| keyword.eq_ignore_ascii_case(marker)) {
+                continue;
+            }
+
+            let syntax = matcher.syntax(keyword);
+            let keyword = keyword_name(keyword);
+
+            // Handles `TODO:text` and `TODO：text` (a full-width colon, common in CJK comments),
+            // where the text isn't separated from the marker by whitespace, like `TODO: text`
+            let attached = suffix.chars().next()
+                .filter(|colon| *colon == ':' || *colon == '：')
+                .map(|colon| keyword_len + colon.len_utf8())
+                .filter(|&marker_len| ! word[marker_len..].is_empty() && ! word[marker_len..].starts_with(':'));
+            let (suffix, text) = match attached {
+                Some(marker_len) => (&word[keyword_len..marker_len], clean_line(line, &word[..marker_len])),
+                None => (suffix, text),
+            };
+
+            // `todo?`: an idea for someday, rather than something to do
+            if suffix.trim_end_matches([':', '：']) == "?" {
+                entries.push(Entry {
+                    assignee: assignee.clone(),
+                    keyword: keyword.clone(),
+                    ..Entry::new(text.to_string(), Location { file: filename.clone(), line: line_num + 1 }, EntryData::Someday)
+                });
+
+                break;
+            }
+
+            // Handles: `todo`, `TODO`, `todo:`, `TODO:`, `TODO：`
             // Also trims `"` and `'` to handle cases like `foo="bar todo"`
-            if word.to_lowercase().trim_end_matches(':').trim_end_matches('"').trim_end_matches('\'') == "todo" {
+            if suffix.trim_end_matches([':', '：']).trim_end_matches('"').trim_end_matches('\'').is_empty() {
                 entries.push(Entry {
-                    text: text.to_string(),
-                    location: Location {
-                        file: filename.clone(),
-                        line: line_num + 1,
-                    },
-                    data: EntryData::Generic,
+                    assignee: assignee.clone(),
+                    keyword: keyword.clone(),
+                    ..Entry::new(text.to_string(), Location { file: filename.clone(), line: line_num + 1 }, EntryData::Generic)
                 });
 
                 break;
             }
 
-            if word.contains('@') {
-                let category = word.split('@').nth(1).unwrap();
+            // The keyword itself can start with `@`, e.g. `@todo`
+            if suffix.contains('@') {
+                if ! syntax.categories {
+                    continue;
+                }
+
+                let category = suffix.split('@').nth(1).unwrap();
 
                 entries.push(Entry {
-                    text: text.to_string(),
-                    location: Location {
-                        file: filename.clone(),
-                        line: line_num + 1,
-                    },
-                    data: EntryData::Category(category.to_string()),
+                    assignee: assignee.clone(),
+                    keyword: keyword.clone(),
+                    ..Entry::new(
+                        text.to_string(),
+                        Location { file: filename.clone(), line: line_num + 1 },
+                        EntryData::Category(category.to_string()),
+                    )
                 });
 
                 break;
             }
 
-            if word.chars().any(|ch| PRIORITY_CHARS.contains(&ch)) {
-                if let Some(priority) = parse_priority(word) {
+            if suffix.chars().any(|ch| PRIORITY_CHARS.contains(&ch)) {
+                if ! syntax.priorities {
+                    continue;
+                }
+
+                if let Some(priority) = parse_priority(suffix) {
                     entries.push(Entry {
-                        text: text.to_string(),
-                        location: Location {
-                            file: filename.clone(),
-                            line: line_num + 1,
-                        },
-                        data: EntryData::Priority(priority),
+                        assignee: assignee.clone(),
+                        keyword: keyword.clone(),
+                        ..Entry::new(
+                            text.to_string(),
+                            Location { file: filename.clone(), line: line_num + 1 },
+                            EntryData::Priority(priority),
+                        )
                     });
                 }
 
@@ -214,56 +448,320 @@ pub fn scan_string(str: String, filename: PathBuf, entries: &mut Vec<Entry>) {
             }
         }
     }
+
+    for (line_num, captures) in matcher.pattern_matches(&str) {
+        // Lines with a keyword, or an earlier match, already have an entry
+        if entries[first..].iter().any(|entry| entry.location.line == line_num + 1) {
+            continue;
+        }
+
+        let priority = captures.name("priority")
+            .map(|priority| priority.as_str())
+            .filter(|priority| ! priority.is_empty() && priority.chars().all(|ch| PRIORITY_CHARS.contains(&ch)))
+            .and_then(parse_priority);
+        let category = captures.name("category").map(|category| category.as_str()).filter(|category| ! category.is_empty());
+
+        entries.push(Entry::new(
+            captures.name("text").map_or("", |text| strip_closers(text.as_str(), &CLOSERS)).to_string(),
+            Location { file: filename.clone(), line: line_num + 1 },
+            match (priority, category) {
+                (Some(priority), _) => EntryData::Priority(priority),
+                (None, Some(category)) => EntryData::Category(category.to_string()),
+                (None, None) => EntryData::Generic,
+            },
+        ));
+    }
 }
 
-pub fn scan_file(path: &Path, entries: &mut Vec<Entry>) -> io::Result<()> {
-    match std::fs::read_to_string(path) {
-        Ok(str) => scan_string(str, path.to_path_buf(), entries),
-        Err(_) => (),
+/// Decode the content of a file. Besides UTF-8, this handles UTF-8, UTF-16LE, and UTF-16BE
+/// files with a byte order mark (common with Windows tooling). Returns the text and whether
+/// it was transcoded from UTF-16, or `None` for anything else (e.g. binary files).
+pub fn decode(bytes: Vec<u8>) -> Option<(String, bool)> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        if ! bytes.len().is_multiple_of(2) {
+            return None;
+        }
+
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]])).collect();
+
+        String::from_utf16(&units).ok().map(|text| (text, true))
     };
 
+    match bytes.as_slice() {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => std::str::from_utf8(rest).ok().map(|text| (text.to_string(), false)),
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        _ => String::from_utf8(bytes).ok().map(|text| (text, false)),
+    }
+}
+
+/// Like `decode`, failing with `InvalidData` if the bytes can't be decoded
+pub fn decode_text(bytes: Vec<u8>) -> io::Result<(String, bool)> {
+    decode(bytes).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8 or UTF-16 with a BOM"))
+}
+
+/// Read a file as text using `decode`, failing with `InvalidData` if it can't be decoded
+pub fn read_text(path: &Path) -> io::Result<(String, bool)> {
+    decode_text(fs::read(path)?)
+}
+
+/// Read the unmodified lines of the entries from their files, for `--raw`
+pub fn add_raw_lines(entries: &mut [Entry]) -> io::Result<()> {
+    let mut files: HashMap<Arc<Path>, Vec<String>> = HashMap::new();
+
+    for entry in entries {
+        if ! files.contains_key(&entry.location.file) {
+            let (content, _) = read_text(&entry.location.file)?;
+            files.insert(entry.location.file.clone(), content.lines().map(String::from).collect());
+        }
+
+        // Plugins can report line 0
+        entry.raw = entry.location.line.checked_sub(1).and_then(|index| files[&entry.location.file].get(index)).cloned();
+    }
+
     Ok(())
 }
 
-pub fn scan_dir(dir: &Path, entries: &mut Vec<Entry>, excludes: &mut Vec<PathBuf>, stats: &mut Stats) -> io::Result<()> {
-    let mut gitignore = dir.to_path_buf().clone();
-    gitignore.push(".gitignore");
+/// Scan a single file, returning whether it was transcoded from UTF-16. Files that can't be
+/// decoded are skipped.
+pub fn scan_file(path: &Path, scanners: &Scanners, entries: &mut Vec<Entry>) -> io::Result<bool> {
+    let Ok((str, transcoded)) = read_text(path) else {
+        return Ok(false);
+    };
+
+    scanners.scan(str, path, entries);
+
+    Ok(transcoded)
+}
+
+/// Resolve an `--exclude` relative to `root`. Bare names exclude everything with that name
+/// at any depth, like in .gitignore. Paths that don't exist are kept as patterns and matched
+/// during the walk, e.g. an optional directory or `packages/*/dist`.
+pub fn resolve_exclude(root: &Path, exclude: &str) -> PathBuf {
+    let exclude = exclude.trim_end_matches('/');
+    let root_pattern = PathBuf::from(Pattern::escape(&root.to_string_lossy()));
+
+    if Path::new(exclude).components().count() == 1 {
+        return root_pattern.join("**").join(exclude);
+    }
+
+    canonicalize(root.join(exclude)).unwrap_or_else(|_| root_pattern.join(exclude))
+}
+
+/// Split an `--exclude` scoped to one of the scanned `roots` (as they were passed), like
+/// `frontend:dist`, into the root and the exclude. Other excludes apply to all roots.
+pub fn split_scoped_exclude<'a>(exclude: &'a str, roots: &[String]) -> (Option<&'a str>, &'a str) {
+    roots.iter()
+        .filter_map(|root| {
+            let scoped = exclude.strip_prefix(root.as_str())?.strip_prefix(':')?;
+
+            Some((&exclude[..root.len()], scoped))
+        })
+        .max_by_key(|(root, _)| root.len())
+        .map_or((None, exclude), |(root, scoped)| (Some(root), scoped))
+}
+
+fn is_pattern(str: &str) -> bool {
+    str.contains(['*', '?', '['])
+}
+
+/// Whether `path` is excluded by `exclude`. Excludes are canonical paths, except for the
+/// `--exclude`s that didn't exist when the scan started. Those are kept as patterns
+/// (e.g. `/project/packages/*/dist`) and matched against the paths during the walk.
+pub fn matches_exclude(path: &Path, exclude: &Path) -> bool {
+    if path == exclude {
+        return true;
+    }
+
+    let Some(pattern) = exclude.to_str().filter(|exclude| is_pattern(exclude)) else {
+        return false;
+    };
+
+    // `/project/**/node_modules` (from `--exclude node_modules`) and `/project/**/*.png` (from
+    // the skipped files) are checked without compiling a pattern, since the default excludes
+    // and skipped files are checked for every path
+    if let (Some(name), Some(parent)) = (exclude.file_name().and_then(|name| name.to_str()), exclude.parent()) {
+        if let (Some("**"), Some(root)) = (parent.file_name().and_then(|name| name.to_str()), parent.parent()) {
+            if ! is_pattern(&root.to_string_lossy()) {
+                let file_name = path.file_name().map(|name| name.to_string_lossy());
+
+                if ! is_pattern(name) {
+                    return file_name.is_some_and(|file_name| file_name == name) && path.starts_with(root);
+                }
+
+                if let Some(suffix) = name.strip_prefix('*').filter(|suffix| ! is_pattern(suffix)) {
+                    return file_name.is_some_and(|file_name| file_name.ends_with(suffix)) && path.starts_with(root);
+                }
+            }
+        }
+    }
+
+    let options = MatchOptions { require_literal_separator: true, ..MatchOptions::new() };
+
+    Pattern::new(pattern).is_ok_and(|pattern| pattern.matches_path_with(path, options))
+}
+
+/// Whether a file found outside of the walk (e.g. in the git index) would be
+/// scanned: it has to be inside one of the scanned paths, with no hidden,
+/// excluded, or ignored components on the way there.
+pub fn is_scannable(file: &Path, paths: &[PathBuf], excludes: &[PathBuf], ignores: &mut IgnoreCache) -> bool {
+    let Some(root) = paths.iter().find(|root| file.starts_with(root)) else {
+        return false;
+    };
+
+    let mut current = root.clone();
+
+    for component in file.strip_prefix(root).unwrap().components() {
+        let dir_ignores = ignores.get(root, &current);
+        current.push(component);
+
+        if component.as_os_str().to_string_lossy().starts_with('.') {
+            return false;
+        }
+
+        if excludes.iter().any(|exclude| matches_exclude(&current, exclude)) {
+            return false;
+        }
+
+        if dir_ignores.is_ignored(&current, current != file) {
+            return false;
+        }
+    }
+
+    true
+}
 
-    if gitignore.exists() {
-        add_excludes_from_gitignore(&dir.to_path_buf(), excludes);
+/// Scan a single file or a directory, the same way the CLI scans the paths passed to it
+pub fn scan_path(path: &Path, scanners: &Scanners, entries: &mut Vec<Entry>, excludes: &[PathBuf], stats: &mut Stats) -> io::Result<()> {
+    if path.is_dir() {
+        scan_dir(path, scanners, entries, excludes, stats)
+    } else {
+        stats.add_file(path.to_string_lossy().to_string());
 
-        // `add_excludes_from_gitignore` can add the *entire* directory being scanned here to excludes
-        // e.g. if it contains a `*` line. Tthe directory is visited first, and gitignore is read second,
-        // so the exclude would not affect anything inside the for loop. For that reason, we re-check if
-        // `dir` hasn't become excluded after running `add_excludes_from_gitignore`.
-        for exclude in &*excludes {
-            if canonicalize(dir.to_path_buf()).unwrap() == *exclude {
-                return Ok(());
+        if let Ok((str, transcoded)) = read_text(path) {
+            if transcoded {
+                stats.add_transcoded_file(path.to_string_lossy().to_string());
             }
+
+            scanners.scan(str, path, entries);
+        }
+
+        Ok(())
+    }
+}
+
+/// Scan `dir`, reading and scanning its files in parallel. The entries are in the same
+/// order as they'd be with [`stream_dir`], regardless of which file finishes first.
+pub fn scan_dir(dir: &Path, scanners: &Scanners, entries: &mut Vec<Entry>, excludes: &[PathBuf], stats: &mut Stats) -> io::Result<()> {
+    let mut files: Vec<PathBuf> = vec![];
+
+    walk_dir(dir, excludes, stats, &mut |path, _| {
+        files.push(path.to_path_buf());
+
+        Ok(())
+    })?;
+
+    let scanned: Vec<Option<ScannedFile>> = files.par_iter()
+        .map(|path| scan_timed(path, scanners))
+        .collect();
+
+    for (path, scanned) in files.iter().zip(scanned) {
+        if let Some(scanned) = scanned {
+            entries.extend(scanned.record(path, stats));
+        }
+    }
+
+    Ok(())
+}
+
+/// The entries of a file, with what [`Stats`] records about it
+struct ScannedFile {
+    entries: Vec<Entry>,
+    transcoded: bool,
+    read: Duration,
+    parse: Duration,
+}
+
+impl ScannedFile {
+    /// Add the file to `stats`, returning its entries
+    fn record(self, path: &Path, stats: &mut Stats) -> Vec<Entry> {
+        if self.transcoded {
+            stats.add_transcoded_file(path.to_string_lossy().to_string());
         }
+
+        stats.add_file_timing(path.to_string_lossy().to_string(), self.read, self.parse);
+
+        self.entries
     }
+}
+
+/// Read and scan `path`, or `None` if it can't be decoded
+fn scan_timed(path: &Path, scanners: &Scanners) -> Option<ScannedFile> {
+    let start = Instant::now();
+    let (str, transcoded) = read_text(path).ok()?;
+    let read = start.elapsed();
+
+    let start = Instant::now();
+    let mut entries: Vec<Entry> = vec![];
+    scanners.scan(str, path, &mut entries);
+
+    Some(ScannedFile { entries, transcoded, read, parse: start.elapsed() })
+}
+
+/// Scan `dir` like `scan_dir`, but one file at a time, passing the entries of each file to
+/// `found` as soon as the file is scanned. Files without entries are skipped.
+pub fn stream_dir(dir: &Path, scanners: &Scanners, excludes: &[PathBuf], stats: &mut Stats, found: &mut dyn FnMut(Vec<Entry>)) -> io::Result<()> {
+    walk_dir(dir, excludes, stats, &mut |path, stats| {
+        if let Some(scanned) = scan_timed(path, scanners) {
+            let entries = scanned.record(path, stats);
+
+            if ! entries.is_empty() {
+                found(entries);
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// Walk `dir` recursively, calling `visit` for every file that isn't excluded or ignored by git
+pub fn walk_dir(dir: &Path, excludes: &[PathBuf], stats: &mut Stats, visit: &mut dyn FnMut(&Path, &mut Stats) -> io::Result<()>) -> io::Result<()> {
+    walk(dir, &Ignores::for_dir(dir), excludes, stats, visit)
+}
 
+/// Walk `dir`, with `ignores` being the gitignore rules for its entries
+fn walk(dir: &Path, ignores: &Ignores, excludes: &[PathBuf], stats: &mut Stats, visit: &mut dyn FnMut(&Path, &mut Stats) -> io::Result<()>) -> io::Result<()> {
     stats.add_folder(dir.to_string_lossy().to_string());
 
-    'entry: for entry in fs::read_dir(dir)? {
+    for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
 
-        if path.components().last().unwrap().as_os_str().to_string_lossy().starts_with('.') {
+        if path.components().next_back().unwrap().as_os_str().to_string_lossy().starts_with('.') {
             continue;
         }
 
-        for exclude in &*excludes {
-            if canonicalize(&path).unwrap() == *exclude {
-                continue 'entry;
-            }
+        // e.g. a dangling symlink, which has nothing to scan
+        let Ok(realpath) = canonicalize(&path) else {
+            continue;
+        };
+
+        if excludes.iter().any(|exclude| *exclude == realpath || matches_exclude(&path, exclude)) {
+            continue;
+        }
+
+        let is_dir = path.is_dir();
+
+        // Like in git, files in an ignored directory can't be re-included
+        if ignores.is_ignored(&path, is_dir) {
+            continue;
         }
 
-        if path.is_dir() {
-            scan_dir(path.as_path(), entries, excludes, stats)?
+        if is_dir {
+            walk(path.as_path(), &ignores.with_dir(&path), excludes, stats, visit)?
         } else {
             stats.add_file(path.to_string_lossy().to_string());
-            scan_file(path.as_path(), entries)?
+            visit(path.as_path(), stats)?
         }
     }
 
@@ -271,34 +769,303 @@ pub fn scan_dir(dir: &Path, entries: &mut Vec<Entry>, excludes: &mut Vec<PathBuf
 }
 
 pub fn scan_todo_file(path: &Path, entries: &mut Vec<Entry>) -> io::Result<()> {
-    let str = fs::read_to_string(path)?;
-    let mut current_category: Option<&str> = None;
+    scan_todo_string(&read_text(path)?.0, path, entries);
+
+    Ok(())
+}
+
+/// The text of a markdown list item (`- foo`, `* foo`, `+ foo`, `1. foo`, or `1) foo`), without
+/// the bullet and an unchecked checkbox (`- [ ] foo`)
+fn list_item(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+
+    let rest = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let number = line.len() - line.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+
+            if number == 0 {
+                return None;
+            }
+
+            line[number..].strip_prefix(['.', ')'])?
+        },
+    };
+
+    // `---` rules and `**bold**` text aren't list items
+    if ! rest.is_empty() && ! rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let text = rest.trim();
+
+    Some(text.strip_prefix("[ ] ").unwrap_or(text))
+}
+
+/// The text of a list item that isn't done, i.e. `None` for checked ones (`- [x] foo`)
+fn open_item(line: &str) -> Option<&str> {
+    list_item(line).filter(|item| ! item.starts_with("[x]") && ! item.starts_with("[X]"))
+}
+
+/// The text of a markdown heading, e.g. `Bugs` in `## Bugs`
+fn heading(line: &str) -> &str {
+    line.trim_start_matches('#').trim()
+}
+
+/// Add the heading on `line` to the `headings` above it, replacing the headings of the same
+/// or a lower level, and return the category, e.g. `Backend/Auth`
+fn push_heading<'a>(headings: &mut Vec<(usize, &'a str)>, line: &'a str) -> String {
+    let level = line.len() - line.trim_start_matches('#').len();
+
+    headings.retain(|(parent_level, _)| *parent_level < level);
+    headings.push((level, heading(line)));
+
+    headings.iter().map(|(_, heading)| *heading).collect::<Vec<_>>().join("/")
+}
+
+/// The paragraphs directly under the headings of todo.md, by category. Blank lines between
+/// the heading and the paragraph are allowed, and the lines of the paragraph are joined.
+pub fn todo_descriptions(str: &str) -> BTreeMap<String, String> {
+    let front_matter_lines = FrontMatter::split(str).map_or(0, |(_, lines)| lines);
+
+    let mut descriptions: BTreeMap<String, String> = BTreeMap::new();
+    let mut headings: Vec<(usize, &str)> = vec![];
+    // The category whose description can still continue on the next line
+    let mut current: Option<String> = None;
+
+    for line in str.lines().skip(front_matter_lines) {
+        if line.starts_with('#') {
+            current = Some(push_heading(&mut headings, line));
+
+            continue;
+        }
+
+        let Some(category) = &current else {
+            continue;
+        };
+
+        if list_item(line).is_some() {
+            current = None;
+        } else if line.trim().is_empty() {
+            if descriptions.contains_key(category) {
+                current = None;
+            }
+        } else {
+            let description = descriptions.entry(category.clone()).or_default();
+
+            if ! description.is_empty() {
+                description.push(' ');
+            }
+
+            description.push_str(line.trim());
+        }
+    }
+
+    descriptions
+}
+
+pub fn scan_todo_string(str: &str, path: &Path, entries: &mut Vec<Entry>) {
+    let file: Arc<Path> = path.into();
+    // Invalid front matter is reported when the config is loaded
+    let front_matter = FrontMatter::parse(str).unwrap_or_default();
+    let front_matter_lines = FrontMatter::split(str).map_or(0, |(_, lines)| lines);
+
+    let mut current_category: Option<String> = front_matter.category;
+    // The headings above the current line, with their levels, e.g. `[(2, "Backend"), (3, "Auth")]`
+    let mut headings: Vec<(usize, &str)> = vec![];
+
+    // This can produce:
+    // - generic todos (above any category, unless the front matter sets a default category)
+    // - category todos (below a ## category heading, or `parent/child` below a ### subheading)
+    // - priority todos (priority keyword part of the line)
+    'line: for (line_num, line) in str.lines().enumerate().skip(front_matter_lines) {
+        if line.starts_with('#') {
+            current_category = Some(push_heading(&mut headings, line));
+
+            continue;
+        }
+
+        let Some(item) = open_item(line) else {
+            continue;
+        };
+
+        for word in item.split_whitespace() {
+            if starts_with_todo(word) && word.chars().any(|ch| PRIORITY_CHARS.contains(&ch)) {
+                if let Some(priority) = parse_priority(&word[4..]) {
+                    entries.push(Entry {
+                        source: SourceKind::TodoFile,
+                        ..Entry::new(
+                            clean_line(item, word).to_string(),
+                            Location { file: file.clone(), line: line_num + 1 },
+                            EntryData::Priority(priority),
+                        )
+                    });
+                }
+
+                continue 'line;
+            }
+        }
+
+        let text = item.to_string();
+
+        if let Some(category) = &current_category {
+            entries.push(Entry {
+                source: SourceKind::TodoFile,
+                ..Entry::new(text, Location { file: file.clone(), line: line_num + 1 }, EntryData::Category(category.to_string()))
+            });
+
+            continue;
+        }
+
+        entries.push(Entry {
+            source: SourceKind::TodoFile,
+            ..Entry::new(text, Location { file: file.clone(), line: line_num + 1 }, EntryData::Generic)
+        });
+    }
+}
+
+/// Org-mode headlines starting with a keyword, e.g. `** TODO [#A] Fix the build :backend:`.
+/// The priority cookies `[#A]`, `[#B]`, ... are priorities 0, 1, ..., and the first tag is the
+/// category of headlines without a priority. Other lines are scanned like code.
+pub fn scan_org_string(str: &str, path: &Path, matcher: &Matcher, entries: &mut Vec<Entry>) {
+    let file: Arc<Path> = path.into();
+    // The content without the headlines, with the same line numbers
+    let mut rest = String::with_capacity(str.len());
+
+    for (line_num, line) in str.lines().enumerate() {
+        match org_headline(line, matcher) {
+            Some((data, keyword, text)) => entries.push(Entry {
+                keyword,
+                ..Entry::new(text.to_string(), Location { file: file.clone(), line: line_num + 1 }, data)
+            }),
+            None => rest.push_str(line),
+        }
+
+        rest.push('\n');
+    }
+
+    scan_string_with(rest, path.to_path_buf(), matcher, entries);
+}
+
+fn org_headline<'a>(line: &'a str, matcher: &Matcher) -> Option<(EntryData, Option<String>, &'a str)> {
+    let headline = line.trim_start_matches('*');
+
+    if headline.len() == line.len() || ! headline.starts_with(' ') {
+        return None;
+    }
+
+    let (keyword, text) = headline.trim_start().split_once(' ').unwrap_or((headline.trim_start(), ""));
+
+    if matcher.words(keyword).next().is_none_or(|(_, keyword_len)| keyword_len != keyword.len()) {
+        return None;
+    }
+
+    let mut text = text.trim();
+    let mut priority: Option<isize> = None;
+
+    if let Some(cookie) = text.strip_prefix("[#").and_then(|cookie| cookie.get(..2)) {
+        if let [letter @ b'A'..=b'Z', b']'] = cookie.as_bytes() {
+            priority = Some((letter - b'A') as isize);
+            text = text[4..].trim_start();
+        }
+    }
+
+    let mut tag: Option<&str> = None;
+
+    if let Some((rest, tags)) = text.rsplit_once(char::is_whitespace).filter(|(_, tags)| tags.len() > 2 && tags.starts_with(':') && tags.ends_with(':')) {
+        tag = tags.trim_matches(':').split(':').next();
+        text = rest.trim_end();
+    }
+
+    let data = match (priority, tag) {
+        (Some(priority), _) => EntryData::Priority(priority),
+        (None, Some(tag)) => EntryData::Category(tag.to_string()),
+        (None, None) => EntryData::Generic,
+    };
+
+    Some((data, keyword_name(keyword), text))
+}
+
+/// Jupyter notebooks. The source lines of the cells are scanned like code, with the line
+/// numbers of the notebook file, so the locations point into the JSON. Jupyter writes each
+/// source line on its own line (`"source": ["# todo foo\n", ...]`).
+pub fn scan_notebook_string(str: &str, path: &Path, matcher: &Matcher, entries: &mut Vec<Entry>) {
+    // The source lines, with the same line numbers as in the notebook
+    let mut sources = String::with_capacity(str.len());
+    let mut in_source = false;
+
+    for line in str.lines() {
+        let line = line.trim();
+        let mut source: Option<String> = None;
+
+        if in_source {
+            if line.starts_with(']') {
+                in_source = false;
+            } else {
+                source = serde_json::from_str(line.strip_suffix(',').unwrap_or(line)).ok();
+            }
+        } else if let Some(value) = line.strip_prefix("\"source\":") {
+            let value = value.trim();
+            let value = value.strip_suffix(',').unwrap_or(value);
+
+            match value {
+                "[" => in_source = true,
+                // Sources can also be a single string
+                _ => source = serde_json::from_str(value).ok(),
+            }
+        }
+
+        if let Some(source) = source {
+            sources.push_str(&source.trim_end_matches('\n').replace('\n', " "));
+        }
+
+        sources.push('\n');
+    }
+
+    scan_string_with(sources, path.to_path_buf(), matcher, entries);
+}
+
+pub fn scan_readme_file(path: &Path, entries: &mut Vec<Entry>) -> io::Result<()> {
+    scan_readme_string(&read_text(path)?.0, path, entries);
+
+    Ok(())
+}
+
+pub fn scan_readme_string(str: &str, path: &Path, entries: &mut Vec<Entry>) {
+    let file: Arc<Path> = path.into();
+    let mut in_todo_section = false;
 
     // This can produce:
     // - generic todos (above any category)
-    // - category todos (below a ## category heading)
+    // - category todos (below a ## category heading) todo@real add this logic and update README.md
     // - priority todos (priority keyword part of the line)
     'line: for (line_num, line) in str.lines().enumerate() {
         if line.starts_with('#') {
-            current_category = Some(line.split_once("# ").unwrap().1);
+            let cleaned_section = heading(line).to_lowercase().trim_end_matches(':').trim().to_string();
+
+            in_todo_section = cleaned_section == "todo" || cleaned_section == "todos";
 
             continue;
         }
 
-        if ! line.trim_start().starts_with('-') {
+        if ! in_todo_section {
             continue;
         }
 
-        for word in line.split_whitespace() {
-            if word.to_lowercase().starts_with("todo") && word.chars().any(|ch| PRIORITY_CHARS.contains(&ch)) {
-                if let Some(priority) = parse_priority(word) {
+        let Some(item) = open_item(line) else {
+            continue;
+        };
+
+        for word in item.split_whitespace() {
+            if starts_with_todo(word) && word.chars().any(|ch| PRIORITY_CHARS.contains(&ch)) {
+                if let Some(priority) = parse_priority(&word[4..]) {
                     entries.push(Entry {
-                        text: clean_line(line, word).to_string(),
-                        location: Location {
-                            file: path.to_path_buf(),
-                            line: line_num + 1,
-                        },
-                        data: EntryData::Priority(priority),
+                        source: SourceKind::Readme,
+                        ..Entry::new(
+                            clean_line(item, word).to_string(),
+                            Location { file: file.clone(), line: line_num + 1 },
+                            EntryData::Priority(priority),
+                        )
                     });
                 }
 
@@ -306,176 +1073,380 @@ pub fn scan_todo_file(path: &Path, entries: &mut Vec<Entry>) -> io::Result<()> {
             }
         }
 
-        let text = line.trim_start().trim_start_matches("- [ ] ").trim_start_matches("- ").to_string();
+        // README.md can only have priority entries and generic entries
+        entries.push(Entry {
+            source: SourceKind::Readme,
+            ..Entry::new(item.to_string(), Location { file: file.clone(), line: line_num + 1 }, EntryData::Generic)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{TempDir, entry};
+    use crate::matcher::Syntax;
+
+    #[test]
+    fn generic_test() {
+        let str = r#"
+            1
+            2
+            // todo foo
+            /* TODO: foo bar */
+            /*
+
+            * TODO baz
+            TODO baz2
+            TODO baz2 todo
+            <!-- TODO foo2 -->
+            */
+        "#;
+
+        let mut entries: Vec<Entry> = vec![];
+        let mut path = PathBuf::new();
+        path.push("foo.txt");
+
+        scan_string(str.to_string(), path.clone(), &mut entries);
+
+        assert_eq!(6, entries.len());
+
+        assert_eq!(Entry::new(String::from("foo"), Location { file: path.clone().into(), line: 4 }, EntryData::Generic), entries[0]);
+
+        assert_eq!(Entry::new(String::from("foo bar"), Location { file: path.clone().into(), line: 5 }, EntryData::Generic), entries[1]);
+
+        assert_eq!(Entry::new(String::from("baz"), Location { file: path.clone().into(), line: 8 }, EntryData::Generic), entries[2]);
+
+        assert_eq!(Entry::new(String::from("baz2"), Location { file: path.clone().into(), line: 9 }, EntryData::Generic), entries[3]);
+
+        assert_eq!(Entry::new(String::from("baz2 todo"), Location { file: path.clone().into(), line: 10 }, EntryData::Generic), entries[4]);
+
+        assert_eq!(Entry::new(String::from("foo2"), Location { file: path.clone().into(), line: 11 }, EntryData::Generic), entries[5]);
+    }
+
+    #[test]
+    fn attached_text_test() {
+        let str = r#"
+            // TODO:fix this
+            // TODO：修正する
+            // TODO： 確認 */
+            // todo@日本語 テキスト
+            let x = Todo::new();
+        "#;
+
+        let mut entries: Vec<Entry> = vec![];
+        scan_string(str.to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        let texts: Vec<(&str, &EntryData)> = entries.iter().map(|entry| (entry.text.as_str(), &entry.data)).collect();
+
+        assert_eq!(vec![
+            ("fix this", &EntryData::Generic),
+            ("修正する", &EntryData::Generic),
+            ("確認", &EntryData::Generic),
+            ("テキスト", &EntryData::Category("日本語".to_string())),
+        ], texts);
+    }
+
+    #[test]
+    fn add_raw_lines_test() {
+        let dir = TempDir::new("raw");
+        let path = dir.join("main.rs");
+        fs::write(&path, "fn main() {\n    // todo0 foo\n}\n").unwrap();
+
+        let file = path.to_str().unwrap();
+        let mut entries = vec![entry(file, 2, EntryData::Priority(0), "foo"), entry(file, 0, EntryData::Generic, "bar")];
+        add_raw_lines(&mut entries).unwrap();
+
+        assert_eq!(Some("    // todo0 foo"), entries[0].raw.as_deref());
+        assert_eq!(None, entries[1].raw);
+    }
+
+    #[test]
+    fn decode_test() {
+        let utf16 = |text: &str, bom: [u8; 2], to_bytes: fn(u16) -> [u8; 2]| -> Vec<u8> {
+            bom.into_iter().chain(text.encode_utf16().flat_map(to_bytes)).collect()
+        };
+
+        assert_eq!(Some(("// todo0 ü".to_string(), false)), decode("// todo0 ü".as_bytes().to_vec()));
+        assert_eq!(Some(("// todo0 ü".to_string(), false)), decode([&[0xEF, 0xBB, 0xBF], "// todo0 ü".as_bytes()].concat()));
+        assert_eq!(Some(("// todo0 ü".to_string(), true)), decode(utf16("// todo0 ü", [0xFF, 0xFE], u16::to_le_bytes)));
+        assert_eq!(Some(("// todo0 ü".to_string(), true)), decode(utf16("// todo0 ü", [0xFE, 0xFF], u16::to_be_bytes)));
+        assert_eq!(None, decode(vec![0x00, 0xFF, 0xFE, 0x80]));
+        assert_eq!(None, decode(vec![0xFF, 0xFE, 0x41]));
+        assert_eq!(io::ErrorKind::InvalidData, decode_text(vec![0xFF, 0xFE, 0x41]).unwrap_err().kind());
+
+        let dir = TempDir::new("decode");
+        let path = dir.join("main.rs");
+        fs::write(&path, utf16("// todo0 ü", [0xFF, 0xFE], u16::to_le_bytes)).unwrap();
+
+        let mut entries: Vec<Entry> = vec![];
+        assert!(scan_file(&path, &Scanners::new(Matcher::todo().clone()), &mut entries).unwrap());
+        assert_eq!("ü", entries[0].text);
+    }
+
+    #[test]
+    fn case_insensitive_test() {
+        assert_eq!(Some(7), find_todo("// ñ: ToDo foo"));
+        assert_eq!(None, find_todo("// tod o"));
+        assert!(starts_with_todo("TODO:"));
+        assert!(! starts_with_todo("tod"));
+        assert_eq!(Some(-1), parse_priority("00"));
+        assert_eq!(Some(2), parse_priority("2todo"));
+
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// ToDo@Foo a\n// tOdO? b\n// TODO: c\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        assert_eq!(vec![
+            EntryData::Category("Foo".to_string()),
+            EntryData::Someday,
+            EntryData::Generic,
+        ], entries.into_iter().map(|entry| entry.data).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn keywords_test() {
+        let matcher = Matcher::new(&["fixme", "hack"]).unwrap();
+
+        let mut entries: Vec<Entry> = vec![];
+        scan_string_with("// FIXME: a\n// hack0 b\n// fixme@perf c\n// FIXME(alice) d\n// todo e\n// fixmes f\n".to_string(), PathBuf::from("foo.txt"), &matcher, &mut entries);
+
+        assert_eq!(vec![
+            ("a", &EntryData::Generic, None, Some("fixme")),
+            ("b", &EntryData::Priority(0), None, Some("hack")),
+            ("c", &EntryData::Category("perf".to_string()), None, Some("fixme")),
+            ("d", &EntryData::Generic, Some("alice"), Some("fixme")),
+            ("e", &EntryData::Generic, None, None),
+        ], entries.iter().map(|entry| (entry.text.as_str(), &entry.data, entry.assignee.as_deref(), entry.keyword.as_deref())).collect::<Vec<_>>());
+
+        // The built-in keywords are only found in uppercase
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// XXX: a\n// note b\n// NOTE c\n// BUG? d\n// a bug e\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        assert_eq!(vec![
+            ("a", &EntryData::Generic, Some("xxx")),
+            ("c", &EntryData::Generic, Some("note")),
+            ("d", &EntryData::Someday, Some("bug")),
+        ], entries.iter().map(|entry| (entry.text.as_str(), &entry.data, entry.keyword.as_deref())).collect::<Vec<_>>());
+
+        assert_eq!(vec!["XXX", "NOTE", "BUG?"], entries.iter().map(Entry::badge).collect::<Vec<_>>());
+
+        // Keywords can start with `@`, and be limited to parts of the syntax
+        let matcher = Matcher::new(&["@todo", "review"]).unwrap().with_syntax("review", Syntax { categories: false, priorities: true });
 
-        if let Some(category) = current_category {
-            entries.push(Entry {
-                text,
-                location: Location {
-                    file: path.to_path_buf(),
-                    line: line_num + 1,
-                },
-                data: EntryData::Category(category.to_string()),
-            });
+        let mut entries: Vec<Entry> = vec![];
+        scan_string_with(" * @todo a\n * @todo@api b\n// REVIEW@api c\n// review1 d\n".to_string(), PathBuf::from("foo.txt"), &matcher, &mut entries);
 
-            continue;
-        }
+        assert_eq!(vec![
+            ("a", &EntryData::Generic, Some("@todo")),
+            ("b", &EntryData::Category("api".to_string()), Some("@todo")),
+            ("d", &EntryData::Priority(1), Some("review")),
+        ], entries.iter().map(|entry| (entry.text.as_str(), &entry.data, entry.keyword.as_deref())).collect::<Vec<_>>());
+    }
 
-        entries.push(Entry {
-            text,
-            location: Location {
-                file: path.to_path_buf(),
-                line: line_num + 1,
-            },
-            data: EntryData::Generic,
-        });
+    #[test]
+    fn matches_exclude_test() {
+        assert!(matches_exclude(Path::new("/project/dist"), Path::new("/project/dist")));
+        assert!(matches_exclude(Path::new("/project/packages/a/dist"), Path::new("/project/packages/*/dist")));
+        assert!(matches_exclude(Path::new("/project/a/b/generated"), Path::new("/project/**/generated")));
+        assert!(! matches_exclude(Path::new("/project/packages/a/b/dist"), Path::new("/project/packages/*/dist")));
+        assert!(! matches_exclude(Path::new("/project/dist2"), Path::new("/project/dist")));
+
+        assert!(matches_exclude(Path::new("/project/node_modules"), Path::new("/project/**/node_modules")));
+        assert!(matches_exclude(Path::new("/project/packages/a/node_modules"), Path::new("/project/**/node_modules")));
+        assert!(! matches_exclude(Path::new("/elsewhere/node_modules"), Path::new("/project/**/node_modules")));
+        assert!(! matches_exclude(Path::new("/project/node_modules2"), Path::new("/project/**/node_modules")));
+        assert!(matches_exclude(Path::new("/project/a.min.js"), Path::new("/project/**/*.min.js")));
+        assert!(matches_exclude(Path::new("/project/public/a.min.js"), Path::new("/project/**/*.min.js")));
+        assert!(! matches_exclude(Path::new("/project/public/a.js"), Path::new("/project/**/*.min.js")));
+        assert!(! matches_exclude(Path::new("/elsewhere/a.min.js"), Path::new("/project/**/*.min.js")));
+        assert!(matches_exclude(Path::new("/project/a/b/logo.png"), Path::new("/project/**/logo.*")));
+        assert!(matches_exclude(Path::new("/project/a/x.js.map"), Path::new("/project/**/*.map")));
     }
 
-    Ok(())
-}
+    #[test]
+    fn split_scoped_exclude_test() {
+        let roots = vec!["frontend".to_string(), "../backend".to_string(), "../backend/api".to_string()];
+
+        assert_eq!((Some("frontend"), "dist"), split_scoped_exclude("frontend:dist", &roots));
+        assert_eq!((Some("../backend/api"), "generated/*.rs"), split_scoped_exclude("../backend/api:generated/*.rs", &roots));
+        assert_eq!((None, "node_modules"), split_scoped_exclude("node_modules", &roots));
+        assert_eq!((None, "other:dist"), split_scoped_exclude("other:dist", &roots));
+        assert_eq!((None, "frontend"), split_scoped_exclude("frontend", &roots));
+    }
 
-pub fn scan_readme_file(path: &Path, entries: &mut Vec<Entry>) -> io::Result<()> {
-    let str = fs::read_to_string(path)?;
-    let mut in_todo_section = false;
+    #[test]
+    fn test_paths_test() {
+        let excludes: Vec<PathBuf> = TEST_PATHS.iter().map(|path| resolve_exclude(Path::new("/project"), path)).collect();
+        let excluded = |path: &str| excludes.iter().any(|exclude| matches_exclude(Path::new(path), exclude));
+
+        assert!(excluded("/project/tests"));
+        assert!(excluded("/project/src/__tests__"));
+        assert!(excluded("/project/spec"));
+        assert!(excluded("/project/pkg/server_test.go"));
+        assert!(excluded("/project/src/app.test.tsx"));
+        assert!(excluded("/project/src/UserTest.java"));
+        assert!(! excluded("/project/src/server.go"));
+        assert!(! excluded("/project/src/testing.rs"));
+        assert!(! excluded("/project/src/contest"));
+    }
 
-    // This can produce:
-    // - generic todos (above any category)
-    // - category todos (below a ## category heading) todo@real add this logic and update README.md
-    // - priority todos (priority keyword part of the line)
-    'line: for (line_num, line) in str.lines().enumerate() {
-        if line.starts_with('#') {
-            let section = line.split_once("# ").unwrap().1;
-            let cleaned_section = section.to_lowercase().trim_end_matches(':').trim().to_string();
+    #[test]
+    fn shared_path_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo a\n// todo b\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
 
-            if cleaned_section == "todo" || cleaned_section == "todos" {
-                in_todo_section = true;
-            } else {
-                in_todo_section = false;
-            }
+        assert!(Arc::ptr_eq(&entries[0].location.file, &entries[1].location.file));
+    }
 
-            continue;
-        }
+    #[test]
+    fn entry_counts_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo1 a\n// todo00 b\n// todo1 c\n// todo@foo d\n// todo e\n// todo? f\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
 
-        if ! in_todo_section {
-            continue;
-        }
+        let mut stats = Stats::new(0);
+        stats.add_entries(&entries);
 
-        if ! line.trim_start().starts_with('-') {
-            continue;
-        }
+        assert_eq!(vec![(&-1, &1), (&1, &2)], stats.priority_counts.iter().collect::<Vec<_>>());
+        assert_eq!((1, 1, 1), (stats.category_count, stats.generic_count, stats.someday_count));
+    }
 
-        for word in line.split_whitespace() {
-            if word.to_lowercase().starts_with("todo") && word.chars().any(|ch| PRIORITY_CHARS.contains(&ch)) {
-                if let Some(priority) = parse_priority(word) {
-                    entries.push(Entry {
-                        text: clean_line(line, word).to_string(),
-                        location: Location {
-                            file: path.to_path_buf(),
-                            line: line_num + 1,
-                        },
-                        data: EntryData::Priority(priority),
-                    });
-                }
+    #[cfg(unix)]
+    #[test]
+    fn dangling_symlink_test() {
+        let dir = TempDir::new("symlink");
+        fs::write(dir.join("main.rs"), "// todo foo\n").unwrap();
+        std::os::unix::fs::symlink(dir.join("missing.rs"), dir.join("link.rs")).unwrap();
 
-                continue 'line;
-            }
-        }
+        let mut files: Vec<PathBuf> = vec![];
+        walk_dir(&dir, &[], &mut Stats::new(0), &mut |path, _| {
+            files.push(path.to_path_buf());
 
-        // README.md can only have priority entries and generic entries
-        entries.push(Entry {
-            text: line.trim_start().trim_start_matches("- [ ] ").trim_start_matches("- ").to_string(),
-            location: Location {
-                file: path.to_path_buf(),
-                line: line_num + 1,
-            },
-            data: EntryData::Generic,
-        });
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(vec![dir.join("main.rs")], files);
     }
 
-    Ok(())
-}
+    #[test]
+    fn scan_dir_order_test() {
+        let dir = std::env::current_dir().unwrap().join("samples");
+        let scanners = Scanners::new(Matcher::todo().clone());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let mut parallel: Vec<Entry> = vec![];
+        scan_dir(&dir, &scanners, &mut parallel, &[], &mut Stats::new(0)).unwrap();
+
+        let mut streamed: Vec<Entry> = vec![];
+        stream_dir(&dir, &scanners, &[], &mut Stats::new(0), &mut |found| streamed.extend(found)).unwrap();
+
+        assert!(! parallel.is_empty());
+        assert_eq!(streamed, parallel);
+    }
 
     #[test]
-    fn generic_test() {
+    fn closers_test() {
         let str = r#"
-            1
-            2
-            // todo foo
-            /* TODO: foo bar */
-            /*
-
-            * TODO baz
-            TODO baz2
-            TODO baz2 todo
-            <!-- TODO foo2 -->
-            */
+            {# todo@templates twig #}
+            {% if user %} {# todo jinja #}
+            <?php // todo php ?>
+            (* todo ocaml *)
+            """ todo python docstring """
+            /* todo nested */ -->
         "#;
 
         let mut entries: Vec<Entry> = vec![];
-        let mut path = PathBuf::new();
-        path.push("foo.txt");
+        scan_string(str.to_string(), PathBuf::from("foo.txt"), &mut entries);
 
-        scan_string(str.to_string(), path.clone(), &mut entries);
+        let texts: Vec<&str> = entries.iter().map(|entry| entry.text.as_str()).collect();
 
-        assert_eq!(6, entries.len());
+        assert_eq!(vec!["twig", "jinja", "php", "ocaml", "python docstring", "nested"], texts);
+        assert_eq!("foo", strip_closers("foo end ]]", &["]]", "end"]));
+    }
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("foo"),
-            location: Location {
-                file: path.clone(),
-                line: 4,
-            }
-        }, entries[0]);
+    #[test]
+    fn assignee_test() {
+        let str = r#"
+            // TODO(alice): fix this
+            // todo0(bob) urgent
+            // todo@ui(carol) spacing
+            // todo foo(bar)
+            todo!(dave);
+            // todo @@erin fix the layout
+            // todo1 tidy up @@frank
+            // todo@api @@gina
+            // todo mention @@ or a@@b
+        "#;
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("foo bar"),
-            location: Location {
-                file: path.clone(),
-                line: 5,
-            }
-        }, entries[1]);
+        let mut entries: Vec<Entry> = vec![];
+        scan_string(str.to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        let entries: Vec<(&str, &EntryData, Option<&str>)> = entries.iter()
+            .map(|entry| (entry.text.as_str(), &entry.data, entry.assignee.as_deref()))
+            .collect();
+
+        assert_eq!(vec![
+            ("fix this", &EntryData::Generic, Some("alice")),
+            ("urgent", &EntryData::Priority(0), Some("bob")),
+            ("spacing", &EntryData::Category("ui".to_string()), Some("carol")),
+            ("foo(bar)", &EntryData::Generic, None),
+            ("todo!(dave);", &EntryData::Unimplemented, None),
+            ("fix the layout", &EntryData::Generic, Some("erin")),
+            ("tidy up", &EntryData::Priority(1), Some("frank")),
+            ("", &EntryData::Category("api".to_string()), Some("gina")),
+            ("mention @@ or a@@b", &EntryData::Generic, None),
+        ], entries);
+    }
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("baz"),
-            location: Location {
-                file: path.clone(),
-                line: 8,
-            }
-        }, entries[2]);
+    #[test]
+    fn markers_test() {
+        let str = r#"
+            let x = dbg!(a * 2);
+            dbg!(&x);
+            _ => unreachable!("todo: handle v2"),
+            panic!("TODO: check this");
+            panic!("invalid state");
+            DBG!(not a macro);
+            foo(dbg!(x));
+            Some(dbg!(x))
+            bar(&dbg!(x), unreachable!("todo"));
+            let y = (dbg!(x));
+            my_dbg!(x);
+        "#;
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("baz2"),
-            location: Location {
-                file: path.clone(),
-                line: 9,
-            }
-        }, entries[3]);
+        let mut entries: Vec<Entry> = vec![];
+        scan_string(str.to_string(), PathBuf::from("foo.rs"), &mut entries);
+
+        let entries: Vec<(&str, &EntryData, Option<&str>)> = entries.iter()
+            .map(|entry| (entry.text.as_str(), &entry.data, entry.assignee.as_deref()))
+            .collect();
+
+        assert_eq!(vec![
+            ("let x = dbg!(a * 2);", &EntryData::Marker, None),
+            ("dbg!(&x);", &EntryData::Marker, None),
+            ("_ => unreachable!(\"todo: handle v2\"),", &EntryData::Marker, None),
+            ("panic!(\"TODO: check this\");", &EntryData::Marker, None),
+            ("foo(dbg!(x));", &EntryData::Marker, None),
+            ("Some(dbg!(x))", &EntryData::Marker, None),
+            ("bar(&dbg!(x), unreachable!(\"todo\"));", &EntryData::Marker, None),
+            ("let y = (dbg!(x));", &EntryData::Marker, None),
+        ], entries);
+    }
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("baz2 todo"),
-            location: Location {
-                file: path.clone(),
-                line: 10,
-            }
-        }, entries[4]);
+    #[test]
+    fn someday_test() {
+        let str = r#"
+            // todo? rewrite this in a better language
+            // TODO?: dark mode
+            // todo?foo
+        "#;
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("foo2"),
-            location: Location {
-                file: path.clone(),
-                line: 11,
-            }
-        }, entries[5]);
+        let mut entries: Vec<Entry> = vec![];
+        scan_string(str.to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        assert_eq!(2, entries.len());
+        assert_eq!(EntryData::Someday, entries[0].data);
+        assert_eq!("rewrite this in a better language", entries[0].text);
+        assert_eq!(EntryData::Someday, entries[1].data);
+        assert_eq!("dark mode", entries[1].text);
     }
 
     #[test]
@@ -502,68 +1473,19 @@ mod tests {
 
         assert_eq!(7, entries.len());
 
-        assert_eq!(Entry {
-            data: EntryData::Category(String::from("foo")),
-            text: String::from(""),
-            location: Location {
-                file: path.clone(),
-                line: 4,
-            }
-        }, entries[0]);
+        assert_eq!(Entry::new(String::from(""), Location { file: path.clone().into(), line: 4 }, EntryData::Category(String::from("foo"))), entries[0]);
 
-        assert_eq!(Entry {
-            data: EntryData::Category(String::from("bar")),
-            text: String::from("abc def"),
-            location: Location {
-                file: path.clone(),
-                line: 5,
-            }
-        }, entries[1]);
+        assert_eq!(Entry::new(String::from("abc def"), Location { file: path.clone().into(), line: 5 }, EntryData::Category(String::from("bar"))), entries[1]);
 
-        assert_eq!(Entry {
-            data: EntryData::Category(String::from("baz")),
-            text: String::from("x y"),
-            location: Location {
-                file: path.clone(),
-                line: 7,
-            }
-        }, entries[2]);
+        assert_eq!(Entry::new(String::from("x y"), Location { file: path.clone().into(), line: 7 }, EntryData::Category(String::from("baz"))), entries[2]);
 
-        assert_eq!(Entry {
-            data: EntryData::Category(String::from("baz2")),
-            text: String::from("a"),
-            location: Location {
-                file: path.clone(),
-                line: 9,
-            }
-        }, entries[3]);
+        assert_eq!(Entry::new(String::from("a"), Location { file: path.clone().into(), line: 9 }, EntryData::Category(String::from("baz2"))), entries[3]);
 
-        assert_eq!(Entry {
-            data: EntryData::Category(String::from("baz3")),
-            text: String::from(""),
-            location: Location {
-                file: path.clone(),
-                line: 10,
-            }
-        }, entries[4]);
+        assert_eq!(Entry::new(String::from(""), Location { file: path.clone().into(), line: 10 }, EntryData::Category(String::from("baz3"))), entries[4]);
 
-        assert_eq!(Entry {
-            data: EntryData::Category(String::from("baz3")),
-            text: String::from("b"),
-            location: Location {
-                file: path.clone(),
-                line: 11,
-            }
-        }, entries[5]);
+        assert_eq!(Entry::new(String::from("b"), Location { file: path.clone().into(), line: 11 }, EntryData::Category(String::from("baz3"))), entries[5]);
 
-        assert_eq!(Entry {
-            data: EntryData::Category(String::from("baz3")),
-            text: String::from(""),
-            location: Location {
-                file: path.clone(),
-                line: 12,
-            }
-        }, entries[6]);
+        assert_eq!(Entry::new(String::from(""), Location { file: path.clone().into(), line: 12 }, EntryData::Category(String::from("baz3"))), entries[6]);
     }
 
     #[test]
@@ -593,95 +1515,25 @@ mod tests {
 
         assert_eq!(10, entries.len());
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(-1),
-            text: String::from(""),
-            location: Location {
-                file: path.clone(),
-                line: 4,
-            }
-        }, entries[0]);
+        assert_eq!(Entry::new(String::from(""), Location { file: path.clone().into(), line: 4 }, EntryData::Priority(-1)), entries[0]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(-2),
-            text: String::from("abc"),
-            location: Location {
-                file: path.clone(),
-                line: 5,
-            }
-        }, entries[1]);
+        assert_eq!(Entry::new(String::from("abc"), Location { file: path.clone().into(), line: 5 }, EntryData::Priority(-2)), entries[1]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(0),
-            text: String::from("abc def"),
-            location: Location {
-                file: path.clone(),
-                line: 6,
-            }
-        }, entries[2]);
+        assert_eq!(Entry::new(String::from("abc def"), Location { file: path.clone().into(), line: 6 }, EntryData::Priority(0)), entries[2]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(1),
-            text: String::from("foo"),
-            location: Location {
-                file: path.clone(),
-                line: 7,
-            }
-        }, entries[3]);
+        assert_eq!(Entry::new(String::from("foo"), Location { file: path.clone().into(), line: 7 }, EntryData::Priority(1)), entries[3]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(1),
-            text: String::from("x y"),
-            location: Location {
-                file: path.clone(),
-                line: 9,
-            }
-        }, entries[4]);
+        assert_eq!(Entry::new(String::from("x y"), Location { file: path.clone().into(), line: 9 }, EntryData::Priority(1)), entries[4]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(0),
-            text: String::from("bar"),
-            location: Location {
-                file: path.clone(),
-                line: 11,
-            }
-        }, entries[5]);
+        assert_eq!(Entry::new(String::from("bar"), Location { file: path.clone().into(), line: 11 }, EntryData::Priority(0)), entries[5]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(1),
-            text: String::from("a"),
-            location: Location {
-                file: path.clone(),
-                line: 12,
-            }
-        }, entries[6]);
+        assert_eq!(Entry::new(String::from("a"), Location { file: path.clone().into(), line: 12 }, EntryData::Priority(1)), entries[6]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(2),
-            text: String::from(""),
-            location: Location {
-                file: path.clone(),
-                line: 13,
-            }
-        }, entries[7]);
+        assert_eq!(Entry::new(String::from(""), Location { file: path.clone().into(), line: 13 }, EntryData::Priority(2)), entries[7]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(3),
-            text: String::from("b"),
-            location: Location {
-                file: path.clone(),
-                line: 14,
-            }
-        }, entries[8]);
+        assert_eq!(Entry::new(String::from("b"), Location { file: path.clone().into(), line: 14 }, EntryData::Priority(3)), entries[8]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(4),
-            text: String::from("b"),
-            location: Location {
-                file: path.clone(),
-                line: 15,
-            }
-        }, entries[9]);
+        assert_eq!(Entry::new(String::from("b"), Location { file: path.clone().into(), line: 15 }, EntryData::Priority(4)), entries[9]);
     }
 
     #[test]
@@ -692,99 +1544,29 @@ mod tests {
         path.push("samples");
         path.push("1.ts");
 
-        scan_file(path.as_path(), &mut entries).unwrap();
+        assert!(! scan_file(path.as_path(), &Scanners::new(Matcher::todo().clone()), &mut entries).unwrap());
 
         assert_eq!(10, entries.len());
 
-        assert_eq!(Entry {
-            data: EntryData::Category(String::from("types")),
-            text: String::from(""),
-            location: Location {
-                file: path.clone(),
-                line: 1,
-            }
-        }, entries[0]);
+        assert_eq!(Entry::new(String::from(""), Location { file: path.clone().into(), line: 1 }, EntryData::Category(String::from("types"))), entries[0]);
 
-        assert_eq!(Entry {
-            data: EntryData::Category(String::from("types")),
-            text: String::from("add types"),
-            location: Location {
-                file: path.clone(),
-                line: 5,
-            }
-        }, entries[1]);
+        assert_eq!(Entry::new(String::from("add types"), Location { file: path.clone().into(), line: 5 }, EntryData::Category(String::from("types"))), entries[1]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(-2),
-            text: String::from(""),
-            location: Location {
-                file: path.clone(),
-                line: 10,
-            }
-        }, entries[2]);
+        assert_eq!(Entry::new(String::from(""), Location { file: path.clone().into(), line: 10 }, EntryData::Priority(-2)), entries[2]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(-1),
-            text: String::from("add return typehint"),
-            location: Location {
-                file: path.clone(),
-                line: 14,
-            }
-        }, entries[3]);
+        assert_eq!(Entry::new(String::from("add return typehint"), Location { file: path.clone().into(), line: 14 }, EntryData::Priority(-1)), entries[3]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(0),
-            text: String::from("add name typehint"),
-            location: Location {
-                file: path.clone(),
-                line: 19,
-            }
-        }, entries[4]);
+        assert_eq!(Entry::new(String::from("add name typehint"), Location { file: path.clone().into(), line: 19 }, EntryData::Priority(0)), entries[4]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(1),
-            text: String::from("add return typehint"),
-            location: Location {
-                file: path.clone(),
-                line: 23,
-            }
-        }, entries[5]);
+        assert_eq!(Entry::new(String::from("add return typehint"), Location { file: path.clone().into(), line: 23 }, EntryData::Priority(1)), entries[5]);
 
-        assert_eq!(Entry {
-            data: EntryData::Priority(2),
-            text: String::from("add return typehint"),
-            location: Location {
-                file: path.clone(),
-                line: 27,
-            }
-        }, entries[6]);
+        assert_eq!(Entry::new(String::from("add return typehint"), Location { file: path.clone().into(), line: 27 }, EntryData::Priority(2)), entries[6]);
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from(""),
-            location: Location {
-                file: path.clone(),
-                line: 31,
-            }
-        }, entries[7]);
+        assert_eq!(Entry::new(String::from(""), Location { file: path.clone().into(), line: 31 }, EntryData::Generic), entries[7]);
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("generic todo 2"),
-            location: Location {
-                file: path.clone(),
-                line: 33,
-            }
-        }, entries[8]);
+        assert_eq!(Entry::new(String::from("generic todo 2"), Location { file: path.clone().into(), line: 33 }, EntryData::Generic), entries[8]);
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("generic todo 3"),
-            location: Location {
-                file: path.clone(),
-                line: 34,
-            }
-        }, entries[9]);
+        assert_eq!(Entry::new(String::from("generic todo 3"), Location { file: path.clone().into(), line: 34 }, EntryData::Generic), entries[9]);
     }
 
     #[test]
@@ -795,45 +1577,23 @@ mod tests {
         path.push("samples");
         path.push("2.rs");
 
-        scan_file(path.as_path(), &mut entries).unwrap();
+        scan_file(path.as_path(), &Scanners::new(Matcher::todo().clone()), &mut entries).unwrap();
 
-        assert_eq!(4, entries.len());
+        assert_eq!(5, entries.len());
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("todo!(\"generic\");"),
-            location: Location {
-                file: path.clone(),
-                line: 3,
-            }
-        }, entries[0]);
+        assert_eq!(Entry::new(String::from("todo!(\"generic\");"), Location { file: path.clone().into(), line: 3 }, EntryData::Unimplemented), entries[0]);
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("todo!();"),
-            location: Location {
-                file: path.clone(),
-                line: 4,
-            }
-        }, entries[1]);
+        assert_eq!(Entry::new(String::from("todo!();"), Location { file: path.clone().into(), line: 4 }, EntryData::Unimplemented), entries[1]);
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("todo!(\"@foo not category\");"),
-            location: Location {
-                file: path.clone(),
-                line: 5,
-            }
-        }, entries[2]);
+        assert_eq!(Entry::new(
+            String::from("todo!(\"@foo not category\");"),
+            Location { file: path.clone().into(), line: 5 },
+            EntryData::Unimplemented,
+        ), entries[2]);
 
-        assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("todo!(\"00 not priority\");"),
-            location: Location {
-                file: path.clone(),
-                line: 6,
-            }
-        }, entries[3]);
+        assert_eq!(Entry::new(String::from("todo!(\"00 not priority\");"), Location { file: path.clone().into(), line: 6 }, EntryData::Unimplemented), entries[3]);
+
+        assert_eq!(Entry::new(String::from("unimplemented!(\"@foo\");"), Location { file: path.clone().into(), line: 7 }, EntryData::Unimplemented), entries[4]);
     }
 
     #[test]
@@ -849,78 +1609,232 @@ mod tests {
         assert_eq!(8, entries.len());
 
         assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("generic foo"),
-            location: Location {
-                file: path.clone(),
-                line: 1,
-            }
+            source: SourceKind::TodoFile,
+            ..Entry::new(String::from("generic foo"), Location { file: path.clone().into(), line: 1 }, EntryData::Generic)
         }, entries[0]);
 
         assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("generic bar"),
-            location: Location {
-                file: path.clone(),
-                line: 2,
-            }
+            source: SourceKind::TodoFile,
+            ..Entry::new(String::from("generic bar"), Location { file: path.clone().into(), line: 2 }, EntryData::Generic)
         }, entries[1]);
 
         assert_eq!(Entry {
-            data: EntryData::Priority(-1),
-            text: String::from("priority bar"),
-            location: Location {
-                file: path.clone(),
-                line: 3,
-            }
+            source: SourceKind::TodoFile,
+            ..Entry::new(String::from("priority bar"), Location { file: path.clone().into(), line: 3 }, EntryData::Priority(-1))
         }, entries[2]);
 
         assert_eq!(Entry {
-            data: EntryData::Priority(0),
-            text: String::from("a"),
-            location: Location {
-                file: path.clone(),
-                line: 6,
-            }
+            source: SourceKind::TodoFile,
+            ..Entry::new(String::from("a"), Location { file: path.clone().into(), line: 6 }, EntryData::Priority(0))
         }, entries[3]);
 
         assert_eq!(Entry {
-            data: EntryData::Category(String::from("High priority")),
-            text: String::from("foo"),
-            location: Location {
-                file: path.clone(),
-                line: 7,
-            }
+            source: SourceKind::TodoFile,
+            ..Entry::new(
+                String::from("foo"),
+                Location { file: path.clone().into(), line: 7 },
+                EntryData::Category(String::from("High priority")),
+            )
         }, entries[4]);
 
         assert_eq!(Entry {
-            data: EntryData::Category(String::from("High priority")),
-            text: String::from("bar"),
-            location: Location {
-                file: path.clone(),
-                line: 8,
-            }
+            source: SourceKind::TodoFile,
+            ..Entry::new(
+                String::from("bar"),
+                Location { file: path.clone().into(), line: 8 },
+                EntryData::Category(String::from("High priority")),
+            )
         }, entries[5]);
 
         assert_eq!(Entry {
-            data: EntryData::Category(String::from("Responsivity")),
-            text: String::from("abc"),
-            location: Location {
-                file: path.clone(),
-                line: 11,
-            }
+            source: SourceKind::TodoFile,
+            ..Entry::new(
+                String::from("abc"),
+                Location { file: path.clone().into(), line: 11 },
+                EntryData::Category(String::from("Responsivity")),
+            )
         }, entries[6]);
 
         assert_eq!(Entry {
-            data: EntryData::Category(String::from("Responsivity")),
-            text: String::from("def"),
-            location: Location {
-                file: path.clone(),
-                line: 12,
-            }
+            source: SourceKind::TodoFile,
+            ..Entry::new(
+                String::from("def"),
+                Location { file: path.clone().into(), line: 12 },
+                EntryData::Category(String::from("Responsivity")),
+            )
         }, entries[7]);
     }
 
+    #[test]
+    fn todo_front_matter_test() {
+        let str = "---\ncategory: Backlog\norder:\n  - todo0\n  - Backlog\n---\n- foo\n- todo0 bar\n\n## Bugs\n- baz\n";
+
+        let mut entries: Vec<Entry> = vec![];
+        scan_todo_string(str, Path::new("todo.md"), &mut entries);
+
+        let entries: Vec<(usize, &EntryData, &str)> = entries.iter()
+            .map(|entry| (entry.location.line, &entry.data, entry.text.as_str()))
+            .collect();
+
+        assert_eq!(vec![
+            (7, &EntryData::Category("Backlog".to_string()), "foo"),
+            (8, &EntryData::Priority(0), "bar"),
+            (11, &EntryData::Category("Bugs".to_string()), "baz"),
+        ], entries);
+    }
+
+    #[test]
+    fn crlf_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo a \r\n// todo@foo b\r\n".to_string(), PathBuf::from("foo.rs"), &mut entries);
+        scan_todo_string("- generic \r\n## Bugs \r\n- [ ] c\t\r\n- todo0 d\r\n\r\n## Bugs\r\n- e\r\n", Path::new("todo.md"), &mut entries);
+        scan_readme_string("# App\r\n## TODO: \r\n- f \r\n- todo1 g\r\n", Path::new("README.md"), &mut entries);
+
+        let entries: Vec<(&EntryData, &str)> = entries.iter()
+            .map(|entry| (&entry.data, entry.text.as_str()))
+            .collect();
+
+        assert_eq!(vec![
+            (&EntryData::Generic, "a"),
+            (&EntryData::Category("foo".to_string()), "b"),
+            (&EntryData::Generic, "generic"),
+            (&EntryData::Category("Bugs".to_string()), "c"),
+            (&EntryData::Priority(0), "d"),
+            (&EntryData::Category("Bugs".to_string()), "e"),
+            (&EntryData::Generic, "f"),
+            (&EntryData::Priority(1), "g"),
+        ], entries);
+    }
+
+    #[test]
+    fn todo_subheadings_test() {
+        let str = "## Backend\n- a\n### Auth\n- b\n#### Tokens\n- c\n### Billing\n- d\n## Frontend\n- e\n";
+
+        let mut entries: Vec<Entry> = vec![];
+        scan_todo_string(str, Path::new("todo.md"), &mut entries);
+
+        let categories: Vec<(&EntryData, &str)> = entries.iter()
+            .map(|entry| (&entry.data, entry.text.as_str()))
+            .collect();
+
+        assert_eq!(vec![
+            (&EntryData::Category("Backend".to_string()), "a"),
+            (&EntryData::Category("Backend/Auth".to_string()), "b"),
+            (&EntryData::Category("Backend/Auth/Tokens".to_string()), "c"),
+            (&EntryData::Category("Backend/Billing".to_string()), "d"),
+            (&EntryData::Category("Frontend".to_string()), "e"),
+        ], categories);
+    }
+
+    #[test]
+    fn todo_descriptions_test() {
+        let str = "---\ncategory: Backlog\n---\nNot a description\n\n## Backend\n\nServer-side work,\nsee the wiki.\n\nNot a description\n- a\n### Auth\nLogin and tokens\n- b\nNot a description\n## Frontend\n- c\n";
+
+        assert_eq!(BTreeMap::from([
+            ("Backend".to_string(), "Server-side work, see the wiki.".to_string()),
+            ("Backend/Auth".to_string(), "Login and tokens".to_string()),
+        ]), todo_descriptions(str));
+    }
+
+    #[test]
+    fn org_test() {
+        let str = "* TODO [#A] Fix the build\n** TODO Polish the UI  :ui:design:\n*** DONE Old\n* Notes\nTODO write more\n*bold* todo no\n** FIXME crash\n";
+
+        let mut entries: Vec<Entry> = vec![];
+        scan_org_string(str, Path::new("notes.org"), &Matcher::new(&["fixme"]).unwrap(), &mut entries);
+
+        let entries: Vec<(usize, &EntryData, &str)> = entries.iter()
+            .map(|entry| (entry.location.line, &entry.data, entry.text.as_str()))
+            .collect();
+
+        assert_eq!(vec![
+            (1, &EntryData::Priority(0), "Fix the build"),
+            (2, &EntryData::Category("ui".to_string()), "Polish the UI"),
+            (7, &EntryData::Generic, "crash"),
+            (5, &EntryData::Generic, "write more"),
+            (6, &EntryData::Generic, "no"),
+        ], entries);
+    }
+
+    #[test]
+    fn notebook_test() {
+        let str = r##"{
+ "cells": [
+  {
+   "cell_type": "code",
+   "metadata": {"tags": ["todo metadata"]},
+   "outputs": [{"text": ["todo output\n"]}],
+   "source": [
+    "import pandas\n",
+    "# TODO: \"quoted\", with a comma,\n",
+    "df = load()  # todo0 cache this"
+   ]
+  },
+  {
+   "cell_type": "markdown",
+   "source": "- todo@docs explain the model"
+  }
+ ]
+}
+"##;
+
+        let mut entries: Vec<Entry> = vec![];
+        scan_notebook_string(str, Path::new("analysis.ipynb"), Matcher::todo(), &mut entries);
+
+        let entries: Vec<(usize, &EntryData, &str)> = entries.iter()
+            .map(|entry| (entry.location.line, &entry.data, entry.text.as_str()))
+            .collect();
+
+        assert_eq!(vec![
+            (9, &EntryData::Generic, "\"quoted\", with a comma,"),
+            (10, &EntryData::Priority(0), "cache this"),
+            (15, &EntryData::Category("docs".to_string()), "explain the model"),
+        ], entries);
+    }
+
+    #[test]
+    fn patterns_test() {
+        let matcher = Matcher::todo().clone().with_patterns(&[
+            r"NOCOMMIT:\s*(?P<text>.*)",
+            r"@(?P<category>\w+)!(?P<priority>\d*)\s+(?P<text>.*)",
+        ]).unwrap();
+
+        let mut entries: Vec<Entry> = vec![];
+        scan_string_with("// NOCOMMIT: remove the debug log */\n// todo0 NOCOMMIT: both\n# @ops!00 rotate keys\n# @ui! spacing\n# @ui!x no match\n".to_string(), PathBuf::from("foo.rs"), &matcher, &mut entries);
+
+        let entries: Vec<(usize, &EntryData, &str)> = entries.iter()
+            .map(|entry| (entry.location.line, &entry.data, entry.text.as_str()))
+            .collect();
+
+        assert_eq!(vec![
+            (2, &EntryData::Priority(0), "NOCOMMIT: both"),
+            (1, &EntryData::Generic, "remove the debug log"),
+            (3, &EntryData::Priority(-1), "rotate keys"),
+            (4, &EntryData::Category("ui".to_string()), "spacing"),
+        ], entries);
+    }
+
+    #[test]
+    fn todo_list_styles_test() {
+        let str = "* foo\n+ bar\n1. baz\n2) todo1 qux\n* [ ] quux\n  - [ ] nested\n---\n**bold**\n10 items\n- [x] done\n* [X] todo0 also done\n";
+
+        let mut entries: Vec<Entry> = vec![];
+        scan_todo_string(str, Path::new("todo.md"), &mut entries);
+
+        let entries: Vec<(usize, &EntryData, &str)> = entries.iter()
+            .map(|entry| (entry.location.line, &entry.data, entry.text.as_str()))
+            .collect();
+
+        assert_eq!(vec![
+            (1, &EntryData::Generic, "foo"),
+            (2, &EntryData::Generic, "bar"),
+            (3, &EntryData::Generic, "baz"),
+            (4, &EntryData::Priority(1), "qux"),
+            (5, &EntryData::Generic, "quux"),
+            (6, &EntryData::Generic, "nested"),
+        ], entries);
+    }
+
     #[test]
     fn readme_file_test() {
         let mut entries: Vec<Entry> = vec![];
@@ -934,39 +1848,23 @@ mod tests {
         assert_eq!(4, entries.len());
 
         assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("abc"),
-            location: Location {
-                file: path.clone(),
-                line: 19,
-            }
+            source: SourceKind::Readme,
+            ..Entry::new(String::from("abc"), Location { file: path.clone().into(), line: 19 }, EntryData::Generic)
         }, entries[0]);
 
         assert_eq!(Entry {
-            data: EntryData::Priority(0),
-            text: String::from("def"),
-            location: Location {
-                file: path.clone(),
-                line: 20,
-            }
+            source: SourceKind::Readme,
+            ..Entry::new(String::from("def"), Location { file: path.clone().into(), line: 20 }, EntryData::Priority(0))
         }, entries[1]);
 
         assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("bar"),
-            location: Location {
-                file: path.clone(),
-                line: 21,
-            }
+            source: SourceKind::Readme,
+            ..Entry::new(String::from("bar"), Location { file: path.clone().into(), line: 21 }, EntryData::Generic)
         }, entries[2]);
 
         assert_eq!(Entry {
-            data: EntryData::Generic,
-            text: String::from("baz"),
-            location: Location {
-                file: path.clone(),
-                line: 22,
-            }
+            source: SourceKind::Readme,
+            ..Entry::new(String::from("baz"), Location { file: path.clone().into(), line: 22 }, EntryData::Generic)
         }, entries[3]);
     }
 }
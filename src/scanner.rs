@@ -0,0 +1,154 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::entries::{Entry, make_relative, postprocess};
+use crate::matcher::Matcher;
+use crate::paths::{canonicalize, resolve};
+use crate::scan::{self, Stats, TEST_PATHS, resolve_exclude, scan_file};
+use crate::scanners::{Readme, Scanners, TodoFile};
+
+/// What [`Scanner::scan_path`] scans, like the CLI's options
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Excluded names (at any depth) or paths relative to the scanned directory, like `--exclude`
+    pub exclude: Vec<String>,
+    /// Also exclude conventional test locations, like `--skip-tests`
+    pub skip_tests: bool,
+    /// The todo.md files, relative to the scanned directory
+    pub todo_files: Vec<PathBuf>,
+    /// The readme, relative to the scanned directory
+    pub readme: PathBuf,
+    /// Make the paths of the entries relative to the scanned directory, like in the CLI's output
+    pub relative_paths: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> ScanOptions {
+        ScanOptions {
+            exclude: vec!["node_modules".to_string(), "vendor".to_string()],
+            skip_tests: false,
+            todo_files: vec![PathBuf::from("todo.md")],
+            readme: PathBuf::from("README.md"),
+            relative_paths: false,
+        }
+    }
+}
+
+/// Scans files and directories like the `todos` binary does, for embedding it in other tools:
+///
+/// ```no_run
+/// use todos::{ScanOptions, Scanner};
+///
+/// let entries = Scanner::new(ScanOptions::default()).scan_path("src".as_ref()).unwrap();
+/// ```
+pub struct Scanner {
+    options: ScanOptions,
+    config: Config,
+    matcher: Matcher,
+}
+
+impl Scanner {
+    /// A scanner using the default settings
+    pub fn new(options: ScanOptions) -> Scanner {
+        Scanner { options, config: Config::default(), matcher: Matcher::todo().clone() }
+    }
+
+    /// A scanner using the settings of a todo.toml, e.g. from [`Config::load`]. Fails if its
+    /// keywords are invalid.
+    pub fn with_config(options: ScanOptions, config: Config) -> Result<Scanner, String> {
+        let matcher = config.scan.matcher()?;
+
+        Ok(Scanner { options, config, matcher })
+    }
+
+    /// The entries in `path`, a file or a directory. For directories, the todo.md files and the
+    /// readme are scanned as well, unless they're turned off in `[sources]`.
+    pub fn scan_path(&self, path: &Path) -> io::Result<Vec<Entry>> {
+        let path = canonicalize(path)?;
+        let dir = match path.is_dir() {
+            true => path.clone(),
+            false => path.parent().map_or_else(|| path.clone(), Path::to_path_buf),
+        };
+
+        let mut scanners = Scanners::new(self.matcher.clone());
+        let mut sources: Vec<PathBuf> = vec![];
+
+        for plugin in &self.config.plugins {
+            scanners.register(plugin.clone());
+        }
+
+        if self.config.sources.todo_file {
+            for todo_file in &self.options.todo_files {
+                scanners.register(TodoFile { path: resolve(&dir, todo_file) });
+                sources.push(resolve(&dir, todo_file));
+            }
+        }
+
+        if self.config.sources.readme {
+            scanners.register(Readme { path: resolve(&dir, &self.options.readme) });
+            sources.push(resolve(&dir, &self.options.readme));
+        }
+
+        let test_paths: &[&str] = if self.options.skip_tests { &TEST_PATHS } else { &[] };
+
+        let mut excludes: Vec<PathBuf> = self.options.exclude.iter().map(String::as_str)
+            .chain(self.config.scan.skipped_files())
+            .chain(test_paths.iter().copied())
+            .map(|exclude| resolve_exclude(&dir, exclude))
+            .collect();
+
+        // Scanned as markdown, not as code
+        excludes.extend(sources.iter().filter(|source| source.exists()).cloned());
+
+        let mut entries: Vec<Entry> = vec![];
+
+        if path.is_dir() {
+            for source in &sources {
+                if source.exists() {
+                    scan_file(source, &scanners, &mut entries)?;
+                }
+            }
+        }
+
+        scan::scan_path(&path, &scanners, &mut entries, &excludes, &mut Stats::new(0))?;
+        postprocess(&mut entries, &self.config, &dir, &sources);
+
+        if self.options.relative_paths {
+            make_relative(&mut entries, &dir);
+        }
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+    use std::fs;
+    use crate::entries::EntryData;
+
+    #[test]
+    fn scan_path_test() {
+        let dir = TempDir::new("scanner");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("tests")).unwrap();
+
+        fs::write(dir.join("todo.md"), "## ui\n- spacing\n").unwrap();
+        fs::write(dir.join("src").join("main.rs"), "// todo0 foo */\n").unwrap();
+        fs::write(dir.join("tests").join("main.rs"), "// todo bar\n").unwrap();
+
+        let options = ScanOptions { skip_tests: true, relative_paths: true, ..ScanOptions::default() };
+        let entries = Scanner::new(options.clone()).scan_path(&dir).unwrap();
+
+        assert_eq!(vec![
+            (PathBuf::from("todo.md"), 2, EntryData::Category("ui".to_string()), "spacing"),
+            (PathBuf::from("src").join("main.rs"), 1, EntryData::Priority(0), "foo"),
+        ], entries.iter().map(|entry| (entry.location.file.to_path_buf(), entry.location.line, entry.data.clone(), entry.text.as_str())).collect::<Vec<_>>());
+
+        let config = Config::parse("[sources]\ntodo_file = false\n").unwrap();
+        let entries = Scanner::with_config(ScanOptions { skip_tests: false, ..options }, config).unwrap().scan_path(&dir).unwrap();
+        assert_eq!(vec!["foo", "bar"], entries.iter().map(|entry| entry.text.as_str()).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,314 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use glob::Pattern;
+use serde::Deserialize;
+
+use crate::entries::{Entry, EntryData, Location};
+use crate::matcher::Matcher;
+use crate::scan::{scan_notebook_string, scan_org_string, scan_readme_string, scan_string_with, scan_todo_string};
+
+/// Reads the entries out of one kind of file. Scanners are registered in [`Scanners`], which
+/// picks the scanner for each file, so new formats can be supported without changing the walk.
+pub trait SourceScanner: Send + Sync {
+    /// Whether this scanner reads `path`, e.g. by its file name or extension
+    fn handles(&self, path: &Path) -> bool;
+
+    /// Add the entries in `content`, read from `path`, to `entries`. `matcher` finds the
+    /// configured keywords.
+    fn scan(&self, content: String, path: &Path, matcher: &Matcher, entries: &mut Vec<Entry>);
+}
+
+/// Whether `path` has the extension `extension`, in any ASCII case
+pub fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// TODO comments in code, and any other text. Used for the files no other scanner handles.
+pub struct Code;
+
+impl SourceScanner for Code {
+    fn handles(&self, _path: &Path) -> bool {
+        true
+    }
+
+    fn scan(&self, content: String, path: &Path, matcher: &Matcher, entries: &mut Vec<Entry>) {
+        scan_string_with(content, path.to_path_buf(), matcher, entries);
+    }
+}
+
+/// The todo.md list at `path`, see [`scan_todo_string`]
+pub struct TodoFile {
+    pub path: PathBuf,
+}
+
+impl SourceScanner for TodoFile {
+    fn handles(&self, path: &Path) -> bool {
+        path == self.path
+    }
+
+    fn scan(&self, content: String, path: &Path, _matcher: &Matcher, entries: &mut Vec<Entry>) {
+        scan_todo_string(&content, path, entries);
+    }
+}
+
+/// The TODO section of the readme at `path`, see [`scan_readme_string`]
+pub struct Readme {
+    pub path: PathBuf,
+}
+
+impl SourceScanner for Readme {
+    fn handles(&self, path: &Path) -> bool {
+        path == self.path
+    }
+
+    fn scan(&self, content: String, path: &Path, _matcher: &Matcher, entries: &mut Vec<Entry>) {
+        scan_readme_string(&content, path, entries);
+    }
+}
+
+/// Org-mode files (`.org`), see [`scan_org_string`]
+pub struct OrgMode;
+
+impl SourceScanner for OrgMode {
+    fn handles(&self, path: &Path) -> bool {
+        has_extension(path, "org")
+    }
+
+    fn scan(&self, content: String, path: &Path, matcher: &Matcher, entries: &mut Vec<Entry>) {
+        scan_org_string(&content, path, matcher, entries);
+    }
+}
+
+/// Jupyter notebooks (`.ipynb`), see [`scan_notebook_string`]
+pub struct Notebook;
+
+impl SourceScanner for Notebook {
+    fn handles(&self, path: &Path) -> bool {
+        has_extension(path, "ipynb")
+    }
+
+    fn scan(&self, content: String, path: &Path, matcher: &Matcher, entries: &mut Vec<Entry>) {
+        scan_notebook_string(&content, path, matcher, entries);
+    }
+}
+
+/// An external command that scans the files matching `files` (file name patterns, e.g.
+/// `*.drawio`), configured under `[[plugins]]` in todo.toml. The command gets the content on
+/// stdin and the path in the `TODOS_FILE` environment variable, and prints the entries as a
+/// JSON array, e.g. `[{"line": 3, "text": "foo", "priority": 0}]`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Plugin {
+    /// The program and its arguments
+    pub command: Vec<String>,
+    pub files: Vec<String>,
+}
+
+/// An entry printed by a plugin. Entries with neither a priority nor a category are generic,
+/// or someday entries with `someday`.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PluginEntry {
+    pub line: usize,
+    pub text: String,
+    #[serde(default)]
+    pub priority: Option<isize>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub someday: bool,
+    #[serde(default)]
+    pub assignee: Option<String>,
+}
+
+impl Plugin {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.command.is_empty() {
+            return Err("the command is empty".to_string());
+        }
+
+        for pattern in &self.files {
+            Pattern::new(pattern).map_err(|err| format!("invalid file pattern {:?}: {}", pattern, err))?;
+        }
+
+        Ok(())
+    }
+
+    /// Run the command on `content`, returning the entries it printed
+    pub fn run(&self, content: String, path: &Path) -> Result<Vec<PluginEntry>, String> {
+        let mut child = Command::new(&self.command[0])
+            .args(&self.command[1..])
+            .env("TODOS_FILE", path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| err.to_string())?;
+
+        // Written from another thread, so that a command printing entries before it has read
+        // all of its input can't block on a full pipe
+        let mut stdin = child.stdin.take().unwrap();
+        let writer = std::thread::spawn(move || stdin.write_all(content.as_bytes()));
+
+        let output = child.wait_with_output().map_err(|err| err.to_string())?;
+
+        // The command doesn't have to read its input
+        let _ = writer.join();
+
+        if ! output.status.success() {
+            return Err(format!("exited with {}", output.status));
+        }
+
+        serde_json::from_slice(&output.stdout).map_err(|err| format!("invalid output: {}", err))
+    }
+}
+
+impl SourceScanner for Plugin {
+    fn handles(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+
+        // Validated when the config is loaded
+        self.files.iter().any(|pattern| Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(name)))
+    }
+
+    fn scan(&self, content: String, path: &Path, _matcher: &Matcher, entries: &mut Vec<Entry>) {
+        let found = match self.run(content, path) {
+            Ok(found) => found,
+            Err(err) => {
+                eprintln!("Plugin {} failed to scan {}: {}", self.command.join(" "), path.to_string_lossy(), err);
+                return;
+            },
+        };
+
+        let file: Arc<Path> = path.into();
+
+        entries.extend(found.into_iter().map(|entry| Entry {
+            assignee: entry.assignee,
+            ..Entry::new(
+                entry.text,
+                Location { file: file.clone(), line: entry.line },
+                match (entry.priority, entry.category, entry.someday) {
+                    (Some(priority), _, _) => EntryData::Priority(priority),
+                    (None, Some(category), _) => EntryData::Category(category),
+                    (None, None, true) => EntryData::Someday,
+                    (None, None, false) => EntryData::Generic,
+                },
+            )
+        }));
+    }
+}
+
+/// The registered scanners, along with the keyword matcher they use
+pub struct Scanners {
+    matcher: Matcher,
+    scanners: Vec<Box<dyn SourceScanner>>,
+}
+
+impl Scanners {
+    /// The built-in scanners for org-mode files, notebooks, and code. todo.md and the readme
+    /// are configurable paths, so their scanners are registered by the caller.
+    pub fn new(matcher: Matcher) -> Scanners {
+        let mut scanners = Scanners { matcher, scanners: vec![] };
+
+        scanners.register(Code);
+        scanners.register(OrgMode);
+        scanners.register(Notebook);
+
+        scanners
+    }
+
+    /// Use `scanner` for the files it handles, instead of the scanners registered before it
+    pub fn register(&mut self, scanner: impl SourceScanner + 'static) {
+        self.scanners.push(Box::new(scanner));
+    }
+
+    pub fn matcher(&self) -> &Matcher {
+        &self.matcher
+    }
+
+    /// The scanner used for `path`: the last registered one that handles it
+    pub fn scanner(&self, path: &Path) -> &dyn SourceScanner {
+        self.scanners.iter().rev()
+            .find(|scanner| scanner.handles(path))
+            .map_or(&Code, |scanner| scanner.as_ref())
+    }
+
+    /// Add the entries in `content`, read from `path`, to `entries`
+    pub fn scan(&self, content: String, path: &Path, entries: &mut Vec<Entry>) {
+        self.scanner(path).scan(content, path, &self.matcher, entries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entries::EntryData;
+
+    struct Csv;
+
+    impl SourceScanner for Csv {
+        fn handles(&self, path: &Path) -> bool {
+            has_extension(path, "csv")
+        }
+
+        fn scan(&self, content: String, path: &Path, _matcher: &Matcher, entries: &mut Vec<Entry>) {
+            for (line, row) in content.lines().enumerate() {
+                if let Some(text) = row.strip_prefix("todo,") {
+                    scan_string_with(format!("todo {}", text), path.to_path_buf(), Matcher::todo(), entries);
+                    entries.last_mut().unwrap().location.line = line + 1;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scanners_test() {
+        let mut scanners = Scanners::new(Matcher::todo().clone());
+        scanners.register(TodoFile { path: PathBuf::from("/repo/todo.md") });
+        scanners.register(Csv);
+
+        let mut entries: Vec<Entry> = vec![];
+        scanners.scan("- foo\n".to_string(), Path::new("/repo/todo.md"), &mut entries);
+        scanners.scan("// todo bar\n".to_string(), Path::new("/repo/docs/todo.md"), &mut entries);
+        scanners.scan("id,text\ntodo,baz\n".to_string(), Path::new("/repo/tasks.CSV"), &mut entries);
+
+        let entries: Vec<(&EntryData, &str, usize)> = entries.iter()
+            .map(|entry| (&entry.data, entry.text.as_str(), entry.location.line))
+            .collect();
+
+        assert_eq!(vec![
+            (&EntryData::Generic, "foo", 1),
+            (&EntryData::Generic, "bar", 1),
+            (&EntryData::Generic, "baz", 2),
+        ], entries);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn plugin_test() {
+        let script = r#"grep -q 'fix me' && echo "[{\"line\": 2, \"text\": \"$TODOS_FILE\", \"priority\": 1}, {\"line\": 4, \"text\": \"b\", \"category\": \"ui\"}]""#;
+        let plugin = Plugin { command: vec!["sh".to_string(), "-c".to_string(), script.to_string()], files: vec!["*.drawio".to_string()] };
+
+        assert!(plugin.handles(Path::new("/repo/docs/Diagram.drawio")));
+        assert!(! plugin.handles(Path::new("/repo/drawio/a.rs")));
+
+        let mut entries: Vec<Entry> = vec![];
+        plugin.scan("a\nfix me\n".to_string(), Path::new("a.drawio"), Matcher::todo(), &mut entries);
+
+        let entries: Vec<(&EntryData, &str, usize)> = entries.iter()
+            .map(|entry| (&entry.data, entry.text.as_str(), entry.location.line))
+            .collect();
+
+        assert_eq!(vec![
+            (&EntryData::Priority(1), "a.drawio", 2),
+            (&EntryData::Category("ui".to_string()), "b", 4),
+        ], entries);
+
+        assert!(plugin.run("nothing to fix".to_string(), Path::new("a.drawio")).is_err());
+        assert!(Plugin { command: vec![], files: vec![] }.validate().is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use schemars::{JsonSchema, Schema, schema_for};
+use serde::{Deserialize, Serialize};
+
+use crate::authors::AuthorsReport;
+use crate::commits::CommitsReport;
+use crate::diff::{Diff, ResolvedReport};
+use crate::due::DueReport;
+use crate::lint::LintReport;
+use crate::org::OrgReport;
+use crate::suggest::SuggestReport;
+use crate::rename::RenameReport;
+use crate::render::{CategoriesReport, JsonReport};
+use crate::thresholds::Summary;
+
+/// The version of the JSON output formats.
+///
+/// Within a version, fields are only ever added, so consumers should ignore the fields they
+/// don't know. Removing or renaming a field, or changing its type or meaning, bumps the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON output, tagged with the schema version
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Versioned<T> {
+    /// Missing in output from before the schema was versioned
+    #[serde(default)]
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+/// The commands and formats producing JSON
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Output {
+    /// `--format json`
+    Scan,
+    Diff,
+    Resolved,
+    Categories,
+    Authors,
+    ScanLog,
+    Due,
+    Lint,
+    OrgReport,
+    Suggest,
+    RenameCategory,
+    /// `--summary-file`
+    Summary,
+}
+
+/// Print `data` as pretty JSON, with the current schema version
+pub fn print_json<T: Serialize>(data: T) {
+    println!("{}", versioned_json(data));
+}
+
+/// `data` as pretty JSON, with the current schema version
+pub fn versioned_json<T: Serialize>(data: T) -> String {
+    serde_json::to_string_pretty(&Versioned { schema_version: SCHEMA_VERSION, data }).unwrap()
+}
+
+/// The JSON Schema of an output
+pub fn schema(output: Output) -> Schema {
+    match output {
+        Output::Scan => schema_for!(Versioned<JsonReport>),
+        Output::Diff => schema_for!(Versioned<Diff>),
+        Output::Resolved => schema_for!(Versioned<ResolvedReport>),
+        Output::Categories => schema_for!(Versioned<CategoriesReport>),
+        Output::Authors => schema_for!(Versioned<AuthorsReport>),
+        Output::ScanLog => schema_for!(Versioned<CommitsReport>),
+        Output::Due => schema_for!(Versioned<DueReport>),
+        Output::Lint => schema_for!(Versioned<LintReport>),
+        Output::OrgReport => schema_for!(Versioned<OrgReport>),
+        Output::Suggest => schema_for!(Versioned<SuggestReport>),
+        Output::RenameCategory => schema_for!(Versioned<RenameReport>),
+        Output::Summary => schema_for!(Versioned<Summary>),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn versioned_test() {
+        let json = serde_json::to_value(Versioned { schema_version: SCHEMA_VERSION, data: JsonReport { entries: vec![], descriptions: BTreeMap::new(), owners: BTreeMap::new() } }).unwrap();
+        assert_eq!(serde_json::json!({ "schema_version": SCHEMA_VERSION, "entries": [] }), json);
+
+        // Scans saved before the schema was versioned can still be read
+        let report: Versioned<JsonReport> = serde_json::from_str(r#"{ "entries": [] }"#).unwrap();
+        assert_eq!(0, report.schema_version);
+
+        let schema = serde_json::to_value(schema(Output::Scan)).unwrap();
+        assert!(schema["required"].as_array().unwrap().contains(&serde_json::json!("entries")));
+    }
+}
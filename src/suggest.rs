@@ -0,0 +1,291 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use termcolor::{BufferedStandardStream, Color, ColorChoice};
+
+use crate::config::{Categories, Config};
+use crate::entries::{Entry, EntryData};
+use crate::matcher::Matcher;
+use crate::paths::relative;
+use crate::render::write_ansi;
+use crate::scan::read_text;
+use crate::schema::print_json;
+
+/// How similar (0 to 1) the text of an entry has to be to a categorized entry's text for its
+/// category to be suggested
+const MIN_SIMILARITY: f64 = 0.3;
+
+/// Why a category is suggested
+#[derive(Serialize, JsonSchema, Debug, PartialEq, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Reason {
+    /// The entry's file matches one of `[categories] paths`
+    Path { pattern: String },
+    /// The entry's text is similar to the text of an entry with the category
+    Similar { text: String },
+}
+
+/// A category proposed for an entry without one, and the rewritten source line adding it
+#[derive(Serialize, JsonSchema, Debug, PartialEq, Clone)]
+pub struct Suggestion {
+    /// With the current source line as `raw`
+    pub entry: Entry,
+    pub category: String,
+    pub reason: Reason,
+    /// e.g. `// TODO@backend: foo` for `// TODO: foo`
+    pub rewritten: String,
+}
+
+/// The shape of `suggest --format json` output
+#[derive(Serialize, JsonSchema)]
+pub struct SuggestReport {
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Suggest categories for the entries that don't have one written in the code: the generic
+/// ones, and the ones categorized by `[categories] paths`. Path rules take precedence over
+/// similar entries. The files are relative to `root`.
+pub fn suggest(entries: &[Entry], categories: &Categories, matcher: &Matcher, root: &Path) -> Vec<Suggestion> {
+    let path_category = |entry: &Entry| {
+        categories.path_rule(&relative(&entry.location.file, root))
+            .map(|(pattern, category)| (pattern.to_string(), category.to_string()))
+    };
+
+    // Entries categorized by their path aren't examples of what the category is about
+    let examples: Vec<(&str, BTreeSet<String>, &Entry)> = entries.iter()
+        .filter_map(|entry| match &entry.data {
+            EntryData::Category(category) if path_category(entry).is_none_or(|(_, path)| path != *category) => Some((category.as_str(), words(&entry.text), entry)),
+            _ => None,
+        })
+        .collect();
+
+    let mut lines: HashMap<Arc<Path>, Option<Vec<String>>> = HashMap::new();
+    let mut suggestions: Vec<Suggestion> = vec![];
+
+    for entry in entries {
+        let from_path = path_category(entry);
+
+        let uncategorized = match &entry.data {
+            EntryData::Generic => true,
+            EntryData::Category(category) => from_path.as_ref().is_some_and(|(_, path)| path == category),
+            _ => false,
+        };
+
+        if ! uncategorized {
+            continue;
+        }
+
+        let file_lines = lines.entry(entry.location.file.clone())
+            .or_insert_with_key(|file| read_text(file).ok().map(|(content, _)| content.lines().map(String::from).collect()));
+
+        let Some(line) = entry.location.line.checked_sub(1).and_then(|index| file_lines.as_ref()?.get(index)) else {
+            continue;
+        };
+
+        let (category, reason) = match from_path {
+            Some((pattern, category)) => (category, Reason::Path { pattern }),
+            None => {
+                let text = words(&entry.text);
+                let best = examples.iter()
+                    .filter(|(_, _, example)| ! std::ptr::eq(*example, entry))
+                    .map(|(category, words, example)| (category, example, similarity(&text, words)))
+                    .filter(|(_, _, similarity)| *similarity >= MIN_SIMILARITY)
+                    .max_by(|a, b| a.2.total_cmp(&b.2).then_with(|| b.0.cmp(a.0)));
+
+                let Some((category, example, _)) = best else {
+                    continue;
+                };
+
+                (category.to_string(), Reason::Similar { text: example.text.clone() })
+            },
+        };
+
+        let Some(rewritten) = rewrite(line, matcher, &category) else {
+            continue;
+        };
+
+        suggestions.push(Suggestion {
+            entry: Entry { raw: Some(line.clone()), ..entry.clone() },
+            category,
+            reason,
+            rewritten,
+        });
+    }
+
+    suggestions
+}
+
+/// The lowercase words of `text` that are at least 3 characters long
+fn words(text: &str) -> BTreeSet<String> {
+    text.split(|char: char| ! char.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The Jaccard index of two sets of words
+pub(crate) fn similarity(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+
+    if union == 0 {
+        return 0.0;
+    }
+
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// `line` with `@category` added to its plain keyword, e.g. `// TODO(alice): foo` becomes
+/// `// TODO@backend(alice): foo`. `None` if the keyword already has a category or priority.
+fn rewrite(line: &str, matcher: &Matcher, category: &str) -> Option<String> {
+    let (word, keyword_len) = matcher.words(line).find(|(word, keyword_len)| {
+        let suffix = &word[*keyword_len..];
+
+        ! word[..*keyword_len].ends_with('(') && (suffix.is_empty() || suffix.starts_with([':', '：', '(']))
+    })?;
+
+    // `word` is a slice of `line`
+    let end = word.as_ptr() as usize - line.as_ptr() as usize + keyword_len;
+
+    Some(format!("{}@{}{}", &line[..end], category, &line[end..]))
+}
+
+/// Rewrite the source lines of the suggestions, skipping the ones that changed since the
+/// scan. Returns the number of rewritten lines.
+pub fn apply(suggestions: &[Suggestion]) -> io::Result<usize> {
+    let mut by_file: HashMap<&Path, Vec<&Suggestion>> = HashMap::new();
+
+    for suggestion in suggestions {
+        by_file.entry(&suggestion.entry.location.file).or_default().push(suggestion);
+    }
+
+    let mut count = 0;
+
+    for (file, suggestions) in by_file {
+        let content = fs::read_to_string(file)?;
+        let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+        let mut changed = false;
+
+        for suggestion in suggestions {
+            let Some(line) = suggestion.entry.location.line.checked_sub(1).and_then(|index| lines.get_mut(index)) else {
+                continue;
+            };
+
+            let (text, ending) = line.split_at(line.trim_end_matches(['\n', '\r']).len());
+
+            if Some(text) != suggestion.entry.raw.as_deref() {
+                continue;
+            }
+
+            *line = format!("{}{}", suggestion.rewritten, ending);
+            changed = true;
+            count += 1;
+        }
+
+        if changed {
+            fs::write(file, lines.concat())?;
+        }
+    }
+
+    Ok(count)
+}
+
+pub fn render_suggestions(suggestions: &[Suggestion], config: &Config) {
+    let mut stdout = BufferedStandardStream::stdout(ColorChoice::Auto);
+
+    write_ansi(&mut stdout, Color::Yellow, "# Suggested categories", true);
+    write!(stdout, "\n\n").unwrap();
+
+    for suggestion in suggestions {
+        let reason = match &suggestion.reason {
+            Reason::Path { pattern } => format!("path {}", pattern),
+            Reason::Similar { text } => format!("like \"{}\"", text),
+        };
+
+        write_ansi(&mut stdout, Color::Ansi256(243), "- ", false);
+        write_ansi(&mut stdout, Color::Green, &format!("@{}", suggestion.category), true);
+        write!(stdout, " {}", suggestion.entry.text).unwrap();
+        write_ansi(&mut stdout, Color::Ansi256(243), &format!(" ({}, {})", suggestion.entry.rendered_location(&config.locations), reason), false);
+        writeln!(stdout).unwrap();
+
+        write_ansi(&mut stdout, Color::Red, &format!("  - {}", suggestion.entry.raw.as_deref().unwrap_or_default().trim()), false);
+        writeln!(stdout).unwrap();
+        write_ansi(&mut stdout, Color::Green, &format!("  + {}", suggestion.rewritten.trim()), false);
+        writeln!(stdout).unwrap();
+    }
+
+    stdout.flush().unwrap();
+}
+
+pub fn render_suggestions_json(suggestions: Vec<Suggestion>) {
+    print_json(SuggestReport { suggestions });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn rewrite_test() {
+        let matcher = Matcher::todo();
+
+        assert_eq!(Some("// TODO@api: foo".to_string()), rewrite("// TODO: foo", matcher, "api"));
+        assert_eq!(Some("# todo@api foo".to_string()), rewrite("# todo foo", matcher, "api"));
+        assert_eq!(Some("// TODO@api(alice): foo".to_string()), rewrite("// TODO(alice): foo", matcher, "api"));
+        assert_eq!(Some("// FIXME@api foo".to_string()), rewrite("// FIXME foo", matcher, "api"));
+        assert_eq!(None, rewrite("// todo0 foo", matcher, "api"));
+        assert_eq!(None, rewrite("// todo@ui foo", matcher, "api"));
+        assert_eq!(None, rewrite("todo!(\"foo\")", matcher, "api"));
+    }
+
+    #[test]
+    fn suggest_test() {
+        let dir = TempDir::new("suggest");
+        fs::create_dir_all(dir.join("src").join("api")).unwrap();
+
+        let content = "// todo@cache invalidate the user cache on logout\n// TODO: invalidate the session cache\n// todo unrelated\r\n";
+        fs::write(dir.join("src").join("auth.rs"), content).unwrap();
+        fs::write(dir.join("src").join("api").join("users.rs"), "// todo paginate\n").unwrap();
+
+        let categories = Categories {
+            paths: BTreeMap::from([("src/api".to_string(), "backend".to_string())]),
+            ..Categories::default()
+        };
+
+        let mut entries: Vec<Entry> = vec![];
+
+        for file in [dir.join("src").join("auth.rs"), dir.join("src").join("api").join("users.rs")] {
+            crate::scan::scan_string(fs::read_to_string(&file).unwrap(), PathBuf::from(&file), &mut entries);
+        }
+
+        crate::entries::categorize_by_path(&mut entries, &categories, &dir);
+
+        let suggestions = suggest(&entries, &categories, Matcher::todo(), &dir);
+
+        assert_eq!(vec![
+            ("invalidate the session cache", Reason::Similar { text: "invalidate the user cache on logout".to_string() }, "// TODO@cache: invalidate the session cache"),
+            ("paginate", Reason::Path { pattern: "src/api".to_string() }, "// todo@backend paginate"),
+        ], suggestions.iter().map(|suggestion| (
+            suggestion.entry.text.as_str(),
+            suggestion.reason.clone(),
+            suggestion.rewritten.as_str(),
+        )).collect::<Vec<_>>());
+
+        assert_eq!(2, apply(&suggestions).unwrap());
+        assert_eq!(
+            "// todo@cache invalidate the user cache on logout\n// TODO@cache: invalidate the session cache\n// todo unrelated\r\n",
+            fs::read_to_string(dir.join("src").join("auth.rs")).unwrap(),
+        );
+        assert_eq!("// todo@backend paginate\n", fs::read_to_string(dir.join("src").join("api").join("users.rs")).unwrap());
+
+        // Lines that changed since the scan are skipped
+        assert_eq!(0, apply(&suggestions).unwrap());
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::entries::{Entry, EntryData};
+use crate::render::{fit, priority_notation};
+
+const FIELDS: [&str; 10] = ["file", "path", "line", "text", "kind", "keyword", "category", "priority", "assignee", "raw"];
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    /// `{a|b}`: the first of the fields that isn't empty, optionally fitted to a width (`{a|b:20}`)
+    Fields(Vec<String>, Option<usize>),
+}
+
+/// A per-entry output format, e.g. `{file}:{line} [{category|priority}] {text}`.
+///
+/// `{file}` is relative to the project root and `{path}` is absolute. `{keyword}` is e.g. `fixme`,
+/// or `todo` for entries found using `todo`. `{category}` and
+/// `{priority}` (e.g. `todo0`) are empty for other kinds of entries, `{assignee}` for unassigned ones,
+/// and `{raw}` (the unmodified line) without `--raw`. `{text:40}` pads or
+/// truncates the value to 40 terminal columns. `{{` and `}}` are literal braces.
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, String> {
+        let mut segments: Vec<Segment> = vec![];
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(char) = chars.next() {
+            match char {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                },
+                '{' => {
+                    let mut placeholder = String::new();
+
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(char) => placeholder.push(char),
+                            None => return Err(format!("unclosed placeholder {{{}", placeholder)),
+                        }
+                    }
+
+                    let width = match placeholder.rsplit_once(':') {
+                        Some((fields, width)) => {
+                            let width = width.trim().parse::<usize>().map_err(|_| format!("invalid width in {{{}}}", placeholder))?;
+                            placeholder = fields.to_string();
+
+                            Some(width)
+                        },
+                        None => None,
+                    };
+
+                    let fields: Vec<String> = placeholder.split('|').map(|field| field.trim().to_string()).collect();
+
+                    if let Some(field) = fields.iter().find(|field| ! FIELDS.contains(&field.as_str())) {
+                        return Err(format!("unknown field {{{}}}, expected one of: {}", field, FIELDS.join(", ")));
+                    }
+
+                    if ! literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+
+                    segments.push(Segment::Fields(fields, width));
+                },
+                '}' => return Err("unmatched }, use }} for a literal brace".to_string()),
+                char => literal.push(char),
+            }
+        }
+
+        if ! literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Template { segments })
+    }
+
+    pub fn render(&self, entry: &Entry, root: &Path) -> String {
+        let field = |name: &str| match name {
+            "file" => entry.relative_path(root),
+            "path" => entry.location.file.to_string_lossy().to_string(),
+            "line" => entry.location.line.to_string(),
+            "text" => entry.text.clone(),
+            "kind" => match entry.data {
+                EntryData::Priority(_) => "priority",
+                EntryData::Category(_) => "category",
+                EntryData::Generic => "generic",
+                EntryData::Someday => "someday",
+                EntryData::Unimplemented => "unimplemented",
+                EntryData::Marker => "marker",
+            }.to_string(),
+            "keyword" => entry.keyword.clone().unwrap_or("todo".to_string()),
+            "category" => match &entry.data {
+                EntryData::Category(category) => category.clone(),
+                _ => String::new(),
+            },
+            "priority" => match entry.data {
+                EntryData::Priority(priority) => priority_notation(priority),
+                _ => String::new(),
+            },
+            "assignee" => entry.assignee.clone().unwrap_or_default(),
+            "raw" => entry.raw.clone().unwrap_or_default(),
+            _ => unreachable!(),
+        };
+
+        self.segments.iter().map(|segment| match segment {
+            Segment::Literal(literal) => literal.clone(),
+            Segment::Fields(fields, width) => {
+                let value = fields.iter().map(|name| field(name)).find(|value| ! value.is_empty()).unwrap_or_default();
+
+                match width {
+                    Some(width) => fit(&value, *width),
+                    None => value,
+                }
+            },
+        }).collect()
+    }
+}
+
+/// Print each entry on its own line, formatted using `template`
+pub fn render_template(entries: &[Entry], template: &Template, root: &Path) {
+    let mut stdout = BufWriter::new(io::stdout().lock());
+
+    for entry in entries {
+        writeln!(stdout, "{}", template.render(entry, root)).unwrap();
+    }
+
+    stdout.flush().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::entry;
+
+    #[test]
+    fn template_test() {
+        let template = Template::parse("{file}:{line} [{category|priority}] {text} {{{kind}}}").unwrap();
+
+        assert_eq!("src/main.rs:12 [foo] bar {category}", template.render(&entry("/repo/src/main.rs", 12, EntryData::Category("foo".to_string()), "bar"), Path::new("/repo")));
+        assert_eq!("src/main.rs:12 [todo00] bar {priority}", template.render(&entry("/repo/src/main.rs", 12, EntryData::Priority(-1), "bar"), Path::new("/repo")));
+        assert_eq!("src/main.rs:12 [] bar {generic}", template.render(&entry("/repo/src/main.rs", 12, EntryData::Generic, "bar"), Path::new("/repo")));
+
+        let template = Template::parse("{category:4}|{text:6}|").unwrap();
+        assert_eq!("foo |日本… |", template.render(&entry("/repo/src/main.rs", 12, EntryData::Category("foo".to_string()), "日本語です"), Path::new("/repo")));
+
+        assert!(Template::parse("{foo}").is_err());
+        assert!(Template::parse("{text:x}").is_err());
+        assert!(Template::parse("{file").is_err());
+        assert!(Template::parse("file}").is_err());
+    }
+}
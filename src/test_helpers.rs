@@ -0,0 +1,45 @@
+//! Fixtures shared by the unit tests
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use crate::entries::{Entry, EntryData, Location};
+
+/// An entry at `file:line`, with the other fields left at their defaults
+pub fn entry(file: &str, line: usize, data: EntryData, text: &str) -> Entry {
+    Entry::new(text.to_string(), Location { file: PathBuf::from(file).into(), line }, data)
+}
+
+/// A directory for the files a test scans, e.g. `todos-bump-test-123` in the system's temp
+/// directory. It's removed when dropped, so also when an assertion fails.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("todos-{}-test-{}", name, std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+
+        TempDir(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use termcolor::{Color, ColorChoice, StandardStream};
+
+use crate::config::Config;
+use crate::entries::{Entry, EntryData};
+use crate::render::{priority_notation, write_ansi};
+use crate::schema::versioned_json;
+
+/// A section with more entries than its configured threshold
+#[derive(Serialize, JsonSchema, Debug, PartialEq, Clone)]
+pub struct Exceeded {
+    pub section: String,
+    pub count: usize,
+    pub threshold: usize,
+}
+
+/// The number of entries in a section, by the same names as in `[sections]`, or all entries
+/// except `todo?` ones with `total`
+fn count(entries: &[Entry], section: &str) -> usize {
+    entries.iter().filter(|entry| match (section, &entry.data) {
+        ("total", data) => ! matches!(data, EntryData::Someday),
+        ("priorities", EntryData::Priority(_)) => true,
+        ("categories", EntryData::Category(_)) => true,
+        ("other", EntryData::Generic) => true,
+        ("someday", EntryData::Someday) => true,
+        ("unimplemented", EntryData::Unimplemented) => true,
+        ("markers", EntryData::Marker) => true,
+        (section, EntryData::Priority(priority)) => section == priority_notation(*priority),
+        (section, EntryData::Category(category)) => section == category,
+        _ => false,
+    }).count()
+}
+
+/// The configured thresholds that are exceeded, sorted by section. Informational entries aren't counted.
+pub fn exceeded(entries: &[Entry], config: &Config) -> Vec<Exceeded> {
+    let entries: Vec<Entry> = entries.iter().filter(|entry| ! entry.informational).cloned().collect();
+
+    let mut exceeded: Vec<Exceeded> = config.thresholds.iter()
+        .map(|(section, threshold)| Exceeded { section: section.clone(), count: count(&entries, section), threshold: *threshold })
+        .filter(|exceeded| exceeded.count > exceeded.threshold)
+        .collect();
+
+    exceeded.sort_by(|a, b| a.section.cmp(&b.section));
+
+    exceeded
+}
+
+/// The count with the right form of "TODO", e.g. `1 TODO` or `2 TODOs`, and the section
+/// before it if there is one, e.g. `2 todo0 TODOs`
+fn todos(count: usize, section: Option<&str>) -> String {
+    let noun = if count == 1 { "TODO" } else { "TODOs" };
+
+    match section {
+        Some(section) => format!("{} {} {}", count, section, noun),
+        None => format!("{} {}", count, noun),
+    }
+}
+
+/// Print a warning for each exceeded threshold to stderr. This never fails the command.
+pub fn render_warnings(exceeded: &[Exceeded]) {
+    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+
+    for exceeded in exceeded {
+        let section = (exceeded.section != "total").then_some(exceeded.section.as_str());
+
+        write_ansi(&mut stderr, Color::Yellow, &format!("⚠ {}, threshold is {}", todos(exceeded.count, section), exceeded.threshold), true);
+        writeln!(stderr).unwrap();
+    }
+}
+
+/// A `--fail-on` condition, e.g. `priority<=0`, `count>50`, or `category=security`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailOn {
+    field: String,
+    operator: String,
+    value: String,
+}
+
+const FIELDS: [&str; 5] = ["count", "priority", "category", "keyword", "kind"];
+
+impl FromStr for FailOn {
+    type Err = String;
+
+    fn from_str(condition: &str) -> Result<FailOn, String> {
+        let start = condition.find(['<', '>', '=', '!'])
+            .ok_or_else(|| format!("expected a condition like priority<=0, count>50, or category=security, got {}", condition))?;
+        let end = start + condition[start..].find(|char: char| ! "<>=!".contains(char)).unwrap_or(condition.len() - start);
+
+        let fail_on = FailOn {
+            field: condition[..start].trim().to_string(),
+            operator: condition[start..end].to_string(),
+            value: condition[end..].trim().to_string(),
+        };
+
+        if ! FIELDS.contains(&fail_on.field.as_str()) {
+            return Err(format!("unknown field {}, expected one of {}", fail_on.field, FIELDS.join(", ")));
+        }
+
+        let numeric = matches!(fail_on.field.as_str(), "count" | "priority");
+        let operators: &[&str] = if numeric { &["<", "<=", ">", ">=", "=", "!="] } else { &["=", "!="] };
+
+        if ! operators.contains(&fail_on.operator.as_str()) {
+            return Err(format!("invalid operator {} for {}, expected one of {}", fail_on.operator, fail_on.field, operators.join(" ")));
+        }
+
+        if numeric && fail_on.number().is_none() {
+            return Err(format!("invalid {} {}, expected a number", fail_on.field, fail_on.value));
+        }
+
+        Ok(fail_on)
+    }
+}
+
+impl FailOn {
+    /// The value as a number. Priorities can also be written like in the code, e.g. `todo00` for -1.
+    fn number(&self) -> Option<isize> {
+        let Some(notation) = self.value.strip_prefix("todo").filter(|_| self.field == "priority") else {
+            return self.value.parse().ok();
+        };
+
+        match notation.len() {
+            1 => notation.parse().ok(),
+            len => notation.chars().all(|char| char == '0').then(|| 1 - len as isize),
+        }
+    }
+
+    fn compare<T: PartialOrd>(&self, actual: T, expected: T) -> bool {
+        match self.operator.as_str() {
+            "<" => actual < expected,
+            "<=" => actual <= expected,
+            ">" => actual > expected,
+            ">=" => actual >= expected,
+            "=" => actual == expected,
+            _ => actual != expected,
+        }
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        match (self.field.as_str(), &entry.data) {
+            ("priority", EntryData::Priority(priority)) => self.compare(*priority, self.number().unwrap()),
+            // `todo!()` panics when it's reached, so it's as urgent as `todo0`
+            ("priority", EntryData::Unimplemented) => self.compare(0, self.number().unwrap()),
+            ("category", EntryData::Category(category)) => self.compare(category.as_str(), self.value.as_str()),
+            ("keyword", _) => self.compare(entry.keyword.as_deref().unwrap_or("todo"), self.value.to_lowercase().as_str()),
+            ("kind", _) => self.compare(entry.data.key().split(':').next().unwrap(), self.value.as_str()),
+            _ => false,
+        }
+    }
+
+    /// Why the condition fails the command, e.g. `3 TODOs match priority<=0`, or `None` if it doesn't.
+    /// Informational and `todo?` entries never fail it.
+    pub fn failure(&self, entries: &[Entry]) -> Option<String> {
+        let entries: Vec<&Entry> = entries.iter()
+            .filter(|entry| ! entry.informational && ! matches!(entry.data, EntryData::Someday))
+            .collect();
+
+        if self.field == "count" {
+            return self.compare(entries.len() as isize, self.number().unwrap())
+                .then(|| format!("{}, failing on {}", todos(entries.len(), None), self));
+        }
+
+        let count = entries.iter().filter(|entry| self.matches(entry)).count();
+        let verb = if count == 1 { "matches" } else { "match" };
+
+        (count > 0).then(|| format!("{} {} {}", todos(count, None), verb, self))
+    }
+}
+
+impl std::fmt::Display for FailOn {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.field, self.operator, self.value)
+    }
+}
+
+/// Print the failed `--fail-on` conditions to stderr
+pub fn render_failures(failures: &[String]) {
+    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+
+    for failure in failures {
+        write_ansi(&mut stderr, Color::Red, &format!("✗ {}", failure), true);
+        writeln!(stderr).unwrap();
+    }
+}
+
+/// The shape of the `--summary-file` JSON, for CI wrappers that don't parse the report
+#[derive(Serialize, JsonSchema, Debug, PartialEq)]
+pub struct Summary {
+    /// All entries except `todo?` ones, including the informational ones
+    pub total: usize,
+    /// Entries by kind, e.g. `priority` or `category`
+    pub kinds: BTreeMap<String, usize>,
+    /// Priority entries by their notation, e.g. `todo0`
+    pub priorities: BTreeMap<String, usize>,
+    pub informational: usize,
+    /// The exceeded `[thresholds]`, which only print warnings
+    pub exceeded: Vec<Exceeded>,
+    /// The matched `--fail-on` conditions, e.g. `2 TODOs match priority<=0`
+    pub failures: Vec<String>,
+    /// Whether the command exits successfully, i.e. no `--fail-on` condition matched
+    pub passed: bool,
+    /// How long the command took, in milliseconds
+    pub duration_ms: u64,
+}
+
+impl Summary {
+    /// The summary of a command that took `duration` so far
+    pub fn new(entries: &[Entry], exceeded: Vec<Exceeded>, failures: Vec<String>, duration: Duration) -> Summary {
+        let mut kinds: BTreeMap<String, usize> = BTreeMap::new();
+        let mut priorities: BTreeMap<String, usize> = BTreeMap::new();
+
+        for entry in entries {
+            *kinds.entry(entry.data.key().split(':').next().unwrap().to_string()).or_default() += 1;
+
+            if let EntryData::Priority(priority) = entry.data {
+                *priorities.entry(priority_notation(priority)).or_default() += 1;
+            }
+        }
+
+        Summary {
+            total: entries.iter().filter(|entry| ! matches!(entry.data, EntryData::Someday)).count(),
+            kinds,
+            priorities,
+            informational: entries.iter().filter(|entry| entry.informational).count(),
+            exceeded,
+            passed: failures.is_empty(),
+            failures,
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+
+    /// Write the summary as JSON to `path`, e.g. `/dev/fd/3`
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, format!("{}\n", versioned_json(self)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use crate::scan::scan_string;
+
+    #[test]
+    fn exceeded_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo0 a\n// todo0 b\n// todo00 c\n// todo@security d\n// todo e\n// todo? f\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        let mut config = Config::default();
+        config.thresholds.insert("todo0".to_string(), 1);
+        config.thresholds.insert("todo00".to_string(), 1);
+        config.thresholds.insert("priorities".to_string(), 2);
+        config.thresholds.insert("security".to_string(), 0);
+        config.thresholds.insert("total".to_string(), 5);
+        config.thresholds.insert("someday".to_string(), 0);
+
+        assert_eq!(vec![
+            Exceeded { section: "priorities".to_string(), count: 3, threshold: 2 },
+            Exceeded { section: "security".to_string(), count: 1, threshold: 0 },
+            Exceeded { section: "someday".to_string(), count: 1, threshold: 0 },
+            Exceeded { section: "todo0".to_string(), count: 2, threshold: 1 },
+        ], exceeded(&entries, &config));
+    }
+
+    #[test]
+    fn fail_on_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo0 a\n// todo00 b\n// todo2 c\n// todo@security d\n// FIXME e\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        let failure = |condition: &str| condition.parse::<FailOn>().unwrap().failure(&entries);
+
+        assert_eq!(Some("2 TODOs match priority<=0".to_string()), failure("priority<=0"));
+        assert_eq!(Some("1 TODO matches priority>=2".to_string()), failure("priority>=2"));
+        assert_eq!(Some("1 TODO matches priority<todo0".to_string()), failure("priority<todo0"));
+        assert_eq!(Some("1 TODO matches category=security".to_string()), failure("category=security"));
+        assert_eq!(Some("1 TODO matches keyword=FIXME".to_string()), failure("keyword=FIXME"));
+        assert_eq!(Some("5 TODOs, failing on count>4".to_string()), failure("count>4"));
+        assert_eq!(None, failure("count>50"));
+        assert_eq!(None, failure("category=ui"));
+        assert_eq!(None, failure("kind=unimplemented"));
+
+        assert!("priority".parse::<FailOn>().is_err());
+        assert!("size>2".parse::<FailOn>().is_err());
+        assert!("category>a".parse::<FailOn>().is_err());
+        assert!("count>many".parse::<FailOn>().is_err());
+    }
+
+    #[test]
+    fn unimplemented_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("fn foo() {\n    todo!()\n}\n// todo1 bar\n".to_string(), PathBuf::from("foo.rs"), &mut entries);
+
+        let failure = |condition: &str| condition.parse::<FailOn>().unwrap().failure(&entries);
+
+        assert_eq!(Some("1 TODO matches kind=unimplemented".to_string()), failure("kind=unimplemented"));
+        assert_eq!(Some("1 TODO matches priority<=0".to_string()), failure("priority<=0"));
+        assert_eq!(Some("2 TODOs match priority<=1".to_string()), failure("priority<=1"));
+        assert_eq!(None, failure("priority<0"));
+    }
+
+    #[test]
+    fn informational_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo0 a\n// todo0 b\n".to_string(), PathBuf::from("examples/foo.txt"), &mut entries);
+        scan_string("// todo0 c\n".to_string(), PathBuf::from("src/foo.txt"), &mut entries);
+
+        let config = Config::parse("[severity]\ninformational = [\"examples/**\"]\n\n[thresholds]\ntodo0 = 1\n").unwrap();
+        crate::entries::mark_informational(&mut entries, &config.severity, Path::new(""));
+
+        assert_eq!(vec![true, true, false], entries.iter().map(|entry| entry.informational).collect::<Vec<_>>());
+        assert_eq!(Vec::<Exceeded>::new(), exceeded(&entries, &config));
+        assert_eq!(Some("1 TODO matches priority<=0".to_string()), "priority<=0".parse::<FailOn>().unwrap().failure(&entries));
+        assert_eq!(None, "count>1".parse::<FailOn>().unwrap().failure(&entries));
+    }
+
+    #[test]
+    fn someday_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo? a\n// todo? b\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
+
+        assert_eq!(None, "count>0".parse::<FailOn>().unwrap().failure(&entries));
+        assert_eq!(None, "kind=someday".parse::<FailOn>().unwrap().failure(&entries));
+
+        scan_string("// todo c\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
+        assert_eq!(Some("1 TODO, failing on count>0".to_string()), "count>0".parse::<FailOn>().unwrap().failure(&entries));
+    }
+
+    #[test]
+    fn summary_test() {
+        let mut entries: Vec<Entry> = vec![];
+        scan_string("// todo0 a\n// todo0 b\n// todo00 c\n// todo@security d\n// todo? e\n".to_string(), PathBuf::from("foo.txt"), &mut entries);
+        entries[3].informational = true;
+
+        let summary = Summary::new(&entries, vec![], vec!["2 TODOs match priority<=0".to_string()], Duration::from_millis(1500));
+
+        assert_eq!(4, summary.total);
+        assert_eq!(BTreeMap::from([("category".to_string(), 1), ("priority".to_string(), 3), ("someday".to_string(), 1)]), summary.kinds);
+        assert_eq!(BTreeMap::from([("todo0".to_string(), 2), ("todo00".to_string(), 1)]), summary.priorities);
+        assert_eq!(1, summary.informational);
+        assert!(! summary.passed);
+        assert_eq!(1500, summary.duration_ms);
+    }
+}
@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/archtechx/todo-system/releases/latest";
+
+/// Name of the release asset built for the current platform, e.g. `todos-linux-x86_64`
+fn asset_name() -> String {
+    format!("todos-{}-{}{}", std::env::consts::OS, std::env::consts::ARCH, std::env::consts::EXE_SUFFIX)
+}
+
+/// Compare two `major.minor.patch` versions, ignoring any `v` prefix
+fn is_newer(latest: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version.trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse::<u64>().unwrap_or(0))
+            .collect()
+    };
+
+    parse(latest) > parse(current)
+}
+
+fn download(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut bytes: Vec<u8> = vec![];
+
+    ureq::get(url)
+        .set("User-Agent", "todos")
+        .call()?
+        .into_reader()
+        .read_to_end(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Replace the running executable with `binary`
+fn replace_executable(binary: &[u8]) -> Result<PathBuf, Box<dyn Error>> {
+    let current = std::env::current_exe()?.canonicalize()?;
+    let new = current.with_extension("new");
+    let old = current.with_extension("old");
+
+    fs::write(&new, binary)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(&new, fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Windows doesn't allow overwriting a running executable, but it does allow renaming it
+    fs::rename(&current, &old)?;
+
+    if let Err(err) = fs::rename(&new, &current) {
+        fs::rename(&old, &current)?;
+
+        return Err(err.into());
+    }
+
+    // This fails on Windows while the old binary is still running, leaving it to be removed on the next update
+    let _ = fs::remove_file(&old);
+
+    Ok(current)
+}
+
+/// Update the running binary to the latest GitHub release
+pub fn self_update(check_only: bool) -> Result<(), Box<dyn Error>> {
+    let release: Value = ureq::get(RELEASES_URL)
+        .set("User-Agent", "todos")
+        .set("Accept", "application/vnd.github+json")
+        .call()?
+        .into_json()?;
+
+    let current_version = env!("CARGO_PKG_VERSION");
+    let latest_version = release["tag_name"].as_str().ok_or("Release has no tag")?;
+
+    if ! is_newer(latest_version, current_version) {
+        println!("Already up to date (v{})", current_version);
+
+        return Ok(());
+    }
+
+    println!("New version available: v{} -> {}", current_version, latest_version);
+
+    if check_only {
+        return Ok(());
+    }
+
+    let assets = release["assets"].as_array().ok_or("Release has no assets")?;
+    let asset_url = |name: &str| -> Option<String> {
+        assets.iter()
+            .find(|asset| asset["name"].as_str() == Some(name))
+            .and_then(|asset| asset["browser_download_url"].as_str())
+            .map(|url| url.to_string())
+    };
+
+    let name = asset_name();
+    let binary_url = asset_url(&name).ok_or(format!("No release binary for this platform ({})", name))?;
+    let checksum_url = asset_url(&format!("{}.sha256", name)).ok_or(format!("No checksum for {}", name))?;
+
+    let binary = download(&binary_url)?;
+    let checksum_file = String::from_utf8(download(&checksum_url)?)?;
+
+    // Accepts both a bare hash and `sha256sum` output (`<hash>  <file>`)
+    let expected_checksum = checksum_file.split_whitespace().next().ok_or("Empty checksum file")?.to_lowercase();
+
+    if sha256_hex(&binary) != expected_checksum {
+        return Err(format!("Checksum mismatch for {}", name).into());
+    }
+
+    let path = replace_executable(&binary)?;
+
+    println!("Updated {} to {}", path.to_string_lossy(), latest_version);
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use wasm_bindgen::prelude::*;
+
+use crate::entries::Entry;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &str = r#"
+export interface Location {
+    file: string;
+    line: number;
+}
+
+export type EntryData =
+    | { kind: "priority"; value: number }
+    | { kind: "category"; value: string }
+    | { kind: "generic" }
+    | { kind: "someday" }
+    | { kind: "unimplemented" }
+    | { kind: "marker" };
+
+export interface Entry {
+    text: string;
+    location: Location;
+    data: EntryData;
+    source: "code" | "todo-file" | "readme";
+    assignee?: string;
+    keyword?: string;
+    also?: Location[];
+    raw?: string;
+    stale?: boolean;
+    informational?: boolean;
+    url?: string;
+}
+"#;
+
+/// Scan a string for TODOs. `filename` is only used for the entries' locations.
+#[wasm_bindgen(js_name = scanString, unchecked_return_type = "Entry[]")]
+pub fn scan_string(str: String, filename: String) -> Result<JsValue, JsValue> {
+    let mut entries: Vec<Entry> = vec![];
+
+    crate::scan::scan_string(str, PathBuf::from(filename), &mut entries);
+
+    Ok(serde_wasm_bindgen::to_value(&entries)?)
+}